@@ -10,11 +10,23 @@ class port(str, Enum):
     Integer = "Integer"
     Real = "Real"
     Complex = "Complex"
+    ArrayInteger = "ArrayInteger"
     ArrayComplex = "ArrayComplex"
     ArrayReal = "ArrayReal"
     Dynamic = "Dynamic"
     # Object = "Object" just nest dictionary definititions!
 
+    def ndim(self, rank: int) -> str:
+        """Array with a fixed number of dimensions, `port.ArrayComplex.ndim(2)`"""
+        return f"{self.value}[{rank}]"
+
+    def shape(self, *dims) -> str:
+        """Array with a fixed shape, `port.ArrayReal.shape("N", 3)`.
+        Named dimensions can have any length, but the same name must have the same length"""
+        if len(dims) == 1:
+            return f"{self.value}[{dims[0]},]"
+        return f"{self.value}[{','.join(str(d) for d in dims)}]"
+
 
 class ui:
     Slider = "Slider"
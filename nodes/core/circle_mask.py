@@ -5,7 +5,7 @@ from foray import port, ui
 def config():
     class out:
         inputs = {}
-        outputs = {"out": port.ArrayReal}
+        outputs = {"out": port.ArrayReal.ndim(2)}
         parameters = {"radius": ui.Slider}
 
     return out
@@ -1,12 +1,22 @@
 use crate::config::Config;
 use crate::file_watch::file_watch_subscription;
 use crate::graph::{Graph, PortRef, IO};
+use crate::gui_node::GUINode;
 use crate::interface::add_node::add_node_tree_panel;
+use crate::interface::command_palette::{
+    command_palette, palette_input_id, palette_items, search, PaletteItem,
+};
+use crate::interface::dashboard::{dashboard_view, viewer_window, DashboardPart};
+use crate::interface::inspector::{inspector_panel, Inspector};
 use crate::interface::node_config::NodeUIWidget;
 use crate::interface::theme_config::{AppThemeMessage, GuiColorMessage};
+use crate::interface::wire::PULSE_DURATION;
 use crate::interface::{side_bar::side_bar, SEPERATOR};
+use crate::keymap::Keymap;
 use crate::math::{Point, Vector};
-use crate::network::Network;
+use crate::network::align::{Align, GRID_SIZE, SNAP_DISTANCE};
+use crate::network::{compatible_port, Network};
+use crate::nodes::figure::ExportSize;
 use crate::nodes::port::PortData;
 use crate::nodes::status::{NodeError, NodeStatus};
 use crate::nodes::{NodeData, NodeTemplate, RustNode};
@@ -14,6 +24,7 @@ use crate::project::Project;
 use crate::python::py_node::PyNode;
 use crate::style::theme::AppTheme;
 use crate::user_data::UserData;
+use crate::widget::annotation::{AnnotationColor, AnnotationId, AnnotationPart};
 use crate::widget::shapes::ShapeId;
 use crate::widget::workspace::workspace;
 use crate::StableMap;
@@ -26,15 +37,20 @@ use iced::keyboard::{self, Key, Modifiers};
 use iced::widget::{column, *};
 use iced::Event::Keyboard;
 use iced::Length::Fill;
-use iced::{mouse, window, Subscription, Task};
+use iced::{clipboard, event, mouse, window, Subscription, Task};
 use itertools::Itertools;
-use log::{debug, error, info, trace, warn};
+use log::{error, info, trace, warn};
 use rfd::FileDialog;
 use std::fs::read_to_string;
 use std::iter::once;
 use std::mem::discriminant;
 use std::time::{Duration, Instant};
 
+/// Distance on screen, within which clicks select a wire
+const WIRE_HIT_DISTANCE: f32 = 5.;
+/// Height of the wire inspector panel, below the workspace
+const INSPECTOR_HEIGHT: f32 = 260.;
+
 #[derive(Default, Clone, PartialEq)]
 pub enum Action {
     #[default]
@@ -42,9 +58,25 @@ pub enum Action {
     Idle,
     DragPan(Vector),
     DragNode(Vec<(u32, Vector)>),
+    /// Annotations and the nodes that move with them, and their offsets from the cursor
+    DragAnnotations {
+        annotations: Vec<(AnnotationId, Vector)>,
+        nodes: Vec<(u32, Vector)>,
+    },
+    ResizeAnnotation(AnnotationId),
+    ResizeNode(u32),
     CreatingInputWire(PortRef, Option<PortRef>),
     CreatingOutputWire(PortRef, Option<PortRef>),
     AddingNode,
+    /// Searching for a node or command, with the index of the highlighted result
+    CommandPalette {
+        query: String,
+        selected: usize,
+        /// A wire released in empty space, and where, that the chosen node is connected to
+        wire: Option<(PortRef, Point)>,
+        /// Results of the search, best first, updated when the query or the node list changes
+        results: Vec<PaletteItem>,
+    },
 }
 
 pub struct App {
@@ -57,15 +89,31 @@ pub struct App {
     pub python_projects: Vec<Project>,
     pub app_theme: AppTheme,
     pub config: Config,
+    pub keymap: Keymap,
 
     /// current editor action
     pub action: Action,
     pub cursor_position: Point,
+    /// Size of the workspace on screen, used to zoom to fit
+    pub workspace_size: iced::Size,
     /// Currently held keyboard modifiers, used for shortcuts
     pub modifiers: Modifiers,
 
     pub debug: bool,
     pub show_palette_ui: bool,
+    pub show_history: bool,
+    /// Wire whose data is shown in the inspector panel
+    pub inspector: Option<Inspector>,
+    /// Text of the selected annotation, while it is being edited
+    pub annotation_editor: text_editor::Content,
+    /// Window with the workspace, closing it exits the application
+    pub main_window: Option<window::Id>,
+    /// Windows that show a single viewer, or the dashboard
+    pub windows: StableMap<window::Id, DetachedWindow>,
+    /// Resolution of exported PNG figures
+    pub export_size: ExportSize,
+    /// Wire under the cursor, whose data is summarized in a tooltip
+    pub hovered_wire: Option<PortRef>,
 }
 impl Default for App {
     fn default() -> Self {
@@ -98,24 +146,45 @@ impl Default for App {
         App {
             network,
             config,
+            keymap: Keymap::read_keymap(),
 
             debug: false,
             show_palette_ui: false,
+            show_history: false,
+            inspector: None,
+            annotation_editor: text_editor::Content::new(),
             cursor_position: Default::default(),
+            workspace_size: iced::Size::ZERO,
             action: Default::default(),
             app_theme,
             modifiers: Default::default(),
             python_projects: projects,
             user_data,
+            main_window: None,
+            windows: StableMap::new(),
+            export_size: ExportSize::default(),
+            hovered_wire: None,
         }
     }
 }
 
+/// Content of a window other than the main window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DetachedWindow {
+    Viewer(ShapeId),
+    Dashboard,
+}
+
 #[derive(Clone, derive_more::Debug)]
 pub enum Message {
     //// Workspace
     OnMove(Point),
     ScrollPan(Vector),
+    Zoom(f32),
+    ZoomReset,
+    ZoomToFit,
+    ZoomToSelection,
+    WorkspaceResized(iced::Size),
 
     //// Port
     PortStartHover(PortRef),
@@ -123,17 +192,61 @@ pub enum Message {
     PortPress(PortRef),
     PortRelease,
     PortDelete(PortRef),
+    /// Show the data carried from an output port in the inspector
+    InspectWire(PortRef),
+    /// Set the index into a leading axis of the inspected array
+    InspectSlice(usize, usize),
+    /// Scroll the inspector table to start at a row
+    InspectRows(usize),
+    CloseInspector,
+
+    //// Windows
+    WindowClosed(window::Id),
+    /// Show a viewer node in its own window
+    PopOut(ShapeId),
+    OpenDashboard,
+    AddToDashboard(ShapeId, DashboardPart),
+    DashboardRemove(usize),
+    /// Move a dashboard item by an offset in the grid
+    DashboardMove(usize, isize),
+    DashboardColumns(usize),
+    /// Save what a viewer shows to a PNG or SVG file
+    Export(ShapeId),
+    ExportResolution(ExportSize),
 
     //// Node
     OnCanvasDown(Option<ShapeId>),
     OnCanvasUp,
+    OnPanPress,
+    /// Pressed the resize handle of a node
+    NodeResizePress(u32),
+    BoxSelect(Vec<ShapeId>),
     OpenAddNodeUi,
     AddNode(NodeTemplate),
     SelectNodeGroup(Vec<String>),
+    OpenCommandPalette,
+    PaletteQuery(String),
+    /// Move the highlighted palette result by an offset
+    PaletteSelect(isize),
+    PaletteSubmit,
+    PaletteChoose(PaletteItem),
 
     UpdateNodeTemplate(u32, NodeTemplate),
     UpdateNodeParameter(u32, String, NodeUIWidget),
     DeleteSelectedNodes,
+    Duplicate,
+    AutoLayout,
+    AnnotationPress(AnnotationId, AnnotationPart),
+    AddNote,
+    AddFrame,
+    EditAnnotationText(text_editor::Action),
+    SetAnnotationColor(AnnotationColor),
+    Align(Align),
+    ToggleGrid,
+    Copy,
+    Cut,
+    Paste,
+    PasteFragment(Option<String>),
 
     QueueCompute(u32),
     ComputeComplete(
@@ -153,6 +266,8 @@ pub enum Message {
     ReloadNodes,
     WindowOpen,
     ModifiersChanged(Modifiers),
+    /// A key press that may be bound to a shortcut in the keymap, and whether a widget captured it
+    KeyPressed(Key, Modifiers, bool),
 
     //// Focus
     FocusNext,
@@ -162,13 +277,64 @@ pub enum Message {
     //// History
     Undo,
     Redo,
+    /// Undo or redo until the given number of history steps are applied
+    JumpToHistory(usize),
+    ToggleHistory,
     //// Misc
     /// Hacky way to have a message that does nothing
     NOP,
 }
 
 impl App {
+    /// Open the main window, other windows are opened on request
+    pub fn new() -> (Self, Task<Message>) {
+        let (id, open) = window::open(window::Settings {
+            size: (1000., 800.).into(),
+            min_size: Some((400., 300.).into()),
+            decorations: true,
+            ..Default::default()
+        });
+        let app = App {
+            main_window: Some(id),
+            ..Default::default()
+        };
+        (app, open.discard())
+    }
+
     pub fn update(&mut self, message: Message) -> Task<Message> {
+        let task = self.handle_message(message);
+        // Record any changes as an undo step, once nothing is being dragged
+        if !matches!(
+            self.action,
+            Action::DragNode(..)
+                | Action::DragAnnotations { .. }
+                | Action::ResizeAnnotation(..)
+                | Action::ResizeNode(..)
+        ) {
+            self.network.commit_edit(self.config.undo_depth());
+        }
+        // Close the inspector once its node is deleted
+        if let Some(inspector) = &self.inspector {
+            if !self
+                .network
+                .shapes
+                .shape_positions
+                .contains_key(&inspector.port.node)
+            {
+                self.inspector = None;
+            }
+        }
+        // Keep the annotation editor in sync with changes such as undo
+        if let Some((_, annotation)) = self.network.edited_annotation() {
+            let editor_text = self.annotation_editor.text();
+            if editor_text.strip_suffix('\n').unwrap_or(&editor_text) != annotation.text {
+                self.annotation_editor = text_editor::Content::with_text(&annotation.text);
+            }
+        }
+        task
+    }
+
+    fn handle_message(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::OnMove(_) => {}
             _ => trace!("---Message--- {:?}", message),
@@ -182,40 +348,85 @@ impl App {
                 // Update node position if currently dragging
                 match &self.action {
                     Action::DragNode(offsets) => {
-                        offsets.iter().for_each(|(id, offset)| {
-                            *self
-                                .network
-                                .shapes
-                                .shape_positions
-                                .get_mut(id)
-                                .expect("Shape index must exist") =
-                                (cursor_position + self.network.shapes.camera.position) + *offset
-                        });
+                        // Hold alt to place nodes freely
+                        let snap = !self.modifiers.alt();
+                        let camera = &self.network.shapes.camera;
+                        let cursor = camera.to_world(cursor_position);
+                        let snap_distance = snap.then_some(SNAP_DISTANCE / camera.zoom);
+                        let grid = (snap && self.user_data.snap_to_grid()).then_some(GRID_SIZE);
+                        self.network
+                            .drag_nodes(offsets, cursor, grid, snap_distance);
+                    }
+                    Action::DragAnnotations { annotations, nodes } => {
+                        let cursor = self.network.shapes.camera.to_world(cursor_position);
+                        self.network.drag_annotations(annotations, cursor);
+                        self.network.drag_nodes(nodes, cursor, None, None);
+                    }
+                    Action::ResizeAnnotation(id) => {
+                        let cursor = self.network.shapes.camera.to_world(cursor_position);
+                        self.network.resize_annotation(*id, cursor);
                     }
-                    Action::DragPan(offset) => {
-                        self.network.shapes.camera.position =
-                            -cursor_position.to_vector() + *offset;
+                    Action::ResizeNode(id) => {
+                        let cursor = self.network.shapes.camera.to_world(cursor_position);
+                        self.network.resize_node(*id, cursor);
+                    }
+                    Action::DragPan(anchor) => {
+                        // Keep the workspace point that was clicked under the cursor
+                        let camera = &mut self.network.shapes.camera;
+                        camera.position =
+                            *anchor - cursor_position.to_vector() * (1. / camera.zoom);
                     }
                     _ => (),
                 }
+
+                //// Wire under the cursor, only while nothing else is going on
+                let camera = &self.network.shapes.camera;
+                self.hovered_wire = match self.action {
+                    Action::Idle => self.wire_at(
+                        camera.to_world(cursor_position),
+                        WIRE_HIT_DISTANCE / camera.zoom,
+                    ),
+                    _ => None,
+                };
             }
 
             Message::ScrollPan(delta) => {
-                self.network.shapes.camera.position.x -= delta.x * 2.;
-                self.network.shapes.camera.position.y -= delta.y * 2.;
+                let camera = &mut self.network.shapes.camera;
+                camera.position = camera.position - delta * (2. / camera.zoom);
+            }
+            Message::Zoom(factor) => {
+                let camera = &mut self.network.shapes.camera;
+                camera.zoom_about(camera.zoom * factor, self.cursor_position);
+            }
+            Message::ZoomReset => {
+                self.network
+                    .shapes
+                    .camera
+                    .zoom_about(1.0, self.cursor_position);
             }
+            Message::ZoomToFit => {
+                let all = self
+                    .network
+                    .shapes
+                    .shape_positions
+                    .keys()
+                    .copied()
+                    .collect();
+                self.network.zoom_to_shapes(&all, self.workspace_size);
+            }
+            Message::ZoomToSelection => {
+                let selected = self.network.selected_shapes.clone();
+                self.network.zoom_to_shapes(&selected, self.workspace_size);
+            }
+            Message::WorkspaceResized(size) => self.workspace_size = size,
 
             //// Port
             Message::PortStartHover(hover_port) => match &self.action {
-                Action::CreatingInputWire(input, _) => {
-                    if *input != hover_port {
-                        self.action = Action::CreatingInputWire(input.clone(), Some(hover_port))
-                    }
+                Action::CreatingInputWire(input, _) if *input != hover_port => {
+                    self.action = Action::CreatingInputWire(input.clone(), Some(hover_port))
                 }
-                Action::CreatingOutputWire(output, _) => {
-                    if *output != hover_port {
-                        self.action = Action::CreatingOutputWire(output.clone(), Some(hover_port))
-                    }
+                Action::CreatingOutputWire(output, _) if *output != hover_port => {
+                    self.action = Action::CreatingOutputWire(output.clone(), Some(hover_port))
                 }
                 _ => {}
             },
@@ -233,12 +444,40 @@ impl App {
                 IO::Out => self.action = Action::CreatingOutputWire(port, None),
             },
             Message::PortRelease => {
+                let release_point = self.network.shapes.camera.to_world(self.cursor_position);
                 let task = match &self.action.clone() {
+                    //// Clicked on a port, inspect the data passing through it
+                    Action::CreatingInputWire(port, None)
+                    | Action::CreatingOutputWire(port, None)
+                        if self.network.node_at(release_point) == Some(port.node) =>
+                    {
+                        let output = match port.io {
+                            IO::In => self.network.graph.get_parent(&port.node, port.name.clone()),
+                            IO::Out => Some(port.clone()),
+                        };
+                        output.map_or(Task::none(), |output| {
+                            Task::done(Message::InspectWire(output))
+                        })
+                    }
                     Action::CreatingInputWire(input, Some(output))
                     | Action::CreatingOutputWire(output, Some(input)) => {
                         self.network.add_edge(input, output);
                         Task::done(Message::QueueCompute(output.node))
                     }
+                    //// Released in empty space, search for a node to connect to
+                    Action::CreatingInputWire(port, None)
+                    | Action::CreatingOutputWire(port, None)
+                        if self.network.node_at(release_point).is_none() =>
+                    {
+                        self.action = Action::CommandPalette {
+                            query: String::new(),
+                            selected: 0,
+                            wire: Some((port.clone(), release_point)),
+                            results: vec![],
+                        };
+                        self.refresh_palette_results();
+                        return text_input::focus(palette_input_id());
+                    }
                     _ => Task::none(),
                 };
                 self.action = Action::Idle;
@@ -248,10 +487,85 @@ impl App {
                 self.network.remove_edge(port);
             }
 
+            Message::InspectWire(port) => {
+                let data = self.network.graph.get_wire_data(&port.node, &port.name);
+                self.inspector = Some(Inspector::new(port, data));
+            }
+            Message::InspectSlice(axis, index) => {
+                if let Some(inspector) = &mut self.inspector {
+                    if inspector.slice.len() <= axis {
+                        inspector.slice.resize(axis + 1, 0);
+                    }
+                    inspector.slice[axis] = index;
+                    inspector.first_row = 0;
+                }
+            }
+            Message::InspectRows(first_row) => {
+                if let Some(inspector) = &mut self.inspector {
+                    inspector.first_row = first_row;
+                }
+            }
+            Message::CloseInspector => self.inspector = None,
+
+            //// Windows
+            Message::WindowClosed(id) => {
+                if Some(id) == self.main_window {
+                    return iced::exit();
+                }
+                self.windows.remove(&id);
+            }
+            Message::PopOut(node) => return self.open_window(DetachedWindow::Viewer(node)),
+            Message::Export(node) => {
+                let file = FileDialog::new()
+                    .set_directory(self.user_data.network_search_dir())
+                    .set_file_name(format!(
+                        "{}.png",
+                        self.network.graph.get_node(node).template
+                    ))
+                    .add_filter("png", &["png"])
+                    .add_filter("svg", &["svg"])
+                    .save_file();
+                match file {
+                    Some(file) => match self.network.export_figure(node, &file, self.export_size) {
+                        Ok(()) => info!("exported {file:?}"),
+                        Err(e) => {
+                            error!("Could not export {file:?}\n{e}");
+                            rfd::MessageDialog::new()
+                                .set_level(rfd::MessageLevel::Error)
+                                .set_title("Export failed")
+                                .set_description(e.to_string())
+                                .show();
+                        }
+                    },
+                    None => info!("File not picked"),
+                }
+            }
+            Message::ExportResolution(size) => self.export_size = size,
+            Message::OpenDashboard => return self.open_window(DetachedWindow::Dashboard),
+            Message::AddToDashboard(node, part) => {
+                self.network.dashboard.add(node, part);
+                self.network.unsaved_changes = true;
+            }
+            Message::DashboardRemove(index) => {
+                self.network.dashboard.remove(index);
+                self.network.unsaved_changes = true;
+            }
+            Message::DashboardMove(index, offset) => {
+                self.network.dashboard.move_item(index, offset);
+                self.network.unsaved_changes = true;
+            }
+            Message::DashboardColumns(columns) => {
+                self.network.dashboard.set_columns(columns);
+                self.network.unsaved_changes = true;
+            }
+
             //// Node
             Message::OnCanvasDown(clicked_id) => {
                 //TODO: break this logic down into pure functions
                 //// Clicked on a node
+                if !self.modifiers.shift() {
+                    self.network.selected_annotations.clear();
+                }
                 if let Some(nx) = clicked_id {
                     self.action =
                         self.network
@@ -260,45 +574,65 @@ impl App {
                 } else
                 //// Clicked on the canvas background
                 {
-                    //// Clear selected shapes
-                    self.network.selected_shapes = Default::default();
-
-                    //// Start Pan
-                    self.action = Action::DragPan(
-                        self.network.shapes.camera.position + self.cursor_position.to_vector(),
-                    );
+                    //// Clicked on a wire
+                    let camera = &self.network.shapes.camera;
+                    if let Some(port) = self.wire_at(
+                        camera.to_world(self.cursor_position),
+                        WIRE_HIT_DISTANCE / camera.zoom,
+                    ) {
+                        return Task::done(Message::InspectWire(port));
+                    }
+                    //// Clear selected shapes, unless a selection box is modifying them
+                    if !(self.modifiers.shift() || self.modifiers.command()) {
+                        self.network.selected_shapes = Default::default();
+                    }
                 }
             }
-            Message::OnCanvasUp => {
-                // TODO: push undo stack if shape has moved
-                match self.action {
-                    Action::DragNode(..) => self.action = Action::Idle,
-                    Action::DragPan(_) => self.action = Action::Idle,
-                    _ => (),
-                }
+            Message::OnPanPress => {
+                self.action = Action::DragPan(
+                    self.network
+                        .shapes
+                        .camera
+                        .to_world(self.cursor_position)
+                        .to_vector(),
+                );
             }
+            Message::NodeResizePress(id) => {
+                self.network.begin_edit("Resize node");
+                self.action = Action::ResizeNode(id);
+            }
+            Message::BoxSelect(ids) => {
+                self.network.box_select(ids, self.modifiers);
+            }
+            Message::OnCanvasUp => match self.action {
+                Action::DragNode(..)
+                | Action::DragAnnotations { .. }
+                | Action::ResizeAnnotation(..)
+                | Action::ResizeNode(..) => self.action = Action::Idle,
+                Action::DragPan(_) => self.action = Action::Idle,
+                _ => (),
+            },
 
             Message::UpdateNodeTemplate(id, new_template) => {
-                //TODO: move into Network
                 let old_template = &self.network.graph.get_node(id).template;
                 if *old_template != new_template {
-                    self.network.stash_state();
-                    // Now we can aquire mutable reference
-                    let old_template = &mut self.network.graph.get_mut_node(id).template;
-                    *old_template = new_template;
+                    self.network
+                        .begin_edit(format!("Edit {}", old_template.name()));
+                    // Some templates change their ports based on configuration
+                    self.network.set_template(id, new_template);
                     return Task::done(Message::QueueCompute(id));
                 };
             }
             Message::UpdateNodeParameter(id, name, updated_widget) => {
-                //TODO: move into Network
-                self.network.stash_state();
-                let old_template = &mut self.network.graph.get_mut_node(id).template;
                 // TODO: support all node types, not just py_node
-                if let NodeTemplate::PyNode(node) = old_template {
+                if let NodeTemplate::PyNode(node) = &self.network.graph.get_node(id).template {
+                    let mut node = node.clone();
+                    self.network.begin_edit(format!("Set {name}"));
                     node.parameters
                         .as_mut()
                         .expect("parameters must exist if they are being edited")
                         .insert(name, updated_widget);
+                    self.network.set_template(id, NodeTemplate::PyNode(node));
                     return Task::done(Message::QueueCompute(id));
                 }
             }
@@ -319,33 +653,149 @@ impl App {
                     "should not be able to select a nope group while Add Node UI is not active"
                 ),
             },
+            Message::OpenCommandPalette => {
+                self.action = Action::CommandPalette {
+                    query: String::new(),
+                    selected: 0,
+                    wire: None,
+                    results: vec![],
+                };
+                self.refresh_palette_results();
+                return text_input::focus(palette_input_id());
+            }
+            Message::PaletteQuery(new_query) => {
+                if let Action::CommandPalette {
+                    query, selected, ..
+                } = &mut self.action
+                {
+                    *query = new_query;
+                    *selected = 0;
+                }
+                self.refresh_palette_results();
+            }
+            Message::PaletteSelect(offset) => {
+                if let Action::CommandPalette {
+                    selected, results, ..
+                } = &mut self.action
+                {
+                    *selected = selected
+                        .saturating_add_signed(offset)
+                        .min(results.len().saturating_sub(1));
+                }
+            }
+            Message::PaletteSubmit => {
+                if let Action::CommandPalette {
+                    selected, results, ..
+                } = &self.action
+                {
+                    if let Some(item) = results.get(*selected) {
+                        return Task::done(Message::PaletteChoose(item.clone()));
+                    }
+                }
+            }
+            Message::PaletteChoose(item) => {
+                self.user_data.add_recent_palette_item(item.key());
+                let action = std::mem::replace(&mut self.action, Action::Idle);
+                match (item, action) {
+                    (
+                        PaletteItem::Node(_, template),
+                        Action::CommandPalette {
+                            wire: Some((port, position)),
+                            ..
+                        },
+                    ) => {
+                        let id = self.network.add_connected_node(template, &port, position);
+                        return Task::done(Message::QueueCompute(id));
+                    }
+                    (item, _) => return Task::done(item.message()),
+                }
+            }
             Message::AddNode(template) => {
-                //TODO: move into Network
-                self.network.stash_state();
-                let id = self.network.graph.node(template.into());
+                self.network.begin_edit(format!("Add {}", template.name()));
+                let position = self.network.shapes.camera.to_world(self.cursor_position);
+                let id = self.network.add_node(template, position);
                 self.network.selected_shapes = [id].into();
-                self.network.shapes.shape_positions.insert_before(
-                    0,
-                    id,
-                    self.cursor_position + self.network.shapes.camera.position,
-                );
                 self.action = Action::DragNode(vec![(id, [0.0, 0.0].into())])
             }
             Message::DeleteSelectedNodes => {
                 //TODO: move into Network
-                if !self.network.selected_shapes.is_empty() {
-                    self.network.stash_state();
-                    self.network.selected_shapes.iter().for_each(|id| {
-                        self.network.graph.delete_node(*id);
-                        self.network.shapes.shape_positions.swap_remove(id);
-                    });
-                    self.network.selected_shapes = [].into();
+                if !(self.network.selected_shapes.is_empty()
+                    && self.network.selected_annotations.is_empty())
+                {
+                    self.network.delete_selection();
                     //PERF: ideally, we should only execute affected nodes
                     return Task::done(Message::ComputeAll);
                 }
             }
+            Message::Duplicate => {
+                if !self
+                    .network
+                    .duplicate_selection(&self.python_projects)
+                    .is_empty()
+                {
+                    return Task::done(Message::ComputeAll);
+                }
+            }
+            Message::AutoLayout => self.network.auto_layout(),
+            Message::AnnotationPress(id, part) => {
+                self.action =
+                    self.network
+                        .select_annotation(id, part, self.modifiers, self.cursor_position);
+            }
+            Message::AddNote => {
+                let center = self.workspace_center();
+                self.network.add_note(center);
+            }
+            Message::AddFrame => {
+                let center = self.workspace_center();
+                self.network.add_frame(center);
+            }
+            Message::EditAnnotationText(action) => {
+                let is_edit = action.is_edit();
+                self.annotation_editor.perform(action);
+                if let (Some((id, _)), true) = (self.network.edited_annotation(), is_edit) {
+                    let text = self.annotation_editor.text();
+                    let text = text.strip_suffix('\n').unwrap_or(&text).to_string();
+                    self.network.set_annotation_text(id, text);
+                }
+            }
+            Message::SetAnnotationColor(color) => {
+                if let Some((id, _)) = self.network.edited_annotation() {
+                    self.network.set_annotation_color(id, color);
+                }
+            }
+            Message::Align(align) => self.network.align_selection(align),
+            Message::ToggleGrid => {
+                let snap_to_grid = !self.user_data.snap_to_grid();
+                self.user_data.set_snap_to_grid(snap_to_grid);
+            }
+            Message::Copy => {
+                if let Some(fragment) = self.network.copy_selection() {
+                    return clipboard::write(fragment);
+                }
+            }
+            Message::Cut => {
+                if let Some(fragment) = self.network.copy_selection() {
+                    return clipboard::write(fragment)
+                        .chain(Task::done(Message::DeleteSelectedNodes));
+                }
+            }
+            Message::Paste => return clipboard::read().map(Message::PasteFragment),
+            Message::PasteFragment(contents) => {
+                let position = self.network.shapes.camera.to_world(self.cursor_position);
+                match contents.map(|c| self.network.paste(&c, position, &self.python_projects)) {
+                    Some(Ok(_)) => return Task::done(Message::ComputeAll),
+                    Some(Err(e)) => warn!("Clipboard does not contain nodes\n{e}"),
+                    None => {}
+                }
+            }
 
-            Message::AnimationTick => {}
+            Message::AnimationTick => {
+                //// Forget pulses that have faded, so animation stops once everything is idle
+                self.network
+                    .wire_pulses
+                    .retain(|_, delivered| delivered.elapsed() < PULSE_DURATION);
+            }
             Message::ThemeValueChange(tm, tv) => self.app_theme.update(tm, tv),
             Message::ToggleDebug => {
                 self.debug = !self.debug;
@@ -453,34 +903,35 @@ impl App {
             }
 
             //// Focus
+            Message::KeyPressed(key, modifiers, captured) => {
+                if let Some(message) = self.keymap.message(&key, modifiers, captured) {
+                    return Task::done(message);
+                }
+            }
             Message::FocusNext => return focus_next(),
             Message::FocusPrevious => return focus_previous(),
 
             //// History
             Message::Undo => {
-                //TODO: move into Network
-                if let Some(prev) = self.network.undo_stack.pop() {
-                    self.network.redo_stack.push((
-                        self.network.graph.clone(),
-                        self.network.shapes.shape_positions.clone(),
-                    ));
-                    self.network.graph = prev.0;
-                    self.network.shapes.shape_positions = prev.1;
+                let position = self.network.history.position();
+                if self.network.jump_to_history(position.saturating_sub(1)) {
                     return Task::done(Message::ComputeAll);
                 }
             }
             Message::Redo => {
-                //TODO: move into Network
-                if let Some(next) = self.network.redo_stack.pop() {
-                    self.network.undo_stack.push((
-                        self.network.graph.clone(),
-                        self.network.shapes.shape_positions.clone(),
-                    ));
-                    self.network.graph = next.0;
-                    self.network.shapes.shape_positions = next.1;
+                let position = self.network.history.position();
+                if self.network.jump_to_history(position + 1) {
+                    return Task::done(Message::ComputeAll);
+                }
+            }
+            Message::JumpToHistory(position) => {
+                if self.network.jump_to_history(position) {
                     return Task::done(Message::ComputeAll);
                 }
             }
+            Message::ToggleHistory => {
+                self.show_history = !self.show_history;
+            }
             Message::ComputeAll => {
                 //TODO: move into Network
                 let nodes = self.network.graph.get_roots();
@@ -522,7 +973,8 @@ impl App {
                         // Assert that status is what is expected
                         let run_time = match &node.status {
                             NodeStatus::Idle => panic!("Node should not be idle here!"),
-                            NodeStatus::Running(start_inst) => Instant::now() - *start_inst,
+                            NodeStatus::Running(start_inst) => Some(Instant::now() - *start_inst),
+                            NodeStatus::Waiting => None,
                             NodeStatus::Error(_node_error) => panic!("Node should not be Error, compute should have returned an Error result and node.status is set to Error in the match arm below"),
                         };
 
@@ -530,13 +982,25 @@ impl App {
 
                         //// Update wire
                         self.network.graph.update_wire_data(nx, output);
+                        self.network.wire_pulses.insert(nx, Instant::now());
+                        if let Some(inspector) = &mut self.inspector {
+                            if inspector.port.node == nx {
+                                let port = &inspector.port;
+                                inspector.update_stats(
+                                    self.network.graph.get_wire_data(&port.node, &port.name),
+                                );
+                            }
+                        }
 
                         //// Update node
                         self.network.graph.set_node_data(
                             nx,
                             NodeData {
-                                status: NodeStatus::Idle,
-                                run_time: Some(run_time),
+                                status: match node.status {
+                                    NodeStatus::Waiting => NodeStatus::Waiting,
+                                    _ => NodeStatus::Idle,
+                                },
+                                run_time,
                                 // We *don't* update template here for some nodes
                                 // because that causes stuttery behaviour for
                                 // fast update scenarios like the slider of the 'constant'
@@ -595,36 +1059,127 @@ impl App {
         Task::none()
     }
 
+    /// Open a window, or focus it if it is already open
+    fn open_window(&mut self, detached: DetachedWindow) -> Task<Message> {
+        if let Some((id, _)) = self.windows.iter().find(|(_, open)| **open == detached) {
+            return window::gain_focus(*id);
+        }
+        let (id, open) = window::open(window::Settings {
+            size: (640., 520.).into(),
+            min_size: Some((200., 150.).into()),
+            ..Default::default()
+        });
+        self.windows.insert(id, detached);
+        open.discard()
+    }
+
     /// App View
-    pub fn view(&self) -> Element<Message, Theme, Renderer> {
+    pub fn view(&self, window: window::Id) -> Element<'_, Message, Theme, Renderer> {
+        let content = match self.windows.get(&window) {
+            Some(DetachedWindow::Viewer(id)) => viewer_window(&self.network, *id),
+            Some(DetachedWindow::Dashboard) => dashboard_view(&self.network),
+            None => return self.main_view(),
+        };
+        if self.debug {
+            content.explain(iced::Color::from_rgba(0.7, 0.7, 0.8, 0.2))
+        } else {
+            content
+        }
+    }
+
+    fn main_view(&self) -> Element<'_, Message, Theme, Renderer> {
+        //// Nodes with stale data, because they or a node upstream of them failed
+        let graph = &self.network.graph;
+        let stale_nodes = graph.downstream(
+            graph
+                .nodes_ref()
+                .into_iter()
+                .filter(|nx| matches!(graph.get_node(*nx).status, NodeStatus::Error(_))),
+        );
         let content = column![
             row![
                 side_bar(self),
                 vertical_rule(SEPERATOR),
                 container(
-                    workspace(
-                        &self.network.shapes,
-                        //// Node view
-                        |id| self.node_content(id),
-                        //// Wires paths
-                        |wire_end_node, points| self.wire_curve(wire_end_node, points),
-                    )
-                    .on_cursor_move(Message::OnMove)
-                    .on_press(Message::OnCanvasDown)
-                    .on_release(Message::OnCanvasUp)
-                    .pan(Message::ScrollPan)
+                    //// Tooltip for the hovered wire, above the workspace
+                    Stack::new()
+                        .push(
+                            workspace(
+                                &self.network.shapes,
+                                //// Node view
+                                |id| self.node_content(id),
+                                //// Wires paths
+                                move |wire_end_node, points| {
+                                    self.wire_curve(wire_end_node, points, &stale_nodes)
+                                },
+                            )
+                            .on_cursor_move(Message::OnMove)
+                            .on_press(Message::OnCanvasDown)
+                            .on_release(Message::OnCanvasUp)
+                            .on_select(Message::BoxSelect)
+                            .on_pan_press(Message::OnPanPress)
+                            .pan(Message::ScrollPan)
+                            .zoom(Message::Zoom)
+                            .on_resize(Message::WorkspaceResized)
+                            .grid(self.user_data.snap_to_grid().then_some(GRID_SIZE))
+                            .annotations(self.network.annotations.iter().map(|(id, annotation)| {
+                                (
+                                    *id,
+                                    annotation,
+                                    self.network.selected_annotations.contains(id),
+                                )
+                            }))
+                            .on_annotation_press(Message::AnnotationPress)
+                        )
+                        .push_maybe(self.wire_tooltip())
                 )
                 .height(Fill)
                 .width(Fill)
             ],
+            match &self.inspector {
+                Some(inspector) => column![
+                    horizontal_rule(SEPERATOR),
+                    container(inspector_panel(
+                        inspector,
+                        self.network
+                            .graph
+                            .get_node(inspector.port.node)
+                            .template
+                            .name(),
+                        self.network
+                            .graph
+                            .get_wire_data(&inspector.port.node, &inspector.port.name),
+                    ))
+                    .height(INSPECTOR_HEIGHT)
+                ],
+                None => column![],
+            },
             match self.show_palette_ui {
                 true => column![horizontal_rule(SEPERATOR), self.app_theme.view()],
                 false => column![],
             }
         ];
 
-        let output: Element<Message, Theme, Renderer> = match &self.action {
-            Action::AddingNode => stack![
+        let modal = match &self.action {
+            Action::AddingNode => Some(add_node_tree_panel(
+                &self.python_projects,
+                self.user_data.get_new_node_path(),
+            )),
+            Action::CommandPalette {
+                query,
+                selected,
+                wire,
+                results,
+            } => Some(command_palette(
+                query,
+                wire.is_some(),
+                &results.iter().collect::<Vec<_>>(),
+                *selected,
+            )),
+            _ => None,
+        };
+        let output: Element<Message, Theme, Renderer> = match modal {
+            Some(modal) => stack![
                 content,
                 // Barrier to stop interaction
                 mouse_area(
@@ -635,24 +1190,21 @@ impl App {
                 // Stop any mouseover interactions from showing,
                 .interaction(mouse::Interaction::Idle)
                 .on_press(Message::Cancel),
-                //// Add node modal
+                //// Add node or command palette modal
                 container(
-                    mouse_area(add_node_tree_panel(
-                        &self.python_projects,
-                        self.user_data.get_new_node_path()
-                    ))
-                    .interaction(mouse::Interaction::Idle)
-                    .on_press(Message::NOP)
+                    mouse_area(modal)
+                        .interaction(mouse::Interaction::Idle)
+                        .on_press(Message::NOP)
                 )
                 .center(Fill)
             ]
             .into(),
-            _ => content.into(),
+            None => content.into(),
         };
 
         // Potentially add a specific mouse cursor
         let output = match self.action {
-            Action::DragNode(_) => mouse_area(output)
+            Action::DragNode(_) | Action::DragAnnotations { .. } => mouse_area(output)
                 .interaction(mouse::Interaction::Move)
                 .into(),
             _ => output,
@@ -665,10 +1217,49 @@ impl App {
         }
     }
 
+    /// Search the command palette again, after its query or the list of nodes changed
+    fn refresh_palette_results(&mut self) {
+        let Action::CommandPalette { query, wire, .. } = &self.action else {
+            return;
+        };
+        let new_results = self.palette_results(query, wire.as_ref());
+        if let Action::CommandPalette { results, .. } = &mut self.action {
+            *results = new_results;
+        }
+    }
+
+    /// Results of the command palette search, best first
+    fn palette_results(&self, query: &str, wire: Option<&(PortRef, Point)>) -> Vec<PaletteItem> {
+        let mut items = palette_items(&self.python_projects);
+        // Only offer nodes that can be connected to a dragged wire
+        if let Some((port, _)) = wire {
+            let port_type = self.network.port_type(port);
+            items.retain(|item| match (item, &port_type) {
+                (PaletteItem::Node(_, template), Some(port_type)) => {
+                    compatible_port(&template.clone().into(), &port.io, port_type).is_some()
+                }
+                _ => false,
+            });
+        }
+        search(&items, query, self.user_data.recent_palette_items())
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// The center of the visible workspace, in workspace coordinates
+    fn workspace_center(&self) -> Point {
+        self.network.shapes.camera.to_world(Point::new(
+            self.workspace_size.width / 2.,
+            self.workspace_size.height / 2.,
+        ))
+    }
+
     /// Read node definitions from disk, and copies node configuration (parameters and port connections) forward.
     /// *Does not trigger the compute function of any nodes.*
     fn reload_nodes(&mut self) {
         // Update any existing nodes in the graph that could change based on file changes
+        self.network.begin_edit("Reload nodes");
         self.network.graph.nodes_ref().iter().for_each(|nx| {
             let node = self.network.graph.get_node(*nx).clone();
             if let NodeTemplate::PyNode(old_py_node) = node.template {
@@ -747,22 +1338,22 @@ impl App {
                             "Removing port {:?} from node {:?}",
                             p.name, new_py_node.name
                         );
-                        self.network.graph.remove_edge(&p);
+                        self.network.disconnect(&p);
                     });
                 }
 
-                // Update Graph Node
+                // Update Graph Node, so that undoing restores the ports of removed wires
                 self.network
-                    .graph
-                    .set_node_data(*nx, NodeTemplate::PyNode(new_py_node).into());
+                    .set_template(*nx, NodeTemplate::PyNode(new_py_node));
             }
         });
         // Update list of available nodes
         self.python_projects = self.config.read_projects();
+        self.refresh_palette_results();
     }
 }
 
-pub fn theme(state: &App) -> Theme {
+pub fn theme(state: &App, _window: window::Id) -> Theme {
     state.app_theme.clone().into()
 }
 
@@ -775,11 +1366,13 @@ pub fn subscriptions(state: &App) -> Subscription<Message> {
             .map(|(id, p)| file_watch_subscription(id, p.absolute_path.clone()))
             .chain([
                 window::open_events().map(|_| Message::WindowOpen),
-                listen_with(|event, _status, _id| match event {
+                window::close_events().map(Message::WindowClosed),
+                listen_with(|event, status, _id| match event {
                     Keyboard(keyboard::Event::ModifiersChanged(m)) => {
                         Some(Message::ModifiersChanged(m))
                     }
                     Keyboard(KeyPressed { key, modifiers, .. }) => match key {
+                        //// Navigation keys are fixed, everything else is in the keymap
                         Key::Named(Named::Tab) => {
                             if modifiers.contains(Modifiers::SHIFT) {
                                 Some(Message::FocusPrevious)
@@ -787,21 +1380,21 @@ pub fn subscriptions(state: &App) -> Subscription<Message> {
                                 Some(Message::FocusNext)
                             }
                         }
-                        Key::Named(Named::Delete) => Some(Message::DeleteSelectedNodes),
                         Key::Named(Named::Escape) => Some(Message::Cancel),
-                        Key::Character(smol_str) => {
-                            if modifiers.control() && smol_str == "a" {
-                                Some(Message::OpenAddNodeUi)
-                            } else {
-                                None
-                            }
-                        }
-                        _ => None,
+                        Key::Named(Named::ArrowDown) => Some(Message::PaletteSelect(1)),
+                        Key::Named(Named::ArrowUp) => Some(Message::PaletteSelect(-1)),
+                        key => Some(Message::KeyPressed(
+                            key,
+                            modifiers,
+                            status == event::Status::Captured,
+                        )),
                     },
                     _ => None,
                 }),
-                // Refresh for animation while nodes are actively running
-                if state.network.graph.running_nodes().is_empty() {
+                // Refresh for animation while nodes are actively running, or wires are pulsing
+                if state.network.graph.running_nodes().is_empty()
+                    && state.network.wire_pulses.is_empty()
+                {
                     Subscription::none()
                 } else {
                     iced::time::every(Duration::from_micros(1_000_000 / 16))
@@ -811,7 +1404,22 @@ pub fn subscriptions(state: &App) -> Subscription<Message> {
     )
 }
 
-pub fn title(state: &App) -> String {
+pub fn title(state: &App, window: window::Id) -> String {
+    let network_title = network_title(state);
+    match state.windows.get(&window) {
+        Some(DetachedWindow::Viewer(id))
+            if state.network.shapes.shape_positions.contains_key(id) =>
+        {
+            let name = state.network.graph.get_node(*id).template.name();
+            format!("{name} - {network_title}")
+        }
+        Some(DetachedWindow::Viewer(_)) => network_title,
+        Some(DetachedWindow::Dashboard) => format!("Dashboard - {network_title}"),
+        None => network_title,
+    }
+}
+
+fn network_title(state: &App) -> String {
     let pre_pend = match state.network.unsaved_changes {
         true => "*",
         false => "",
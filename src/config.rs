@@ -21,13 +21,23 @@ use crate::{
 pub struct Config {
     venv_dir: PathBuf,
     python_nodes_dir: Vec<PathBuf>,
+    /// Maximum number of undo steps to keep, unlimited if not set
+    #[serde(default)]
+    undo_depth: Option<usize>,
 }
 
 impl Config {
+    /// Folder that holds `config.toml` and the other user configuration files
+    pub fn config_dir() -> PathBuf {
+        let user_dirs = directories::UserDirs::new()
+            .expect("Application configuration folder should be accessible");
+        user_dirs.home_dir().join(".config/gpi")
+    }
+
     pub fn read_config() -> Self {
         let user_dirs = directories::UserDirs::new()
             .expect("Application configuration folder should be accessible");
-        let config_dir = user_dirs.home_dir().join(".config/gpi");
+        let config_dir = Self::config_dir();
         let config_file = config_dir.join("config.toml");
 
         match read_to_string(&config_file).map(|s| toml::from_str::<Config>(&s)) {
@@ -63,6 +73,7 @@ impl Config {
                 let config = Config {
                     venv_dir,
                     python_nodes_dir: vec![nodes_dir],
+                    undo_depth: None,
                 };
                 let _ = std::fs::create_dir(config_dir);
                 std::fs::write(
@@ -77,20 +88,22 @@ impl Config {
         }
     }
 
-    pub(crate) fn read_projects(&self) -> Vec<crate::project::Project> {
+    pub fn read_projects(&self) -> Vec<crate::project::Project> {
         self.nodes_dir()
             .iter()
             .map(|dir| python_project(dir))
             .chain([rust_project()])
             .collect()
     }
+
+    pub fn undo_depth(&self) -> Option<usize> {
+        self.undo_depth
+    }
 }
 
 impl Config {
     pub fn load_theme() -> AppTheme {
-        let user_dirs =
-            directories::UserDirs::new().expect("application configuration folder is accessible");
-        let theme_file = user_dirs.home_dir().join(".config/gpi/theme.ron");
+        let theme_file = Self::config_dir().join("theme.ron");
 
         match read_to_string(&theme_file).map(|s| ron::from_str::<AppTheme>(&s)) {
             Ok(Ok(network)) => network,
@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     sync::{Arc, RwLock},
 };
 
@@ -20,6 +20,14 @@ where
         self,
         inputs: StableMap<String, WireDataContainer<WireData>>,
     ) -> Result<(StableMap<String, WireData>, NodeData), NodeError>;
+    /// Result for a node that has inputs, none of which are connected.
+    /// By default there is nothing to compute and the node has no outputs
+    fn compute_unconnected(self) -> Result<(StableMap<String, WireData>, NodeData), NodeError>
+    where
+        Self: Sized + Into<NodeData>,
+    {
+        Ok((StableMap::default(), self.into()))
+    }
 }
 
 type PortName = String;
@@ -101,6 +109,12 @@ where
         id
     }
 
+    /// Add a node with a specific index, e.g. to restore a node that was deleted
+    pub fn insert_node(&mut self, id: NodeIndex, node: NodeData) {
+        self.nodes.insert(id, node);
+        self.next_id = self.next_id.max(id + 1);
+    }
+
     /// Remove a node and all edges associated with it
     pub fn delete_node(&mut self, id: NodeIndex) {
         self.nodes.remove(&id);
@@ -253,11 +267,32 @@ where
         self.edges.push((from, to));
     }
 
+    /// remove a single connection between two ports
+    pub fn disconnect(&mut self, from: &PortRef, to: &PortRef) {
+        self.edges.retain(|edge| (&edge.0, &edge.1) != (from, to))
+    }
+
+    /// All connections in the graph
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
     // remove any edges associated with the given port
     pub fn remove_edge(&mut self, port: &PortRef) {
         self.edges.retain(|(from, to)| port != from && port != to)
     }
 
+    /// Remove any edges connected to ports that no longer exist on node `nx`,
+    /// e.g. after a node's configuration changed its inputs or outputs
+    pub fn remove_invalid_edges(&mut self, nx: NodeIndex) {
+        let inputs = self.get_node(nx).inputs();
+        let outputs = self.get_node(nx).outputs();
+        self.edges.retain(|(from, to)| {
+            (from.node != nx || outputs.contains_key(&from.name))
+                && (to.node != nx || inputs.contains_key(&to.name))
+        })
+    }
+
     pub fn get_parent(&self, nx: &NodeIndex, in_port: PortName) -> Option<PortRef> {
         self.edges
             .iter()
@@ -301,6 +336,49 @@ where
             .collect()
     }
 
+    /// Find the edges that connect nodes within the given set
+    pub fn internal_edges(&self, nodes: &[NodeIndex]) -> Vec<(PortRef, PortRef)> {
+        self.edges
+            .iter()
+            .filter(|(from, to)| nodes.contains(&from.node) && nodes.contains(&to.node))
+            .cloned()
+            .collect()
+    }
+
+    /// `nx` and every node that it depends on through wires
+    pub fn upstream(&self, nx: NodeIndex) -> HashSet<NodeIndex> {
+        let mut found = HashSet::new();
+        let mut stack = vec![nx];
+        while let Some(nx) = stack.pop() {
+            if found.insert(nx) {
+                stack.extend(
+                    self.edges
+                        .iter()
+                        .filter(|(_, to)| to.node == nx)
+                        .map(|(from, _)| from.node),
+                );
+            }
+        }
+        found
+    }
+
+    /// `nodes` and every node that depends on them through wires
+    pub fn downstream(&self, nodes: impl IntoIterator<Item = NodeIndex>) -> HashSet<NodeIndex> {
+        let mut found = HashSet::new();
+        let mut stack: Vec<_> = nodes.into_iter().collect();
+        while let Some(nx) = stack.pop() {
+            if found.insert(nx) {
+                stack.extend(
+                    self.edges
+                        .iter()
+                        .filter(|(from, _)| from.node == nx)
+                        .map(|(_, to)| to.node),
+                );
+            }
+        }
+        found
+    }
+
     /// Find the edges that that originate at `nx`
     pub fn outgoing_edges(&self, nx: &NodeIndex) -> Vec<PortRef> {
         self.edges
@@ -454,6 +532,10 @@ where
         u32,
         Result<(StableMap<String, WireData>, NodeData), NodeError>,
     ) {
+        // Nodes decide what to do without any connected inputs, instead of failing to find them
+        if input_guarded.is_empty() && !node.inputs().is_empty() {
+            return (nx, node.compute_unconnected());
+        }
         let output = { node.compute(input_guarded) };
 
         (nx, output)
@@ -560,6 +642,25 @@ mod test {
         assert_eq!(g.topological_sort(), vec![7, 6, 5, 4, 3, 2, 1, 0]);
     }
 
+    #[test]
+    fn upstream_nodes() {
+        let mut g: Graph<Node, (), u32> = Graph::new();
+        let n1 = g.node(Node::Constant(ConstantNode { value: 7 }));
+        let n2 = g.node(Node::Identity(IdentityNode {}));
+        let n3 = g.node(Node::Identity(IdentityNode {}));
+        let n4 = g.node(Node::Identity(IdentityNode {}));
+        g.connect((n1, "out"), (n2, "in"));
+        g.connect((n2, "out"), (n3, "in"));
+
+        assert_eq!(g.upstream(n3), [n1, n2, n3].into());
+        assert_eq!(g.upstream(n1), [n1].into());
+        assert_eq!(g.upstream(n4), [n4].into());
+
+        assert_eq!(g.downstream([n2]), [n2, n3].into());
+        assert_eq!(g.downstream([n1, n4]), [n1, n2, n3, n4].into());
+        assert!(g.downstream([]).is_empty());
+    }
+
     #[test]
     fn process() {
         let mut g: Graph<Node, (), u32> = Graph::new();
@@ -1,13 +1,14 @@
 use std::sync::{Arc, RwLock, RwLockReadGuard};
 
-use iced::{widget::text, Element};
+use iced::{widget::text, Element, Size};
 
 use crate::{
     app::Message,
     graph::Graph,
     nodes::{
+        figure::{Figure, FigureStyle},
         port::{PortData, PortType},
-        status::NodeStatus,
+        status::{NodeError, NodeStatus},
         NodeData,
     },
     StableMap,
@@ -27,11 +28,16 @@ pub trait GUINode: derive_more::Debug {
         &self,
         _id: u32,
         _input_data: StableMap<String, PortDataContainer>,
-    ) -> Element<Message> {
+    ) -> Element<'_, Message> {
         text("default").into()
     }
     fn node_size(&self) -> iced::Size;
 
+    /// Nodes that visualize their input, which can be popped out into their own window
+    fn is_viewer(&self) -> bool {
+        false
+    }
+
     fn config_view(
         &self,
         _id: u32,
@@ -39,6 +45,19 @@ pub trait GUINode: derive_more::Debug {
     ) -> Option<Element<'_, Message>> {
         None
     }
+
+    /// What a viewer draws at `size`, for exporting to a file
+    fn figure(
+        &self,
+        _input_data: &StableMap<String, PortDataContainer>,
+        _size: Size,
+        _style: FigureStyle,
+    ) -> Result<Figure, NodeError> {
+        Err(NodeError::Config(format!(
+            "{} can't be exported",
+            self.name()
+        )))
+    }
 }
 
 pub type PortDataReference<'a> = RwLockReadGuard<'a, PortData>;
@@ -0,0 +1,512 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    graph::PortRef,
+    math::{Point, Vector},
+    network::Network,
+    nodes::NodeTemplate,
+    widget::{
+        annotation::{Annotation, AnnotationId},
+        shapes::ShapeId,
+    },
+};
+
+/// Steps editing the same nodes that happen within this interval are grouped into a single
+/// undo step, so that dragging a slider or typing in a text field can be undone all at once
+const GROUP_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// A single reversible change to a network
+#[derive(Debug, Clone)]
+pub enum Edit {
+    /// Add a node, drawn at `index` in the drawing order
+    AddNode {
+        id: ShapeId,
+        template: NodeTemplate,
+        position: Point,
+        index: usize,
+    },
+    RemoveNode {
+        id: ShapeId,
+        template: NodeTemplate,
+        position: Point,
+        index: usize,
+    },
+    ChangeNode {
+        id: ShapeId,
+        before: NodeTemplate,
+        after: NodeTemplate,
+    },
+    MoveNode {
+        id: ShapeId,
+        before: Point,
+        after: Point,
+    },
+    AddEdge(PortRef, PortRef),
+    RemoveEdge(PortRef, PortRef),
+    /// Add, remove, or change an annotation
+    SetAnnotation {
+        id: AnnotationId,
+        before: Option<Annotation>,
+        after: Option<Annotation>,
+    },
+    /// Resize a node, `None` is the size of its template
+    ResizeNode {
+        id: ShapeId,
+        before: Option<Vector>,
+        after: Option<Vector>,
+    },
+}
+
+/// What an edit changes, edits of the same item can be merged
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Item {
+    Node(ShapeId),
+    Annotation(AnnotationId),
+}
+
+impl Edit {
+    /// The edit that reverts this one
+    fn inverse(self) -> Self {
+        match self {
+            Edit::AddNode {
+                id,
+                template,
+                position,
+                index,
+            } => Edit::RemoveNode {
+                id,
+                template,
+                position,
+                index,
+            },
+            Edit::RemoveNode {
+                id,
+                template,
+                position,
+                index,
+            } => Edit::AddNode {
+                id,
+                template,
+                position,
+                index,
+            },
+            Edit::ChangeNode { id, before, after } => Edit::ChangeNode {
+                id,
+                before: after,
+                after: before,
+            },
+            Edit::MoveNode { id, before, after } => Edit::MoveNode {
+                id,
+                before: after,
+                after: before,
+            },
+            Edit::AddEdge(from, to) => Edit::RemoveEdge(from, to),
+            Edit::RemoveEdge(from, to) => Edit::AddEdge(from, to),
+            Edit::SetAnnotation { id, before, after } => Edit::SetAnnotation {
+                id,
+                before: after,
+                after: before,
+            },
+            Edit::ResizeNode { id, before, after } => Edit::ResizeNode {
+                id,
+                before: after,
+                after: before,
+            },
+        }
+    }
+
+    pub(crate) fn apply(self, network: &mut Network) {
+        let graph = &mut network.graph;
+        let positions = &mut network.shapes.shape_positions;
+        match self {
+            Edit::AddNode {
+                id,
+                template,
+                position,
+                index,
+            } => {
+                graph.insert_node(id, template.into());
+                positions.insert_before(index.min(positions.len()), id, position);
+            }
+            Edit::RemoveNode { id, .. } => {
+                graph.delete_node(id);
+                positions.shift_remove(&id);
+            }
+            Edit::ChangeNode { id, after, .. } => graph.get_mut_node(id).template = after,
+            Edit::MoveNode { id, after, .. } => {
+                positions.insert(id, after);
+            }
+            Edit::AddEdge(from, to) => graph.add_edge_from_ref(&from, &to),
+            Edit::RemoveEdge(from, to) => graph.disconnect(&from, &to),
+            Edit::SetAnnotation {
+                id,
+                after: Some(annotation),
+                ..
+            } => {
+                network.annotations.insert(id, annotation);
+            }
+            Edit::SetAnnotation {
+                id, after: None, ..
+            } => {
+                network.annotations.shift_remove(&id);
+            }
+            Edit::ResizeNode {
+                id,
+                after: Some(size),
+                ..
+            } => {
+                network.node_sizes.insert(id, size);
+            }
+            Edit::ResizeNode {
+                id, after: None, ..
+            } => {
+                network.node_sizes.shift_remove(&id);
+            }
+        }
+    }
+
+    fn item(&self) -> Option<Item> {
+        match self {
+            Edit::AddNode { id, .. }
+            | Edit::RemoveNode { id, .. }
+            | Edit::ChangeNode { id, .. }
+            | Edit::MoveNode { id, .. }
+            | Edit::ResizeNode { id, .. } => Some(Item::Node(*id)),
+            Edit::SetAnnotation { id, .. } => Some(Item::Annotation(*id)),
+            Edit::AddEdge(..) | Edit::RemoveEdge(..) => None,
+        }
+    }
+
+    /// Fold a later edit of the same kind into this one, keeping this `before` and its `after`.
+    /// Returns false if they can't be merged
+    fn merge(&mut self, later: &Edit) -> bool {
+        match (self, later) {
+            (Edit::ChangeNode { after, .. }, Edit::ChangeNode { after: later, .. }) => {
+                *after = later.clone()
+            }
+            (Edit::MoveNode { after, .. }, Edit::MoveNode { after: later, .. }) => *after = *later,
+            (Edit::ResizeNode { after, .. }, Edit::ResizeNode { after: later, .. }) => {
+                *after = *later
+            }
+            (
+                Edit::SetAnnotation {
+                    after: after @ Some(_),
+                    ..
+                },
+                Edit::SetAnnotation {
+                    after: later @ Some(_),
+                    ..
+                },
+            ) => *after = later.clone(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// An edit that was merged back to where it started
+    fn is_noop(&self) -> bool {
+        match self {
+            Edit::ChangeNode { before, after, .. } => before == after,
+            Edit::MoveNode { before, after, .. } => before == after,
+            Edit::ResizeNode { before, after, .. } => before == after,
+            Edit::SetAnnotation { before, after, .. } => before == after,
+            _ => false,
+        }
+    }
+}
+
+/// A group of edits that are undone and redone together
+#[derive(Debug)]
+pub struct Step {
+    pub label: String,
+    edits: Vec<Edit>,
+    time: Instant,
+}
+
+impl Step {
+    fn new(label: String) -> Self {
+        Step {
+            label,
+            edits: vec![],
+            time: Instant::now(),
+        }
+    }
+
+    /// Add an edit, merged with the last edit of the same item when possible,
+    /// so dragging a node records a single move
+    fn push(&mut self, edit: Edit) {
+        let earlier = edit
+            .item()
+            .and_then(|item| self.edits.iter_mut().rev().find(|e| e.item() == Some(item)));
+        if !earlier.is_some_and(|earlier| earlier.merge(&edit)) {
+            self.edits.push(edit);
+        }
+    }
+
+    /// Continuous edits of the same nodes, e.g. from a slider, that can be merged into one step
+    fn can_group(&self, next: &Step) -> bool {
+        let changed = |step: &Step| {
+            step.edits
+                .iter()
+                .map(|edit| match edit {
+                    Edit::ChangeNode { id, .. } => Some((*id, false)),
+                    Edit::SetAnnotation {
+                        id,
+                        before: Some(_),
+                        after: Some(_),
+                    } => Some((*id, true)),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()
+        };
+        self.label == next.label
+            && next.time.duration_since(self.time) < GROUP_INTERVAL
+            && changed(self).is_some_and(|ids| Some(ids) == changed(next))
+    }
+}
+
+/// Undo history of a network, stored as the edits made by each step
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+    /// Edits made since the step in progress began, that haven't been committed yet
+    pending: Option<Step>,
+}
+
+impl History {
+    /// Start recording a step. If a step is already in progress,
+    /// any further edits are recorded as part of that step
+    pub fn begin(&mut self, label: impl Into<String>) {
+        if self.pending.is_none() {
+            self.pending = Some(Step::new(label.into()));
+        }
+    }
+
+    /// Record an edit that was applied to the network, as part of the step in progress
+    pub fn record(&mut self, edit: Edit) {
+        self.pending
+            .get_or_insert_with(|| Step::new("Edit".to_string()))
+            .push(edit);
+    }
+
+    /// Finish recording the step in progress, keeping at most `depth` steps if specified.
+    /// Returns true if anything changed
+    pub fn commit(&mut self, depth: Option<usize>) -> bool {
+        let Some(mut step) = self.pending.take() else {
+            return false;
+        };
+        step.edits.retain(|edit| !edit.is_noop());
+        if step.edits.is_empty() {
+            return false;
+        }
+        step.time = Instant::now();
+
+        match self.undo.last_mut() {
+            Some(last) if self.redo.is_empty() && last.can_group(&step) => {
+                // Both steps change the same items, keep the first `before` and the last `after`
+                step.edits.into_iter().for_each(|edit| last.push(edit));
+                last.time = step.time;
+                last.edits.retain(|edit| !edit.is_noop());
+                if last.edits.is_empty() {
+                    self.undo.pop();
+                }
+            }
+            _ => self.undo.push(step),
+        }
+        self.redo.clear();
+
+        // Forget the oldest steps
+        if let Some(depth) = depth {
+            let excess = self.undo.len().saturating_sub(depth);
+            self.undo.drain(..excess);
+        }
+        true
+    }
+
+    /// Revert the most recent step, returns false if there is nothing to undo
+    pub fn undo(&mut self, network: &mut Network) -> bool {
+        self.commit(None);
+        let Some(step) = self.undo.pop() else {
+            return false;
+        };
+        step.edits
+            .iter()
+            .rev()
+            .for_each(|edit| edit.clone().inverse().apply(network));
+        self.redo.push(step);
+        true
+    }
+
+    /// Re-apply the most recently undone step, returns false if there is nothing to redo
+    pub fn redo(&mut self, network: &mut Network) -> bool {
+        self.commit(None);
+        let Some(step) = self.redo.pop() else {
+            return false;
+        };
+        step.edits
+            .iter()
+            .for_each(|edit| edit.clone().apply(network));
+        self.undo.push(step);
+        true
+    }
+
+    /// Undo or redo until `position` steps are applied, returns true if anything changed
+    pub fn jump(&mut self, position: usize, network: &mut Network) -> bool {
+        let mut changed = false;
+        while self.position() > position && self.undo(network) {
+            changed = true;
+        }
+        while self.position() < position && self.redo(network) {
+            changed = true;
+        }
+        changed
+    }
+
+    /// Number of steps that are currently applied
+    pub fn position(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Number of steps that can be redone
+    pub fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    /// All steps in chronological order, including those that can be redone
+    pub fn steps(&self) -> impl Iterator<Item = &Step> {
+        self.undo.iter().chain(self.redo.iter().rev())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::nodes::RustNode;
+
+    fn add(network: &mut Network, node: RustNode) -> u32 {
+        let position = Point::new(network.graph.nodes_ref().len() as f32 * 100., 0.);
+        network.add_node(NodeTemplate::RustNode(node), position)
+    }
+
+    #[test]
+    fn undo_redo_restores_network() {
+        let mut network = Network::default();
+        let constant = add(&mut network, RustNode::Constant(1.));
+        let cos = add(&mut network, RustNode::Cos);
+        network.graph.connect((constant, "out"), (cos, "a"));
+        network.commit_edit(None);
+
+        network.begin_edit("Delete nodes");
+        network.remove_node(cos);
+        network.begin_edit("Ignored, already recording");
+        network.move_node(constant, Point::new(5., 5.));
+        network.add_annotation(Annotation::note(Point::new(0., 0.)));
+        network.commit_edit(None);
+
+        assert!(network.jump_to_history(1));
+        assert_eq!(network.graph.edges().len(), 1);
+        assert_eq!(network.graph.get_node(cos).template.to_string(), "Cos");
+        assert_eq!(
+            network.shapes.shape_positions[&constant],
+            Point::new(0., 0.)
+        );
+        assert!(network.annotations.is_empty());
+
+        assert!(network.jump_to_history(2));
+        assert!(network.graph.edges().is_empty());
+        assert!(!network.shapes.shape_positions.contains_key(&cos));
+        assert_eq!(
+            network.shapes.shape_positions[&constant],
+            Point::new(5., 5.)
+        );
+        assert_eq!(network.annotations.len(), 1);
+        assert!(!network.jump_to_history(3));
+    }
+
+    #[test]
+    fn undo_delete_restores_drawing_order() {
+        let mut network = Network::default();
+        let ids: Vec<_> = (0..3).map(|_| add(&mut network, RustNode::Sin)).collect();
+        network.commit_edit(None);
+        let order: Vec<_> = network.shapes.shape_positions.keys().copied().collect();
+
+        network.begin_edit("Delete");
+        network.remove_node(ids[1]);
+        network.commit_edit(None);
+        network.jump_to_history(1);
+        assert!(network.shapes.shape_positions.keys().eq(&order));
+    }
+
+    #[test]
+    fn drags_are_recorded_as_one_move() {
+        let mut network = Network::default();
+        let sin = add(&mut network, RustNode::Sin);
+        network.commit_edit(None);
+
+        network.begin_edit("Move nodes");
+        for x in 1..=10 {
+            network.move_node(sin, Point::new(x as f32, 0.));
+        }
+        // Changes outside of recorded edits, like compute results, aren't part of the step
+        network.graph.get_mut_node(sin).template = NodeTemplate::RustNode(RustNode::Cos);
+        network.commit_edit(None);
+
+        let step = network.history.steps().last().unwrap();
+        assert!(matches!(
+            step.edits[..],
+            [Edit::MoveNode { before, after, .. }]
+                if before == Point::new(0., 0.) && after == Point::new(10., 0.)
+        ));
+
+        // Moving back to the start is not a change
+        network.begin_edit("Move nodes");
+        network.move_node(sin, Point::new(0., 0.));
+        network.move_node(sin, Point::new(10., 0.));
+        network.commit_edit(None);
+        assert_eq!(network.history.position(), 2);
+    }
+
+    #[test]
+    fn continuous_edits_are_grouped() {
+        let mut network = Network::default();
+        let constant = add(&mut network, RustNode::Constant(0.));
+        network.commit_edit(None);
+
+        for value in 1..=5 {
+            network.begin_edit("Edit Constant");
+            network.set_template(
+                constant,
+                NodeTemplate::RustNode(RustNode::Constant(value as f64)),
+            );
+            network.commit_edit(None);
+        }
+        assert_eq!(network.history.position(), 2);
+
+        network.jump_to_history(1);
+        assert_eq!(
+            network.graph.get_node(constant).template,
+            NodeTemplate::RustNode(RustNode::Constant(0.))
+        );
+    }
+
+    #[test]
+    fn depth_keeps_recent_steps() {
+        let mut network = Network::default();
+
+        for n in 0..5 {
+            network.begin_edit(format!("Add {n}"));
+            add(&mut network, RustNode::Sin);
+            network.commit_edit(Some(3));
+        }
+        let history = &network.history;
+        let labels: Vec<_> = history.steps().map(|step| step.label.as_str()).collect();
+        assert_eq!(labels, ["Add 2", "Add 3", "Add 4"]);
+
+        assert!(network.jump_to_history(0));
+        assert_eq!(network.graph.nodes_ref(), [0, 1]);
+        assert_eq!(network.history.redo_len(), 3);
+        assert!(network.jump_to_history(2));
+        assert_eq!(network.graph.nodes_ref(), [0, 1, 2, 3]);
+    }
+}
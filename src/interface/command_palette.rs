@@ -0,0 +1,279 @@
+use crate::app::Message;
+use crate::gui_node::GUINode;
+use crate::nodes::NodeTemplate;
+use crate::project::{NodeTree, Project};
+use crate::style;
+use crate::style::container::rounded_box;
+use derive_more::Display;
+use iced::widget::{button, column, container, horizontal_rule, row, scrollable, text, text_input};
+use iced::{Element, Length::Fill, Shrink};
+use itertools::Itertools;
+use strum::{EnumIter, IntoEnumIterator};
+
+/// Maximum number of search results shown
+const MAX_RESULTS: usize = 12;
+/// Score added to recently chosen items, so they are preferred over equally good matches
+const RECENT_BONUS: i32 = 4;
+
+pub fn palette_input_id() -> text_input::Id {
+    text_input::Id::new("command_palette")
+}
+
+/// Application commands that can be run from the command palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter)]
+pub enum Command {
+    #[display("Save network")]
+    Save,
+    #[display("Load network")]
+    Load,
+    #[display("New network")]
+    New,
+    #[display("Compute all nodes")]
+    ComputeAll,
+    #[display("Reload nodes")]
+    ReloadNodes,
+    #[display("Auto layout")]
+    AutoLayout,
+    #[display("Zoom to fit")]
+    ZoomToFit,
+    #[display("Toggle snap grid")]
+    ToggleGrid,
+    #[display("Toggle history")]
+    ToggleHistory,
+    #[display("Toggle debug view")]
+    ToggleDebug,
+    #[display("Add note")]
+    AddNote,
+    #[display("Add frame")]
+    AddFrame,
+    #[display("Open dashboard")]
+    OpenDashboard,
+}
+
+impl Command {
+    pub fn message(self) -> Message {
+        match self {
+            Command::Save => Message::Save,
+            Command::Load => Message::Load,
+            Command::New => Message::New,
+            Command::ComputeAll => Message::ComputeAll,
+            Command::ReloadNodes => Message::ReloadNodes,
+            Command::AutoLayout => Message::AutoLayout,
+            Command::ZoomToFit => Message::ZoomToFit,
+            Command::ToggleGrid => Message::ToggleGrid,
+            Command::ToggleHistory => Message::ToggleHistory,
+            Command::ToggleDebug => Message::ToggleDebug,
+            Command::AddNote => Message::AddNote,
+            Command::AddFrame => Message::AddFrame,
+            Command::OpenDashboard => Message::OpenDashboard,
+        }
+    }
+}
+
+/// Something that can be chosen in the command palette
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaletteItem {
+    /// A node template, and the path of groups it is found in
+    Node(String, NodeTemplate),
+    Command(Command),
+}
+
+impl PaletteItem {
+    /// Identifies the item in the list of recent choices
+    pub fn key(&self) -> String {
+        match self {
+            PaletteItem::Node(path, template) => format!("node:{path}/{}", template.name()),
+            PaletteItem::Command(command) => format!("command:{command}"),
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            PaletteItem::Node(_, template) => template.name(),
+            PaletteItem::Command(command) => command.to_string(),
+        }
+    }
+
+    fn detail(&self) -> &str {
+        match self {
+            PaletteItem::Node(path, _) => path,
+            PaletteItem::Command(_) => "command",
+        }
+    }
+
+    pub fn message(&self) -> Message {
+        match self {
+            PaletteItem::Node(_, template) => Message::AddNode(template.clone()),
+            PaletteItem::Command(command) => command.message(),
+        }
+    }
+}
+
+/// All node templates in every project, followed by all commands
+pub fn palette_items(projects: &[Project]) -> Vec<PaletteItem> {
+    fn flatten(tree: &NodeTree<NodeTemplate>, path: &str, items: &mut Vec<PaletteItem>) {
+        match tree {
+            NodeTree::Leaf(template) => {
+                items.push(PaletteItem::Node(path.into(), template.clone()))
+            }
+            NodeTree::Group(name, trees) => {
+                let path = match path.is_empty() {
+                    true => name.clone(),
+                    false => format!("{path}/{name}"),
+                };
+                trees.iter().for_each(|tree| flatten(tree, &path, items));
+            }
+        }
+    }
+
+    let mut items = vec![];
+    projects
+        .iter()
+        .flat_map(|prj| &prj.node_tree)
+        .for_each(|tree| flatten(tree, "", &mut items));
+    items.extend(Command::iter().map(PaletteItem::Command));
+    items
+}
+
+/// Score how well `query` matches `candidate`, if all of the query's characters appear in order.
+/// Consecutive characters and characters at the start of words score higher
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let index = next + candidate[next..].iter().position(|c| *c == q)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == index) {
+            score += 4;
+        } else if previous.is_some() {
+            // Penalize gaps between matched characters
+            score -= (index - next).min(3) as i32;
+        }
+        if index == 0 || !candidate[index - 1].is_alphanumeric() {
+            score += 6;
+        }
+        previous = Some(index);
+        next = index + 1;
+    }
+    Some(score)
+}
+
+/// Items matching `query`, best first.
+/// Without a query, recently chosen items are listed first
+pub fn search<'a>(
+    items: &'a [PaletteItem],
+    query: &str,
+    recent: &[String],
+) -> Vec<&'a PaletteItem> {
+    let recent_rank = |item: &PaletteItem| {
+        let key = item.key();
+        recent.iter().position(|r| *r == key)
+    };
+    if query.trim().is_empty() {
+        return items
+            .iter()
+            .sorted_by_key(|item| recent_rank(item).unwrap_or(usize::MAX))
+            .take(MAX_RESULTS)
+            .collect();
+    }
+    items
+        .iter()
+        .filter_map(|item| {
+            // Match the name first, then fall back to including the group path
+            let score = fuzzy_score(query, &item.label()).or_else(|| {
+                fuzzy_score(query, &format!("{} {}", item.detail(), item.label())).map(|s| s - 2)
+            })?;
+            let bonus = recent_rank(item).map_or(0, |_| RECENT_BONUS);
+            Some((score + bonus, item))
+        })
+        .sorted_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .cmp(a_score)
+                .then(a.label().len().cmp(&b.label().len()))
+        })
+        .map(|(_, item)| item)
+        .take(MAX_RESULTS)
+        .collect()
+}
+
+/// Search box with the list of results, `selected` is highlighted and chosen on enter.
+/// `nodes_only` when searching for a node to connect to a dragged wire
+pub fn command_palette<'a>(
+    query: &str,
+    nodes_only: bool,
+    results: &[&PaletteItem],
+    selected: usize,
+) -> Element<'a, Message> {
+    let result_row = |(i, item): (usize, &&PaletteItem)| {
+        button(
+            row![
+                text(item.label()).width(Fill),
+                text(item.detail().to_string())
+                    .size(12.)
+                    .style(text::secondary)
+            ]
+            .padding([0., 4.]),
+        )
+        .padding(2.)
+        .width(Fill)
+        .on_press(Message::PaletteChoose((*item).clone()))
+        .style(move |theme, status| match i == selected {
+            true => style::button::list(theme, button::Status::Hovered),
+            false => style::button::list(theme, status),
+        })
+        .into()
+    };
+
+    container(column![
+        text_input(
+            match nodes_only {
+                true => "Search nodes to connect...",
+                false => "Search nodes and commands...",
+            },
+            query
+        )
+        .id(palette_input_id())
+        .on_input(Message::PaletteQuery)
+        .on_submit(Message::PaletteSubmit)
+        .padding(5.),
+        horizontal_rule(3.0),
+        container(scrollable(
+            column(results.iter().enumerate().map(result_row)).spacing(2.)
+        ))
+        .padding(2.0)
+    ])
+    .style(rounded_box)
+    .width(400.)
+    .height(Shrink)
+    .into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fuzzy_matching() {
+        assert_eq!(fuzzy_score("xyz", "Multiply"), None);
+        assert!(fuzzy_score("mul", "Multiply").is_some());
+        // Consecutive matches and word starts are preferred
+        assert!(fuzzy_score("mul", "Multiply") > fuzzy_score("mul", "cumulative"));
+        assert!(fuzzy_score("ca", "compute all") > fuzzy_score("ca", "scale"));
+    }
+
+    #[test]
+    fn recent_items_first() {
+        let items: Vec<_> = Command::iter().map(PaletteItem::Command).collect();
+        let recent = [PaletteItem::Command(Command::AddNote).key()];
+        assert_eq!(
+            search(&items, "", &recent)[0],
+            &PaletteItem::Command(Command::AddNote)
+        );
+        assert_eq!(
+            search(&items, "save", &recent)[0],
+            &PaletteItem::Command(Command::Save)
+        );
+    }
+}
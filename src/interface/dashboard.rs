@@ -0,0 +1,226 @@
+use derive_more::Display;
+use iced::widget::{button, column, container, horizontal_space, row, scrollable, text, Column};
+use iced::{Alignment::Center, Element, Length::Fill};
+use serde::{Deserialize, Serialize};
+
+use crate::app::Message;
+use crate::gui_node::GUINode;
+use crate::network::Network;
+use crate::style;
+use crate::style::container::rounded_box;
+use crate::widget::shapes::ShapeId;
+
+/// Height of a viewer on the dashboard
+const VIEW_HEIGHT: f32 = 320.;
+const MAX_COLUMNS: usize = 6;
+const TEXT_SIZE: f32 = 12.;
+
+/// Which part of a node is shown on the dashboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, Serialize, Deserialize)]
+pub enum DashboardPart {
+    #[display("view")]
+    View,
+    #[display("parameters")]
+    Parameters,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DashboardItem {
+    pub node: ShapeId,
+    pub part: DashboardPart,
+}
+
+/// Viewers and node parameters arranged in a grid, for presenting results apart from the graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dashboard {
+    pub columns: usize,
+    /// Items in reading order, filling each row of the grid before the next
+    pub items: Vec<DashboardItem>,
+}
+
+impl Default for Dashboard {
+    fn default() -> Self {
+        Dashboard {
+            columns: 2,
+            items: vec![],
+        }
+    }
+}
+
+impl Dashboard {
+    /// Add an item to the end of the grid, unless it is already on the dashboard
+    pub fn add(&mut self, node: ShapeId, part: DashboardPart) {
+        let item = DashboardItem { node, part };
+        if !self.items.contains(&item) {
+            self.items.push(item);
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.items.len() {
+            self.items.remove(index);
+        }
+    }
+
+    /// Move an item by `offset` places in the grid, stopping at either end
+    pub fn move_item(&mut self, index: usize, offset: isize) {
+        if index < self.items.len() {
+            let to = index
+                .saturating_add_signed(offset)
+                .min(self.items.len() - 1);
+            let item = self.items.remove(index);
+            self.items.insert(to, item);
+        }
+    }
+
+    pub fn set_columns(&mut self, columns: usize) {
+        self.columns = columns.clamp(1, MAX_COLUMNS);
+    }
+}
+
+/// A node's view filling its own window
+pub fn viewer_window(network: &Network, id: ShapeId) -> Element<'_, Message> {
+    if !network.shapes.shape_positions.contains_key(&id) {
+        return container(text("This node has been deleted"))
+            .center(Fill)
+            .into();
+    }
+    let node = network.graph.get_node(id);
+    column![
+        container(node.template.view(id, network.graph.get_input_data(&id)))
+            .center(Fill)
+            .padding(5.),
+        row![
+            node.status.icon(),
+            node.status.text_element().size(TEXT_SIZE)
+        ]
+        .align_y(Center)
+        .spacing(4.)
+        .padding([2., 5.]),
+    ]
+    .into()
+}
+
+/// The dashboard grid, with controls for arranging its items
+pub fn dashboard_view(network: &Network) -> Element<'_, Message> {
+    let dashboard = &network.dashboard;
+    let small_button = |label: &'static str, message: Option<Message>| {
+        button(text(label).size(TEXT_SIZE))
+            .padding([1, 4])
+            .style(style::button::secondary)
+            .on_press_maybe(message)
+    };
+    let columns = dashboard.columns;
+    let header = row![
+        text("Dashboard").size(20.),
+        horizontal_space(),
+        text(format!("columns: {columns}")),
+        small_button(
+            "-",
+            (columns > 1).then_some(Message::DashboardColumns(columns - 1))
+        ),
+        small_button(
+            "+",
+            (columns < MAX_COLUMNS).then_some(Message::DashboardColumns(columns + 1))
+        ),
+    ]
+    .spacing(4.)
+    .align_y(Center);
+
+    if dashboard.items.is_empty() {
+        return column![
+            header,
+            container(text(
+                "Select a node and add its view or parameters to the dashboard from the side bar"
+            ))
+            .center(Fill)
+        ]
+        .padding(10.)
+        .into();
+    }
+
+    //// Cells, skipping any items whose node has been deleted
+    let last = dashboard.items.len() - 1;
+    let cells: Vec<Element<Message>> = dashboard
+        .items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| network.shapes.shape_positions.contains_key(&item.node))
+        .map(|(index, item)| {
+            let node = network.graph.get_node(item.node);
+            let input_data = network.graph.get_input_data(&item.node);
+            let title = row![
+                text(format!("{} {}", node.template.name(), item.part)).size(TEXT_SIZE),
+                horizontal_space(),
+                small_button(
+                    "<",
+                    (index > 0).then_some(Message::DashboardMove(index, -1))
+                ),
+                small_button(
+                    ">",
+                    (index < last).then_some(Message::DashboardMove(index, 1))
+                ),
+                small_button("x", Some(Message::DashboardRemove(index))),
+            ]
+            .spacing(2.)
+            .align_y(Center);
+            let content: Element<Message> = match item.part {
+                DashboardPart::View => container(node.template.view(item.node, input_data))
+                    .center_x(Fill)
+                    .height(VIEW_HEIGHT)
+                    .into(),
+                DashboardPart::Parameters => node
+                    .template
+                    .config_view(item.node, input_data)
+                    .unwrap_or(text("No parameters").into()),
+            };
+            container(column![title, content].spacing(5.))
+                .style(rounded_box)
+                .padding(5.)
+                .width(Fill)
+                .into()
+        })
+        .collect();
+
+    //// Grid, filling the last row with space so that the columns line up
+    let mut cells = cells.into_iter().peekable();
+    let mut rows = Column::new().spacing(10.);
+    while cells.peek().is_some() {
+        let mut cells_row: Vec<Element<Message>> = cells.by_ref().take(columns).collect();
+        cells_row.extend((cells_row.len()..columns).map(|_| horizontal_space().into()));
+        rows = rows.push(row(cells_row).spacing(10.));
+    }
+
+    column![header, scrollable(rows).height(Fill)]
+        .spacing(10.)
+        .padding(10.)
+        .into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn arrange_items() {
+        let mut dashboard = Dashboard::default();
+        dashboard.add(1, DashboardPart::View);
+        dashboard.add(2, DashboardPart::Parameters);
+        dashboard.add(1, DashboardPart::View);
+        dashboard.add(1, DashboardPart::Parameters);
+        assert_eq!(dashboard.items.len(), 3);
+
+        dashboard.move_item(0, 5);
+        dashboard.move_item(1, -3);
+        let order: Vec<_> = dashboard.items.iter().map(|item| item.node).collect();
+        assert_eq!(order, [1, 2, 1]);
+        assert_eq!(dashboard.items[2].part, DashboardPart::View);
+
+        dashboard.remove(0);
+        dashboard.remove(10);
+        assert_eq!(dashboard.items.len(), 2);
+
+        dashboard.set_columns(0);
+        assert_eq!(dashboard.columns, 1);
+    }
+}
@@ -0,0 +1,51 @@
+use crate::app::Message;
+use crate::history::History;
+use crate::style;
+use iced::{
+    widget::{button, column, container, scrollable, text},
+    Element,
+    Length::Fill,
+};
+
+/// List of undo steps, clicking a step undoes or redoes the network to that point
+pub fn history_panel(history: &History) -> Element<'_, Message> {
+    let current = history.position();
+    let step_row = |label: String, position: usize| {
+        let label = match position == current {
+            true => text(label).style(text::primary),
+            false => text(label),
+        };
+        button(container(label).padding([0., 4.]))
+            .padding(0.)
+            .width(Fill)
+            .on_press(Message::JumpToHistory(position))
+            .style(move |theme, status| {
+                let style = style::button::list(theme, status);
+                // Steps that have been undone are dimmed
+                if position > current {
+                    button::Style {
+                        text_color: style.text_color.scale_alpha(0.5),
+                        ..style
+                    }
+                } else {
+                    style
+                }
+            })
+            .into()
+    };
+
+    scrollable(
+        column(
+            std::iter::once(step_row("Initial state".to_string(), 0)).chain(
+                history
+                    .steps()
+                    .enumerate()
+                    .map(|(i, step)| step_row(step.label.clone(), i + 1)),
+            ),
+        )
+        .spacing(2.)
+        .padding([10., 5.]),
+    )
+    .height(Fill)
+    .into()
+}
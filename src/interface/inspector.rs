@@ -0,0 +1,357 @@
+use std::mem::size_of;
+
+use iced::widget::scrollable::{Direction, Scrollbar};
+use iced::widget::{button, column, container, horizontal_space, row, scrollable, text, Column};
+use iced::{Alignment::Center, Element, Length::Fill};
+use ndarray::{ArrayD, ArrayViewD, Axis, IxDyn};
+use numpy::Complex64;
+
+use crate::app::Message;
+use crate::graph::PortRef;
+use crate::gui_node::PortDataContainer;
+use crate::nodes::port::PortData;
+use crate::style;
+
+/// Number of array rows shown at once in the table
+const PAGE_ROWS: usize = 50;
+/// Columns beyond this are left out of the table
+const MAX_COLUMNS: usize = 32;
+const CELL_WIDTH: f32 = 80.;
+const TEXT_SIZE: f32 = 12.;
+
+/// The wire being inspected, and which part of its data is shown in the table
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inspector {
+    /// Output port that the wire carries data from
+    pub port: PortRef,
+    /// Index into each leading axis, so that the last two axes can be shown as a table
+    pub slice: Vec<usize>,
+    pub first_row: usize,
+    /// Statistics of the data on the wire, computed when the data changes rather than every frame
+    pub stats: Option<Stats>,
+}
+
+impl Inspector {
+    pub fn new(port: PortRef, data: Option<&PortDataContainer>) -> Self {
+        let mut inspector = Inspector {
+            port,
+            slice: vec![],
+            first_row: 0,
+            stats: None,
+        };
+        inspector.update_stats(data);
+        inspector
+    }
+
+    /// Summarize new data on the inspected wire
+    pub fn update_stats(&mut self, data: Option<&PortDataContainer>) {
+        self.stats = data.and_then(|data| stats(&data.read().unwrap()));
+    }
+}
+
+/// Summary statistics of real values, or the magnitude of complex values
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub nan_count: usize,
+}
+
+impl Stats {
+    /// Statistics of the values that are not NaN
+    pub fn new(values: impl Iterator<Item = f64>) -> Option<Self> {
+        let (mut min, mut max, mut sum, mut count, mut nan_count) =
+            (f64::INFINITY, f64::NEG_INFINITY, 0., 0usize, 0usize);
+        for value in values {
+            if value.is_nan() {
+                nan_count += 1;
+            } else {
+                min = min.min(value);
+                max = max.max(value);
+                sum += value;
+                count += 1;
+            }
+        }
+        (count + nan_count > 0).then_some(Stats {
+            min,
+            max,
+            mean: sum / count as f64,
+            nan_count,
+        })
+    }
+}
+
+/// Element type, shape and size in memory of port data
+pub fn dtype_shape_bytes(data: &PortData) -> (&'static str, Vec<usize>, usize) {
+    match data {
+        PortData::Integer(_) => ("i64", vec![], size_of::<i64>()),
+        PortData::Real(_) => ("f64", vec![], size_of::<f64>()),
+        PortData::Complex(_) => ("c128", vec![], size_of::<Complex64>()),
+        PortData::ArrayInteger(a) => ("i64", a.shape().to_vec(), a.len() * size_of::<i64>()),
+        PortData::ArrayReal(a) | PortData::Dynamic(a) => {
+            ("f64", a.shape().to_vec(), a.len() * size_of::<f64>())
+        }
+        PortData::ArrayComplex(a) => ("c128", a.shape().to_vec(), a.len() * size_of::<Complex64>()),
+        PortData::Object(fields) => (
+            "object",
+            vec![fields.len()],
+            fields
+                .values()
+                .map(|field| dtype_shape_bytes(field).2)
+                .sum(),
+        ),
+    }
+}
+
+pub fn stats(data: &PortData) -> Option<Stats> {
+    match data {
+        PortData::Integer(v) => Stats::new([*v as f64].into_iter()),
+        PortData::Real(v) => Stats::new([*v].into_iter()),
+        PortData::Complex(v) => Stats::new([v.norm()].into_iter()),
+        PortData::ArrayInteger(a) => Stats::new(a.iter().map(|v| *v as f64)),
+        PortData::ArrayReal(a) | PortData::Dynamic(a) => Stats::new(a.iter().copied()),
+        PortData::ArrayComplex(a) => Stats::new(a.iter().map(|v| v.norm())),
+        PortData::Object(_) => None,
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    match bytes {
+        b if b < 1 << 10 => format!("{b} B"),
+        b if b < 1 << 20 => format!("{:.1} KiB", b as f64 / (1 << 10) as f64),
+        b if b < 1 << 30 => format!("{:.1} MiB", b as f64 / (1 << 20) as f64),
+        b => format!("{:.1} GiB", b as f64 / (1 << 30) as f64),
+    }
+}
+
+/// Panel with a summary of the data on a wire, and a table of its values
+pub fn inspector_panel<'a>(
+    inspector: &Inspector,
+    node_name: String,
+    data: Option<&PortDataContainer>,
+) -> Element<'a, Message> {
+    let header = row![
+        text(format!("{node_name} : {}", inspector.port.name)).style(text::primary),
+        horizontal_space(),
+        button(text("close").size(TEXT_SIZE))
+            .padding([1, 4])
+            .style(style::button::secondary)
+            .on_press(Message::CloseInspector)
+    ]
+    .align_y(Center);
+
+    let Some(data) = data else {
+        return container(column![header, text("No data, the node has not run")].spacing(5.))
+            .padding(5.)
+            .into();
+    };
+    let data = data.read().unwrap();
+
+    let (dtype, shape, bytes) = dtype_shape_bytes(&data);
+    let magnitude = matches!(*data, PortData::Complex(_) | PortData::ArrayComplex(_));
+    let summary = column![
+        text(format!("dtype: {dtype}")),
+        text(format!("shape: {shape:?}")),
+        text(format!("memory: {}", format_bytes(bytes))),
+    ]
+    .extend(inspector.stats.as_ref().map(|stats| {
+        let label = if magnitude { "|z| " } else { "" };
+        column![
+            text(format!("{label}min: {:.4}", stats.min)),
+            text(format!("{label}max: {:.4}", stats.max)),
+            text(format!("{label}mean: {:.4}", stats.mean)),
+            text(format!("NaN: {}", stats.nan_count)),
+        ]
+        .into()
+    }))
+    .spacing(2.)
+    .width(160.);
+
+    let table = match &*data {
+        PortData::Integer(v) => text(v.to_string()).into(),
+        PortData::Real(v) => text(format_real(v)).into(),
+        PortData::Complex(v) => text(format_complex(v)).into(),
+        PortData::ArrayInteger(a) => array_table(a, inspector, |v| v.to_string()),
+        PortData::ArrayReal(a) | PortData::Dynamic(a) => array_table(a, inspector, format_real),
+        PortData::ArrayComplex(a) => array_table(a, inspector, format_complex),
+        PortData::Object(fields) => column(fields.iter().map(|(name, field)| {
+            let (dtype, shape, _) = dtype_shape_bytes(field);
+            text(format!("{name}: {dtype} {shape:?}")).into()
+        }))
+        .into(),
+    };
+
+    container(column![header, row![summary, table].spacing(10.).height(Fill)].spacing(5.))
+        .padding(5.)
+        .into()
+}
+
+fn format_real(v: &f64) -> String {
+    format!("{v:.4}")
+}
+
+fn format_complex(v: &Complex64) -> String {
+    format!("{:.3}{:+.3}i", v.re, v.im)
+}
+
+/// The 2D (or 1D) view of an array, after indexing its leading axes with `slice`.
+/// If a leading axis is empty there is nothing to index, and the view has no rows
+fn sliced<'b, T>(array: &'b ArrayD<T>, slice: &[usize]) -> ArrayViewD<'b, T> {
+    let mut view = array.view();
+    let mut axis = 0;
+    while view.ndim() > 2 {
+        let index = slice.get(axis).copied().unwrap_or(0);
+        let len = view.len_of(Axis(0));
+        if len == 0 {
+            let columns = view.shape()[view.ndim() - 1];
+            return ArrayViewD::from_shape(IxDyn(&[0, columns]), &[])
+                .expect("an empty array fits any shape with no elements");
+        }
+        view = view.index_axis_move(Axis(0), index.min(len.saturating_sub(1)));
+        axis += 1;
+    }
+    view
+}
+
+/// A page of rows of an array, with controls to pick the slice of any leading axes
+fn array_table<'a, T>(
+    array: &ArrayD<T>,
+    inspector: &Inspector,
+    format: impl Fn(&T) -> String,
+) -> Element<'a, Message> {
+    let cell = |content: String| {
+        container(text(content).size(TEXT_SIZE))
+            .width(CELL_WIDTH)
+            .into()
+    };
+    let index_cell = |i: usize| {
+        container(text(i.to_string()).size(TEXT_SIZE).style(text::secondary))
+            .width(CELL_WIDTH / 2.)
+            .into()
+    };
+
+    //// Leading axis controls
+    let leading = array.ndim().saturating_sub(2);
+    let axis_controls = row((0..leading).map(|axis| {
+        let len = array.len_of(Axis(axis));
+        let index = inspector
+            .slice
+            .get(axis)
+            .copied()
+            .unwrap_or(0)
+            .min(len.saturating_sub(1));
+        row![
+            text(format!("axis {axis}:")).size(TEXT_SIZE),
+            button(text("-").size(TEXT_SIZE))
+                .padding([0, 4])
+                .on_press_maybe((index > 0).then(|| Message::InspectSlice(axis, index - 1))),
+            text(format!("{index}/{len}")).size(TEXT_SIZE),
+            button(text("+").size(TEXT_SIZE))
+                .padding([0, 4])
+                .on_press_maybe((index + 1 < len).then(|| Message::InspectSlice(axis, index + 1))),
+        ]
+        .spacing(3.)
+        .align_y(Center)
+        .into()
+    }))
+    .spacing(10.);
+
+    //// Table of the current page of rows
+    let view = sliced(array, &inspector.slice);
+    let (rows, columns) = match view.ndim() {
+        0 => (1, 1),
+        1 => (view.len(), 1),
+        _ => (view.len_of(Axis(0)), view.len_of(Axis(1))),
+    };
+    let first_row = inspector.first_row.min(rows.saturating_sub(1));
+    let last_row = (first_row + PAGE_ROWS).min(rows);
+    let shown_columns = columns.min(MAX_COLUMNS);
+    let value = |r: usize, c: usize| match view.ndim() {
+        0 => view.iter().next().map(&format),
+        1 => view.get(r).map(&format),
+        _ => view.get([r, c].as_slice()).map(&format),
+    };
+
+    let column_header = row(
+        std::iter::once(horizontal_space().width(CELL_WIDTH / 2.).into())
+            .chain((0..shown_columns).map(index_cell)),
+    );
+    let table = Column::new()
+        .push(column_header)
+        .extend((first_row..last_row).map(|r| {
+            row(std::iter::once(index_cell(r))
+                .chain((0..shown_columns).map(|c| cell(value(r, c).unwrap_or_default()))))
+            .into()
+        }));
+
+    let page_controls = row![
+        button(text("<").size(TEXT_SIZE))
+            .padding([0, 4])
+            .on_press_maybe(
+                (first_row > 0).then(|| Message::InspectRows(first_row.saturating_sub(PAGE_ROWS)))
+            ),
+        text(format!(
+            "rows {first_row}..{last_row} of {rows}{}",
+            match shown_columns < columns {
+                true => format!(", {shown_columns} of {columns} columns"),
+                false => String::new(),
+            }
+        ))
+        .size(TEXT_SIZE),
+        button(text(">").size(TEXT_SIZE))
+            .padding([0, 4])
+            .on_press_maybe((last_row < rows).then(|| Message::InspectRows(last_row))),
+    ]
+    .spacing(5.)
+    .align_y(Center);
+
+    column![
+        axis_controls,
+        page_controls,
+        scrollable(table).direction(Direction::Both {
+            vertical: Scrollbar::default(),
+            horizontal: Scrollbar::default(),
+        })
+    ]
+    .spacing(5.)
+    .into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ndarray::ArrayD;
+
+    #[test]
+    fn summarize_arrays() {
+        let data = PortData::ArrayReal(
+            ArrayD::from_shape_vec(IxDyn(&[2, 2]), vec![1., f64::NAN, 3., -1.]).unwrap(),
+        );
+        assert_eq!(dtype_shape_bytes(&data), ("f64", vec![2, 2], 32));
+        assert_eq!(
+            stats(&data),
+            Some(Stats {
+                min: -1.,
+                max: 3.,
+                mean: 1.,
+                nan_count: 1
+            })
+        );
+    }
+
+    #[test]
+    fn slice_leading_axes() {
+        let array = ArrayD::from_shape_fn(IxDyn(&[3, 4, 5]), |i| i[0] * 100 + i[1] * 10 + i[2]);
+        let view = sliced(&array, &[2]);
+        assert_eq!(view.shape(), &[4, 5]);
+        assert_eq!(view[[1, 3]], 213);
+        // Indices beyond the end of an axis are clamped
+        assert_eq!(sliced(&array, &[7])[[0, 0]], 200);
+        // Empty leading axes give an empty view
+        let empty = ArrayD::<f64>::zeros(IxDyn(&[0, 3, 4]));
+        assert_eq!(sliced(&empty, &[]).shape(), &[0, 4]);
+        let empty = ArrayD::<f64>::zeros(IxDyn(&[2, 0, 3, 4]));
+        assert_eq!(sliced(&empty, &[1]).shape(), &[0, 4]);
+    }
+}
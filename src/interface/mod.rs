@@ -1,5 +1,9 @@
 use std::fmt::Debug;
 pub mod add_node;
+pub mod command_palette;
+pub mod dashboard;
+pub mod history;
+pub mod inspector;
 pub mod node;
 pub mod node_config;
 pub mod numeric_input;
@@ -15,8 +15,7 @@ use iced::{
 
 use super::port::port_view;
 
-// TODO: remove hard-coded node sizes, use size specified in node-template,
-// or on an individual node level, so that nodes can be dynamically resized
+// TODO: remove hard-coded node sizes, use size specified in node-template
 pub const INNER_NODE_WIDTH: f32 = 120.;
 pub const INNER_NODE_HEIGHT: f32 = 60.;
 pub const PORT_RADIUS: f32 = 8.5;
@@ -30,16 +29,18 @@ pub fn default_node_size() -> iced::Size {
 }
 
 impl App {
-    pub fn node_content(&self, id: u32) -> Element<Message, Theme, Renderer> {
+    pub fn node_content(&self, id: u32) -> Element<'_, Message, Theme, Renderer> {
         let node = self.network.graph.get_node(id);
         let is_selected = self.network.selected_shapes.contains(&id);
 
         let node_style = move |node: &NodeData, t: &Theme| {
             let color = match &node.status {
-                NodeStatus::Idle | NodeStatus::Running(_) => match is_selected {
-                    true => t.extended_palette().primary.strong.color,
-                    false => t.extended_palette().secondary.strong.color,
-                },
+                NodeStatus::Idle | NodeStatus::Running(_) | NodeStatus::Waiting => {
+                    match is_selected {
+                        true => t.extended_palette().primary.strong.color,
+                        false => t.extended_palette().secondary.strong.color,
+                    }
+                }
                 NodeStatus::Error(_node_error) => match is_selected {
                     true => t.extended_palette().danger.base.color,
                     false => t.extended_palette().danger.weak.color,
@@ -72,7 +73,7 @@ impl App {
 
         //// Node
         let input_data = self.network.graph.get_input_data(&id);
-        let node_size = node.template.node_size();
+        let node_size = self.network.node_size(id);
         let node_view = node.template.view(id, input_data);
 
         //// Ports
@@ -94,6 +95,7 @@ impl App {
         )
         .width(node_size.width)
         .height(node_size.height)
+        .on_resize_press(Message::NodeResizePress(id))
         .into();
         content
     }
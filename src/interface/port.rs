@@ -1,11 +1,13 @@
 use iced::{
-    border, color,
+    border, color, mouse,
     widget::{
-        column, container, container::background, mouse_area, rich_text, row, span, text, tooltip,
-        vertical_space, Row,
+        canvas, column, container, container::background, mouse_area, rich_text, row, span, text,
+        tooltip, vertical_space, Row,
     },
     Alignment::Center,
-    Border, Element, Size,
+    Border, Element,
+    Length::Fill,
+    Rectangle, Renderer, Size, Theme,
 };
 
 use crate::{
@@ -45,7 +47,7 @@ pub fn port_view<'a>(
 
             Pin::new(tooltip(
                 mouse_area(
-                    custom_button::Button::new("")
+                    custom_button::Button::new(port_glyph(&port_type, app_theme))
                         .on_press(Message::PortPress(in_port.clone()))
                         .on_drag(Message::OnMove)
                         .on_right_press(Message::PortDelete(in_port.clone()))
@@ -79,7 +81,7 @@ pub fn port_view<'a>(
 
             Pin::new(
                 mouse_area(tooltip(
-                    custom_button::Button::new(vertical_space())
+                    custom_button::Button::new(port_glyph(&port_type, app_theme))
                         .on_press(Message::PortPress(out_port.clone()))
                         .on_drag(Message::OnMove)
                         .on_right_press(Message::PortDelete(out_port.clone()))
@@ -107,10 +109,93 @@ fn port_style(
 ) -> custom_button::Style {
     let color_pair = port_color_pair(&port_type, app_theme);
     let mut style = custom_button::custom(s, color_pair.0, color_pair.1);
-    style.border.radius = border::radius(100.);
+    // Arrays with a declared shape are square, everything else round
+    style.border.radius = match port_type {
+        PortType::Shaped(..) => border::radius(3.),
+        _ => border::radius(100.),
+    };
     style
 }
 
+/// Mark inside a port showing the number of dimensions it was declared with
+fn port_glyph<'a>(port_type: &PortType, app_theme: &AppTheme) -> Element<'a, Message> {
+    match port_type {
+        PortType::Shaped(_, shape) => canvas(RankGlyph {
+            rank: shape.rank(),
+            color: app_theme.background.base_color.into(),
+        })
+        .width(Fill)
+        .height(Fill)
+        .into(),
+        _ => vertical_space().into(),
+    }
+}
+
+/// A line for 1D, a square for 2D, stacked squares for 3D and a dot per axis above that
+struct RankGlyph {
+    rank: usize,
+    color: iced::Color,
+}
+
+impl<Message> canvas::Program<Message> for RankGlyph {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let center = frame.center();
+        let half = bounds.width.min(bounds.height) / 4.;
+        let stroke = canvas::Stroke::default()
+            .with_color(self.color)
+            .with_width(1.5);
+        let square = |offset: f32| {
+            canvas::Path::rectangle(
+                iced::Point::new(center.x - half + offset, center.y - half - offset),
+                Size::new(half * 2., half * 2.),
+            )
+        };
+        match self.rank {
+            0 => {}
+            1 => frame.stroke(
+                &canvas::Path::line(
+                    iced::Point::new(center.x - half, center.y),
+                    iced::Point::new(center.x + half, center.y),
+                ),
+                stroke,
+            ),
+            2 => frame.stroke(&square(0.), stroke),
+            3 => {
+                frame.stroke(&square(-half / 2.), stroke);
+                frame.fill(&square(half / 2.), self.color);
+            }
+            rank => {
+                let columns = rank.div_ceil(2);
+                let spacing = half * 2. / columns as f32;
+                for i in 0..rank {
+                    let (row, column) = (i / columns, i % columns);
+                    frame.fill(
+                        &canvas::Path::circle(
+                            iced::Point::new(
+                                center.x - half + spacing * (column as f32 + 0.5),
+                                center.y + half * (row as f32 - 0.5),
+                            ),
+                            spacing / 3.,
+                        ),
+                        self.color,
+                    );
+                }
+            }
+        }
+        vec![frame.into_geometry()]
+    }
+}
+
 /// Get (base, highlight) color pair for port type
 fn port_color_pair(port_type: &PortType, app_theme: &AppTheme) -> (iced::Color, iced::Color) {
     match port_type {
@@ -126,6 +211,7 @@ fn port_color_pair(port_type: &PortType, app_theme: &AppTheme) -> (iced::Color,
         PortType::ArrayComplex => (color!(36, 131, 123), color!(58, 169, 159)), //cyan
         PortType::Dynamic => (color!(175, 125, 41), color!(209, 150, 65)), //orange
         PortType::Object(_) => (color!(200, 160, 41), color!(229, 180, 65)), //yellow
+        PortType::Shaped(base, _) => port_color_pair(base, app_theme),
     }
 }
 
@@ -145,7 +231,11 @@ fn port_text<'a>(
 }
 
 /// Display summary of port information
-fn port_tooltip(port_name: String, port_type: PortType, app_theme: &AppTheme) -> Element<Message> {
+fn port_tooltip(
+    port_name: String,
+    port_type: PortType,
+    app_theme: &AppTheme,
+) -> Element<'_, Message> {
     port_tooltip_recurse(port_name, port_type, app_theme, true)
 }
 
@@ -154,7 +244,7 @@ fn port_tooltip_recurse(
     port_type: PortType,
     app_theme: &AppTheme,
     even: bool, // Switch between 2 background colors as objects are nested
-) -> Element<Message> {
+) -> Element<'_, Message> {
     let port_type_display = match port_type {
         // Recursive case
         PortType::Object(fields) => row![
@@ -1,10 +1,17 @@
 use crate::app::{App, Message};
 use crate::gui_node::GUINode;
+use crate::interface::dashboard::DashboardPart;
+use crate::interface::history::history_panel;
 use crate::interface::node::format_node_output;
 use crate::interface::{debug_format, SEPERATOR};
+use crate::keymap::Shortcut;
+use crate::network::align::Align;
+use crate::nodes::figure::ExportSize;
 use crate::style::button::{primary_icon, secondary_icon};
 use crate::style::icon::icon;
+use crate::widget::annotation::{AnnotationColor, AnnotationKind};
 use iced::*;
+use strum::IntoEnumIterator;
 use widget::{column, *};
 
 /// Create the sidebar view
@@ -31,66 +38,226 @@ pub fn side_bar(app: &App) -> Element<'_, Message> {
     //''
     //''
     //''
+    //// Tooltip naming an action, and its key chords from the keymap
+    let with_shortcut = |button: Button<'static, Message>, label: &str, shortcut: Shortcut| {
+        let chords = app.keymap.chords(shortcut);
+        let tip = match chords.is_empty() {
+            true => label.to_string(),
+            false => format!("{label} ({})", chords.join(", ")),
+        };
+        Element::from(tooltip(
+            button,
+            text(tip).size(12.),
+            tooltip::Position::Bottom,
+        ))
+    };
+
     let file_commands = row![
-        file_button('󰝒', Message::New),
-        file_button('󰝰', Message::Load),
-        file_button('󰆓', Message::Save),
+        with_shortcut(file_button('󰝒', Message::New), "New", Shortcut::New),
+        with_shortcut(file_button('󰝰', Message::Load), "Load", Shortcut::Load),
+        with_shortcut(file_button('󰆓', Message::Save), "Save", Shortcut::Save),
         file_button('󰃤', Message::ToggleDebug),
         file_button('󰏘', Message::TogglePaletteUI),
     ]
     .spacing(3.0);
 
     let undo = undo_button(
-        debug_format(&app.debug, '', app.network.undo_stack.len()),
-        !app.network.undo_stack.is_empty(),
+        debug_format(&app.debug, '', app.network.history.position()),
+        app.network.history.position() > 0,
         Message::Undo,
     );
     let redo = undo_button(
-        debug_format(&app.debug, '', app.network.redo_stack.len()),
-        !app.network.redo_stack.is_empty(),
+        debug_format(&app.debug, '', app.network.history.redo_len()),
+        app.network.history.redo_len() > 0,
         Message::Redo,
     );
-    let action_commands = row![horizontal_space(), undo, redo].spacing(4.0);
+    let history = undo_button('󰋚', true, Message::ToggleHistory);
+    let layout = undo_button(
+        '󰙅',
+        !app.network.shapes.shape_positions.is_empty(),
+        Message::AutoLayout,
+    );
+    let grid = undo_button(
+        match app.user_data.snap_to_grid() {
+            true => '󰋁',
+            false => '󰋂',
+        },
+        true,
+        Message::ToggleGrid,
+    );
+    let note = undo_button('󰎚', true, Message::AddNote);
+    let frame = undo_button('󰾂', true, Message::AddFrame);
+    let dashboard = tooltip(
+        undo_button('󰕮', true, Message::OpenDashboard),
+        text("Dashboard").size(12.),
+        tooltip::Position::Bottom,
+    );
+    let undo = with_shortcut(undo, "Undo", Shortcut::Undo);
+    let redo = with_shortcut(redo, "Redo", Shortcut::Redo);
+    let layout = with_shortcut(layout, "Auto layout", Shortcut::AutoLayout);
+    let grid = with_shortcut(grid, "Snap to grid", Shortcut::ToggleGrid);
+    let search = with_shortcut(
+        undo_button('󰍉', true, Message::OpenCommandPalette),
+        "Command palette",
+        Shortcut::CommandPalette,
+    );
+    let action_commands = row![search, horizontal_space(), undo, redo, history].spacing(4.0);
+    let canvas_commands = row![grid, layout, note, frame, dashboard].spacing(4.0);
+
+    //// Alignment of multiple selected nodes
+    let align_commands: Element<Message> = if app.network.selected_shapes.len() > 1 {
+        let align_button = |lbl: char, align: Align| {
+            tooltip(
+                undo_button(lbl, true, Message::Align(align)),
+                text(align.to_string()).size(12.),
+                tooltip::Position::Bottom,
+            )
+            .into()
+        };
+        column![
+            row([
+                align_button('󱇂', Align::Left),
+                align_button('󱇃', Align::CenterX),
+                align_button('󱇄', Align::Right),
+                align_button('󱥢', Align::DistributeX),
+            ])
+            .spacing(4.0),
+            row([
+                align_button('󱇇', Align::Top),
+                align_button('󱇆', Align::CenterY),
+                align_button('󱇅', Align::Bottom),
+                align_button('󱥣', Align::DistributeY),
+            ])
+            .spacing(4.0),
+        ]
+        .spacing(4.0)
+        .padding([2., 4.])
+        .into()
+    } else {
+        column![].into()
+    };
 
     //// Config
-    let config: Element<Message> =
-        if let Some(selected_id) = app.network.selected_shapes.iter().next() {
-            let node = app.network.graph.get_node(*selected_id);
-            let input_data = app.network.graph.get_input_data(selected_id);
-            let out_port_display: Element<Message> = if app.debug {
-                column![format_node_output(
-                    node,
-                    &app.network.graph.get_output_data(*selected_id)
-                )]
+    let config: Element<Message> = if app.show_history {
+        history_panel(&app.network.history)
+    } else if let Some((_, annotation)) = app.network.edited_annotation() {
+        let swatch = |color: AnnotationColor| {
+            button(text(""))
+                .width(18.)
+                .height(18.)
+                .on_press(Message::SetAnnotationColor(color))
+                .style(move |_theme, _status| button::Style {
+                    background: Some(color.color().into()),
+                    border: border::rounded(3.).width(match color == annotation.color {
+                        true => 2.,
+                        false => 0.,
+                    }),
+                    ..Default::default()
+                })
                 .into()
-            } else {
-                text("").into()
-            };
-            column![
-                container(text(node.template.name().clone()).size(20.)).center_x(Fill),
-                horizontal_rule(0),
-                row![node.status.icon(), node.status.text_element().size(12.)]
-                    .align_y(Center)
-                    .spacing(4.0),
-                vertical_space().height(10.),
-                node.template
-                    .config_view(*selected_id, input_data)
-                    .unwrap_or(text("...").into()),
-                vertical_space(),
-                scrollable(out_port_display),
-                row![button(text("delete node"))
-                    .style(button::danger)
+        };
+        column![
+            container(
+                text(match annotation.kind {
+                    AnnotationKind::Note => "Note",
+                    AnnotationKind::Frame => "Frame",
+                })
+                .size(20.)
+            )
+            .center_x(Fill),
+            horizontal_rule(0),
+            text_editor(&app.annotation_editor)
+                .on_action(Message::EditAnnotationText)
+                .height(150.),
+            row(AnnotationColor::iter().map(swatch)).spacing(4.),
+            vertical_space(),
+            row![button(text("delete"))
+                .style(button::danger)
+                .padding([1, 4])
+                .on_press(Message::DeleteSelectedNodes)]
+        ]
+        .align_x(Center)
+        .height(Fill)
+        .spacing(5.)
+        .padding([10., 5.])
+        .into()
+    } else if let Some(selected_id) = app.network.selected_shapes.iter().next() {
+        let node = app.network.graph.get_node(*selected_id);
+        let input_data = app.network.graph.get_input_data(selected_id);
+        //// Viewers are shown on the dashboard, other nodes contribute their parameters
+        let config_view = node.template.config_view(*selected_id, input_data);
+        let is_viewer = node.template.is_viewer();
+        let dashboard_part = match is_viewer {
+            true => DashboardPart::View,
+            false => DashboardPart::Parameters,
+        };
+        let window_commands = row![]
+            .push_maybe(is_viewer.then(|| {
+                button(text("pop out"))
+                    .style(button::secondary)
                     .padding([1, 4])
-                    .on_press(Message::DeleteSelectedNodes)]
-            ]
-            .align_x(Center)
-            .height(Fill)
-            .spacing(5.)
-            .padding([10., 5.])
+                    .on_press(Message::PopOut(*selected_id))
+            }))
+            .push_maybe((is_viewer || config_view.is_some()).then(|| {
+                button(text("add to dashboard"))
+                    .style(button::secondary)
+                    .padding([1, 4])
+                    .on_press(Message::AddToDashboard(*selected_id, dashboard_part))
+            }))
+            .spacing(4.);
+        //// The resolution only applies to PNG files, SVG files can be scaled freely
+        let export_commands = row![]
+            .push_maybe(is_viewer.then(|| {
+                button(text("export"))
+                    .style(button::secondary)
+                    .padding([1, 4])
+                    .on_press(Message::Export(*selected_id))
+            }))
+            .push_maybe(is_viewer.then(|| {
+                pick_list(
+                    ExportSize::PRESETS,
+                    Some(app.export_size),
+                    Message::ExportResolution,
+                )
+                .text_size(12.)
+                .padding([1, 4])
+            }))
+            .align_y(Center)
+            .spacing(4.);
+        let out_port_display: Element<Message> = if app.debug {
+            column![format_node_output(
+                node,
+                &app.network.graph.get_output_data(*selected_id)
+            )]
             .into()
         } else {
             text("").into()
         };
+        column![
+            container(text(node.template.name().clone()).size(20.)).center_x(Fill),
+            horizontal_rule(0),
+            row![node.status.icon(), node.status.text_element().size(12.)]
+                .align_y(Center)
+                .spacing(4.0),
+            vertical_space().height(10.),
+            config_view.unwrap_or(text("...").into()),
+            vertical_space(),
+            window_commands,
+            export_commands,
+            scrollable(out_port_display),
+            row![button(text("delete node"))
+                .style(button::danger)
+                .padding([1, 4])
+                .on_press(Message::DeleteSelectedNodes)]
+        ]
+        .align_x(Center)
+        .height(Fill)
+        .spacing(5.)
+        .padding([10., 5.])
+        .into()
+    } else {
+        text("").into()
+    };
     container(
         column![
             row![
@@ -101,6 +268,8 @@ pub fn side_bar(app: &App) -> Element<'_, Message> {
                 action_commands.align_y(Alignment::Center),
             ]
             .padding([2., 4.]),
+            canvas_commands.padding([2., 4.]),
+            align_commands,
             horizontal_rule(SEPERATOR),
             //// Config
             config
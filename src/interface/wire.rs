@@ -1,23 +1,56 @@
+use std::collections::HashSet;
 use std::iter::once;
+use std::time::{Duration, Instant};
 
-use crate::app::{Action, App};
+use crate::app::{Action, App, Message};
 use crate::gui_node::GUINode;
+use crate::interface::inspector::dtype_shape_bytes;
 use crate::math::Point;
+use crate::nodes::port::PortData;
+use crate::nodes::status::NodeStatus;
+use crate::style::color::mix;
+use crate::style::container::rounded_box;
 use crate::style::theme::AppTheme;
 use crate::StableMap;
 use canvas::{Path, Stroke};
-use iced::{widget::*, Size};
+use iced::{widget::*, Element, Padding, Size};
+
+/// Time for a wire's pulse of brightness to decay to about a third, after new data arrives
+const PULSE_DECAY: f32 = 0.3;
+/// Pulses have faded out completely after this long
+pub const PULSE_DURATION: Duration = Duration::from_millis(1500);
+/// Offset of the wire tooltip from the cursor
+const TOOLTIP_OFFSET: f32 = 14.;
+
+/// What has recently happened to the data on a wire
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WireActivity {
+    /// Brightness of the pulse after new data arrived, 0 once it has faded
+    Pulse(f32),
+    /// The node feeding the wire, or a node upstream of it, failed, so its data is out of date
+    Stale,
+}
+
+/// Brightness of a wire that received new data at `delivered`, decaying from 1 to 0
+pub fn wire_pulse(delivered: Instant, now: Instant) -> f32 {
+    let age = now.saturating_duration_since(delivered);
+    match age < PULSE_DURATION {
+        true => (-age.as_secs_f32() / PULSE_DECAY).exp(),
+        false => 0.,
+    }
+}
 
 impl App {
     pub fn wire_curve(
         &self,
         wire_end_node: u32,
         points: &StableMap<u32, Point>,
-    ) -> Vec<(Path, Stroke)> {
+        stale_nodes: &HashSet<u32>,
+    ) -> Vec<(Path, Stroke<'_>)> {
         let port_position = |port: &PortRef| {
-            let node_size = &self.network.graph.get_node(port.node).template.node_size();
+            let node_size = self.network.node_size(port.node);
             points[&port.node]
-                + find_port_offset(port, self.network.graph.port_index(port), *node_size).into()
+                + find_port_offset(port, self.network.graph.port_index(port), node_size).into()
         };
 
         //// Handle currently active wire
@@ -30,7 +63,7 @@ impl App {
             Action::CreatingInputWire(input, None) => Some((
                 (
                     port_position(input),
-                    self.cursor_position + self.network.shapes.camera.position,
+                    self.network.shapes.camera.to_world(self.cursor_position),
                 ),
                 active_wire_stroke(&self.app_theme, false),
             )),
@@ -40,20 +73,41 @@ impl App {
             )),
             Action::CreatingOutputWire(output, None) => Some((
                 (
-                    self.cursor_position + self.network.shapes.camera.position,
+                    self.network.shapes.camera.to_world(self.cursor_position),
                     port_position(output),
                 ),
                 active_wire_stroke(&self.app_theme, false),
             )),
+            //// Wire released in empty space, waiting for a node to be chosen
+            Action::CommandPalette {
+                wire: Some((port, position)),
+                ..
+            } => Some((
+                match port.io {
+                    IO::In => (port_position(port), *position),
+                    IO::Out => (*position, port_position(port)),
+                },
+                active_wire_stroke(&self.app_theme, false),
+            )),
             _ => None,
         };
 
         //// Handle all wires
+        let now = Instant::now();
+        let activity = |output: &PortRef| match stale_nodes.contains(&output.node) {
+            true => WireActivity::Stale,
+            false => WireActivity::Pulse(
+                self.network
+                    .wire_pulses
+                    .get(&output.node)
+                    .map_or(0., |delivered| wire_pulse(*delivered, now)),
+            ),
+        };
         let incoming_wires = self.network.graph.incoming_edges(&wire_end_node);
         incoming_wires
             .iter()
             .map(|(from, to)| {
-                let stroke = wire_status(from, to, &self.action, &self.app_theme);
+                let stroke = wire_status(from, to, activity(from), &self.action, &self.app_theme);
                 ((port_position(to), port_position(from)), stroke)
             })
             //// include the active wire
@@ -62,19 +116,120 @@ impl App {
             .map(|((from, to), stroke)| {
                 (
                     Path::new(|builder| {
+                        let [from, control_a, control_b, to] = wire_bezier(from, to);
                         builder.move_to(from.into());
-                        let mid = f32::abs((to.y - from.y) * 0.5).max(PORT_RADIUS * 2.);
-                        builder.bezier_curve_to(
-                            (from.x, from.y - mid).into(),
-                            (to.x, to.y + mid).into(),
-                            to.into(),
-                        );
+                        builder.bezier_curve_to(control_a.into(), control_b.into(), to.into());
                     }),
                     stroke,
                 )
             })
             .collect()
     }
+
+    /// The output port of the wire closest to `point` (in workspace coordinates),
+    /// if one is within `distance`
+    pub fn wire_at(&self, point: Point, distance: f32) -> Option<PortRef> {
+        let port_position = |port: &PortRef| {
+            let node_size = self.network.node_size(port.node);
+            self.network
+                .shapes
+                .shape_positions
+                .get(&port.node)
+                .map(|position| {
+                    *position
+                        + find_port_offset(port, self.network.graph.port_index(port), node_size)
+                            .into()
+                })
+        };
+        self.network
+            .graph
+            .edges()
+            .iter()
+            .filter_map(|(from, to)| {
+                let curve = wire_bezier(port_position(to)?, port_position(from)?);
+                Some((from, distance_to_curve(&curve, point)))
+            })
+            .filter(|(_, d)| *d < distance)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(from, _)| from.clone())
+    }
+
+    /// The type and shape of the data on the hovered wire, next to the cursor
+    pub fn wire_tooltip(&self) -> Option<Element<'_, Message>> {
+        // The wire may have been deleted since the cursor last moved
+        let port = self.hovered_wire.as_ref().filter(|port| {
+            self.network
+                .graph
+                .edges()
+                .iter()
+                .any(|(from, _)| from == *port)
+        })?;
+        let node = self.network.graph.get_node(port.node);
+        let data = self.network.graph.get_wire_data(&port.node, &port.name);
+        let summary = match data.map(|data| data.read().unwrap()) {
+            Some(data) => match (&*data, dtype_shape_bytes(&data)) {
+                (PortData::Object(_), (_, shape, _)) => format!("object, {} fields", shape[0]),
+                (_, (dtype, shape, _)) if shape.is_empty() => dtype.to_string(),
+                (_, (dtype, shape, _)) => format!("{dtype} {shape:?}"),
+            },
+            None => "no data".to_string(),
+        };
+        let stale = matches!(node.status, NodeStatus::Error(_)).then(|| {
+            text(format!("stale, {} failed", node.template.name()))
+                .color(self.app_theme.danger.strong_color())
+        });
+        let cursor = self.cursor_position;
+        Some(
+            container(
+                container(
+                    iced::widget::column![
+                        text(format!("{}.{}", node.template.name(), port.name)),
+                        text(summary)
+                    ]
+                    .push_maybe(stale)
+                    .spacing(2.),
+                )
+                .style(rounded_box)
+                .padding([2, 6]),
+            )
+            .padding(Padding {
+                top: cursor.y + TOOLTIP_OFFSET,
+                left: cursor.x + TOOLTIP_OFFSET,
+                ..Padding::ZERO
+            })
+            .into(),
+        )
+    }
+}
+
+/// Control points of the bezier curve of a wire, that leaves `from` upwards and enters `to` from below
+pub fn wire_bezier(from: Point, to: Point) -> [Point; 4] {
+    let mid = f32::abs((to.y - from.y) * 0.5).max(PORT_RADIUS * 2.);
+    [
+        from,
+        Point::new(from.x, from.y - mid),
+        Point::new(to.x, to.y + mid),
+        to,
+    ]
+}
+
+/// Approximate distance from `point` to a cubic bezier curve, by sampling the curve
+fn distance_to_curve(curve: &[Point; 4], point: Point) -> f32 {
+    const SAMPLES: usize = 32;
+    (0..=SAMPLES)
+        .map(|i| {
+            let t = i as f32 / SAMPLES as f32;
+            let u = 1. - t;
+            let weights = [u * u * u, 3. * u * u * t, 3. * u * t * t, t * t * t];
+            let sample = curve
+                .iter()
+                .zip(weights)
+                .fold(Point::new(0., 0.), |sum, (p, w)| {
+                    Point::new(sum.x + p.x * w, sum.y + p.y * w)
+                });
+            (sample - point).length()
+        })
+        .fold(f32::MAX, f32::min)
 }
 
 use super::node::{NODE_RADIUS, PORT_RADIUS};
@@ -98,10 +253,12 @@ pub fn find_port_offset(port_ref: &PortRef, port_index: usize, size: Size) -> Ve
 
 /// Determine the status of a given *non-active* wire, and provide the corresponding color
 /// The current action determines how existing wires should be displayed, to provide
-/// context about how the current action will affect other wires
+/// context about how the current action will affect other wires.
+/// Otherwise wires show recent activity: a pulse after new data arrives, or stale data
 pub fn wire_status<'a>(
     output: &PortRef,
     input: &PortRef,
+    activity: WireActivity,
     current_action: &app::Action,
     theme: &'a AppTheme,
 ) -> Stroke<'a> {
@@ -110,7 +267,10 @@ pub fn wire_status<'a>(
 
     //let p = theme.extended_palette();
 
-    let default_stroke = default_wire_stroke(theme);
+    let default_stroke = match activity {
+        WireActivity::Pulse(pulse) => activity_stroke(theme, pulse),
+        WireActivity::Stale => stale_wire_stroke(theme),
+    };
     let maybe_delete = default_stroke.with_color(theme.danger.weak_color().into());
     let will_delete = with_dashed_stroke(maybe_delete);
 
@@ -144,7 +304,7 @@ pub fn wire_status<'a>(
 }
 
 /// active wire color
-pub fn active_wire_stroke(t: &AppTheme, is_tentative_connection: bool) -> Stroke {
+pub fn active_wire_stroke(t: &AppTheme, is_tentative_connection: bool) -> Stroke<'_> {
     let stroke = default_wire_stroke(t).with_color(t.secondary.strong_color().into());
     if !is_tentative_connection {
         with_dashed_stroke(stroke)
@@ -163,9 +323,70 @@ fn with_dashed_stroke(stroke: Stroke) -> Stroke {
     }
 }
 
-pub fn default_wire_stroke(theme: &AppTheme) -> Stroke {
+/// A wire that brightens towards the text color, and widens, as `pulse` goes from 0 to 1
+fn activity_stroke(theme: &AppTheme, pulse: f32) -> Stroke<'_> {
+    let stroke = default_wire_stroke(theme);
+    match pulse > 0. {
+        true => stroke
+            .with_color(mix(theme.secondary.base_color, theme.text.base_color, pulse).into())
+            .with_width(stroke.width + 2. * pulse),
+        false => stroke,
+    }
+}
+
+/// Faded and dotted, for data left behind by a node that has since failed
+fn stale_wire_stroke(theme: &AppTheme) -> Stroke<'_> {
+    let color: iced::Color = theme.secondary.base_color.into();
+    Stroke {
+        line_dash: canvas::LineDash {
+            segments: &[2.0, 6.0],
+            offset: 0,
+        },
+        ..default_wire_stroke(theme).with_color(color.scale_alpha(0.4))
+    }
+}
+
+pub fn default_wire_stroke(theme: &AppTheme) -> Stroke<'_> {
     Stroke::default()
         .with_width(3.0)
         .with_color(theme.secondary.base_color.into())
         .with_line_cap(canvas::LineCap::Round)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wire_activity_styles() {
+        let now = Instant::now();
+        assert_eq!(wire_pulse(now, now), 1.);
+        let fading = wire_pulse(now, now + Duration::from_millis(300));
+        assert!(fading > 0.3 && fading < 0.4);
+        assert_eq!(wire_pulse(now, now + PULSE_DURATION), 0.);
+
+        let theme = AppTheme::default();
+        let output = PortRef {
+            node: 0,
+            name: "out".into(),
+            io: IO::Out,
+        };
+        let input = PortRef {
+            node: 1,
+            name: "a".into(),
+            io: IO::In,
+        };
+        let idle = |activity| wire_status(&output, &input, activity, &Action::Idle, &theme);
+        assert_eq!(
+            idle(WireActivity::Pulse(0.)).width,
+            default_wire_stroke(&theme).width
+        );
+        assert!(idle(WireActivity::Pulse(1.)).width > idle(WireActivity::Pulse(0.5)).width);
+        assert!(!idle(WireActivity::Stale).line_dash.segments.is_empty());
+
+        // Wires that a new wire will replace are shown as such, even when stale
+        let replacing = Action::CreatingInputWire(input.clone(), Some(output.clone()));
+        let stroke = wire_status(&output, &input, WireActivity::Stale, &replacing, &theme);
+        assert_eq!(stroke.line_dash.segments, &[10.0]);
+    }
+}
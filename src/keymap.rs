@@ -0,0 +1,357 @@
+use std::{collections::HashMap, fmt, fs::read_to_string, str::FromStr};
+
+use derive_more::Display;
+use iced::keyboard::{Key, Modifiers};
+use itertools::Itertools;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use strum::{EnumIter, IntoEnumIterator};
+
+use crate::{app::Message, config::Config, StableMap};
+
+/// Modifier used by most shortcuts, `cmd` on macOS and `ctrl` elsewhere
+#[cfg(target_os = "macos")]
+const COMMAND: &str = "cmd";
+#[cfg(not(target_os = "macos"))]
+const COMMAND: &str = "ctrl";
+
+/// Actions that can be bound to key chords in the keymap file
+#[derive(
+    Debug,
+    Display,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    EnumIter,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Shortcut {
+    Save,
+    Load,
+    New,
+    Undo,
+    Redo,
+    ComputeAll,
+    Delete,
+    Duplicate,
+    Copy,
+    Cut,
+    Paste,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+    ZoomToFit,
+    ZoomToSelection,
+    CommandPalette,
+    AddNode,
+    AutoLayout,
+    ToggleGrid,
+}
+
+impl Shortcut {
+    pub fn message(self) -> Message {
+        match self {
+            Shortcut::Save => Message::Save,
+            Shortcut::Load => Message::Load,
+            Shortcut::New => Message::New,
+            Shortcut::Undo => Message::Undo,
+            Shortcut::Redo => Message::Redo,
+            Shortcut::ComputeAll => Message::ComputeAll,
+            Shortcut::Delete => Message::DeleteSelectedNodes,
+            Shortcut::Duplicate => Message::Duplicate,
+            Shortcut::Copy => Message::Copy,
+            Shortcut::Cut => Message::Cut,
+            Shortcut::Paste => Message::Paste,
+            Shortcut::ZoomIn => Message::Zoom(1.25),
+            Shortcut::ZoomOut => Message::Zoom(0.8),
+            Shortcut::ZoomReset => Message::ZoomReset,
+            Shortcut::ZoomToFit => Message::ZoomToFit,
+            Shortcut::ZoomToSelection => Message::ZoomToSelection,
+            Shortcut::CommandPalette => Message::OpenCommandPalette,
+            Shortcut::AddNode => Message::OpenAddNodeUi,
+            Shortcut::AutoLayout => Message::AutoLayout,
+            Shortcut::ToggleGrid => Message::ToggleGrid,
+        }
+    }
+
+    /// Shortcuts that a focused text input handles itself
+    fn yields_to_text_input(self) -> bool {
+        matches!(
+            self,
+            Shortcut::Delete
+                | Shortcut::Copy
+                | Shortcut::Cut
+                | Shortcut::Paste
+                | Shortcut::Undo
+                | Shortcut::Redo
+        )
+    }
+
+    fn default_chords(self) -> Vec<String> {
+        let command = |key: &str| format!("{COMMAND}+{key}");
+        match self {
+            Shortcut::Save => vec![command("s")],
+            Shortcut::Load => vec![command("o")],
+            Shortcut::New => vec![command("n")],
+            Shortcut::Undo => vec![command("z")],
+            Shortcut::Redo if cfg!(target_os = "macos") => vec![command("shift+z")],
+            Shortcut::Redo => vec![command("shift+z"), command("y")],
+            Shortcut::ComputeAll => vec!["f5".into()],
+            Shortcut::Delete if cfg!(target_os = "macos") => {
+                vec!["delete".into(), "backspace".into()]
+            }
+            Shortcut::Delete => vec!["delete".into()],
+            Shortcut::Duplicate => vec![command("d")],
+            Shortcut::Copy => vec![command("c")],
+            Shortcut::Cut => vec![command("x")],
+            Shortcut::Paste => vec![command("v")],
+            Shortcut::ZoomIn => vec![command("="), command("plus")],
+            Shortcut::ZoomOut => vec![command("-")],
+            Shortcut::ZoomReset => vec![command("0")],
+            Shortcut::ZoomToFit => vec![command("1")],
+            Shortcut::ZoomToSelection => vec![command("2")],
+            Shortcut::CommandPalette => vec![command("p")],
+            Shortcut::AddNode => vec![command("a")],
+            Shortcut::AutoLayout => vec![command("l")],
+            Shortcut::ToggleGrid => vec![command("g")],
+        }
+    }
+}
+
+/// A key pressed together with modifiers, written as `ctrl+shift+z`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    modifiers: Modifiers,
+    /// Lowercase character, or the lowercase name of a named key, such as `delete` or `f5`
+    key: String,
+}
+
+impl KeyChord {
+    /// The chord of a key press event.
+    /// Shift is part of symbols like `+`, so it is only kept for letters and named keys
+    pub fn from_event(key: &Key, modifiers: Modifiers) -> Option<Self> {
+        let (key, keep_shift) = match key {
+            Key::Character(c) => {
+                let c = c.to_lowercase();
+                let letter = c.chars().all(char::is_alphabetic);
+                (if c == "+" { "plus".into() } else { c }, letter)
+            }
+            Key::Named(named) => (format!("{named:?}").to_lowercase(), true),
+            Key::Unidentified => return None,
+        };
+        let modifiers = match keep_shift {
+            true => modifiers,
+            false => modifiers.difference(Modifiers::SHIFT),
+        };
+        Some(KeyChord { modifiers, key })
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<_> = s
+            .split('+')
+            .map(|part| part.trim().to_lowercase())
+            .collect();
+        let key = parts.pop().filter(|key| !key.is_empty());
+        let Some(key) = key else {
+            return Err(format!("no key in chord {s:?}"));
+        };
+        let modifiers = parts
+            .iter()
+            .try_fold(Modifiers::empty(), |modifiers, part| {
+                Ok(modifiers
+                    | match part.as_str() {
+                        "ctrl" | "control" => Modifiers::CTRL,
+                        "shift" => Modifiers::SHIFT,
+                        "alt" | "option" => Modifiers::ALT,
+                        "cmd" | "super" | "logo" => Modifiers::LOGO,
+                        other => return Err(format!("unknown modifier {other:?} in chord {s:?}")),
+                    })
+            })?;
+        Ok(KeyChord { modifiers, key })
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (Modifiers::CTRL, "ctrl"),
+            (Modifiers::LOGO, "cmd"),
+            (Modifiers::ALT, "alt"),
+            (Modifiers::SHIFT, "shift"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// Chords of every shortcut, as written to a new keymap file
+fn default_bindings() -> StableMap<Shortcut, Vec<String>> {
+    Shortcut::iter()
+        .map(|shortcut| (shortcut, shortcut.default_chords()))
+        .collect()
+}
+
+/// Key chords for each shortcut, read from `keymap.toml` next to `config.toml`
+#[derive(Debug, Default)]
+pub struct Keymap {
+    chords: HashMap<KeyChord, Shortcut>,
+}
+
+impl Keymap {
+    /// Build a keymap from chords as written in the keymap file.
+    /// Shortcuts missing from `bindings` use their defaults, and conflicting or invalid chords
+    /// are reported and skipped. Chords written in the file take precedence over defaults
+    pub fn new(bindings: &StableMap<Shortcut, Vec<String>>) -> Self {
+        let mut chords = HashMap::new();
+        for shortcut in Shortcut::iter().sorted_by_key(|s| !bindings.contains_key(s)) {
+            let written = bindings
+                .get(&shortcut)
+                .cloned()
+                .unwrap_or_else(|| shortcut.default_chords());
+            for chord in written {
+                match chord.parse::<KeyChord>() {
+                    Ok(chord) => {
+                        if let Some(existing) = chords.get(&chord) {
+                            warn!("Key chord {chord} is bound to both {existing} and {shortcut}, keeping {existing}");
+                        } else {
+                            chords.insert(chord, shortcut);
+                        }
+                    }
+                    Err(e) => error!("Invalid key chord for {shortcut}: {e}"),
+                }
+            }
+        }
+        Keymap { chords }
+    }
+
+    pub fn read_keymap() -> Self {
+        let keymap_file = Config::config_dir().join("keymap.toml");
+        match read_to_string(&keymap_file).map(|s| toml::from_str(&s)) {
+            Ok(Ok(bindings)) => {
+                info!("Loaded keymap: {keymap_file:?}");
+                Keymap::new(&bindings)
+            }
+            Ok(Err(e)) => {
+                error!("Error reading keymap {keymap_file:?}, using defaults.\n{e}");
+                Keymap::default_keymap()
+            }
+            Err(_) => {
+                info!("No keymap found, writing defaults to {keymap_file:?}");
+                let defaults = default_bindings();
+                let _ = std::fs::create_dir_all(Config::config_dir());
+                match toml::to_string_pretty(&defaults) {
+                    Ok(s) => std::fs::write(&keymap_file, s).unwrap_or_else(|e| {
+                        error!("Could not write keymap file {keymap_file:?}\n{e}")
+                    }),
+                    Err(e) => error!("Could not serialize keymap\n{e}"),
+                }
+                Keymap::new(&defaults)
+            }
+        }
+    }
+
+    pub fn default_keymap() -> Self {
+        Keymap::new(&StableMap::new())
+    }
+
+    /// The message bound to a key press, if any.
+    /// `captured` is set when a focused widget, such as a text input, already handled the key
+    pub fn message(&self, key: &Key, modifiers: Modifiers, captured: bool) -> Option<Message> {
+        let chord = KeyChord::from_event(key, modifiers)?;
+        self.chords
+            .get(&chord)
+            .filter(|shortcut| !(captured && shortcut.yields_to_text_input()))
+            .map(|shortcut| shortcut.message())
+    }
+
+    /// Chords bound to a shortcut, for display in tooltips
+    pub fn chords(&self, shortcut: Shortcut) -> Vec<String> {
+        self.chords
+            .iter()
+            .filter(|(_, s)| **s == shortcut)
+            .map(|(chord, _)| chord.to_string())
+            .sorted()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use iced::keyboard::key::Named;
+
+    #[test]
+    fn parse_chords() {
+        let chord: KeyChord = "Ctrl+Shift+Z".parse().unwrap();
+        assert_eq!(chord.to_string(), "ctrl+shift+z");
+        assert!("ctrl+".parse::<KeyChord>().is_err());
+        assert!("hyper+a".parse::<KeyChord>().is_err());
+
+        // Shift is implied by symbols, but not by letters
+        let plus = KeyChord::from_event(
+            &Key::Character("+".into()),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        );
+        assert_eq!(plus, "ctrl+plus".parse().ok());
+        let redo = KeyChord::from_event(
+            &Key::Character("Z".into()),
+            Modifiers::CTRL | Modifiers::SHIFT,
+        );
+        assert_eq!(redo, "ctrl+shift+z".parse().ok());
+        let delete = KeyChord::from_event(&Key::Named(Named::Delete), Modifiers::empty());
+        assert_eq!(delete, "delete".parse().ok());
+    }
+
+    #[test]
+    fn conflicting_chords() {
+        let bindings = [
+            (Shortcut::Save, vec!["ctrl+k".to_string()]),
+            (
+                Shortcut::Load,
+                vec!["ctrl+k".to_string(), "ctrl+j".to_string()],
+            ),
+            (Shortcut::ZoomIn, vec![format!("{COMMAND}+s")]),
+        ]
+        .into();
+        let keymap = Keymap::new(&bindings);
+        // The first shortcut keeps a conflicting chord
+        assert_eq!(keymap.chords(Shortcut::Save), ["ctrl+k"]);
+        assert_eq!(keymap.chords(Shortcut::Load), ["ctrl+j"]);
+        // Configured chords replace defaults of other shortcuts
+        assert_eq!(keymap.chords(Shortcut::ZoomIn), [format!("{COMMAND}+s")]);
+        assert!(keymap.chords(Shortcut::Undo).len() == 1);
+
+        // Text inputs handle their own clipboard shortcuts
+        let key = Key::Character("c".into());
+        let copy = format!("{COMMAND}+c")
+            .parse::<KeyChord>()
+            .unwrap()
+            .modifiers;
+        assert!(keymap.message(&key, copy, true).is_none());
+        assert!(keymap.message(&key, copy, false).is_some());
+    }
+
+    #[test]
+    fn keymap_file_format() {
+        let bindings: StableMap<Shortcut, Vec<String>> =
+            toml::from_str("compute_all = [\"ctrl+enter\"]\nundo = []").unwrap();
+        let keymap = Keymap::new(&bindings);
+        assert_eq!(keymap.chords(Shortcut::ComputeAll), ["ctrl+enter"]);
+        assert!(keymap.chords(Shortcut::Undo).is_empty());
+        let defaults = default_bindings();
+        assert!(toml::to_string_pretty(&defaults).is_ok());
+    }
+}
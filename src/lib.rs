@@ -12,7 +12,9 @@ pub mod config;
 pub mod file_watch;
 pub mod graph;
 pub mod gui_node;
+pub mod history;
 pub mod interface;
+pub mod keymap;
 pub mod math;
 pub mod network;
 pub mod nodes;
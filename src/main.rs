@@ -1,22 +1,70 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
 use foray::app::{subscriptions, theme, title, App};
-use iced::{application, Font};
+use foray::config::Config;
+use foray::network::Network;
+use foray::nodes::figure::ExportSize;
+use iced::{daemon, Font};
+
+const EXPORT_USAGE: &str =
+    "usage: foray export <network.ron> <node id or name> <output.png|svg> [WIDTHxHEIGHT]";
 
-pub fn main() -> iced::Result {
+pub fn main() -> ExitCode {
     env_logger::init();
 
-    application(title, App::update, App::view)
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("export") {
+        return match export(&args[1..]) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let result = daemon(title, App::update, App::view)
         .subscription(subscriptions)
         .theme(theme)
-        .window(iced::window::Settings {
-            min_size: Some((400., 300.).into()),
-            ..Default::default()
-        })
         .antialiasing(true)
-        .window_size((1000., 800.))
-        .decorations(true)
-        .scale_factor(|_| 1.25)
+        .scale_factor(|_, _| 1.25)
         .font(include_bytes!("../data/CaskaydiaCoveNerdFont.ttf").as_slice())
         .font(include_bytes!("../data/CaskaydiaCove.ttf").as_slice())
         .default_font(Font::with_name("CaskaydiaCove"))
-        .run()
+        .run_with(App::new);
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Compute a network without opening a window, and export the figure of one of its viewers
+fn export(args: &[String]) -> Result<(), String> {
+    let [network_file, node, output, rest @ ..] = args else {
+        return Err(EXPORT_USAGE.to_string());
+    };
+    let size = match rest {
+        [] => ExportSize::default(),
+        [size] => ExportSize::parse(size).ok_or(format!(
+            "Invalid size '{size}', expected WIDTHxHEIGHT\n{EXPORT_USAGE}"
+        ))?,
+        _ => return Err(EXPORT_USAGE.to_string()),
+    };
+
+    let config = Config::read_config();
+    config.setup_environment();
+    let projects = config.read_projects();
+    let network_file = PathBuf::from(network_file);
+    let mut network = Network::load_network(&network_file, &projects)
+        .map_err(|_| format!("Could not load network {network_file:?}"))?;
+    network.compute_blocking();
+
+    let id = network.find_node(node).map_err(|e| e.to_string())?;
+    network
+        .export_figure(id, &PathBuf::from(output), size)
+        .map_err(|e| e.to_string())
 }
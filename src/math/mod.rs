@@ -1,3 +1,4 @@
+pub mod random;
 pub mod round_nice;
 mod vector;
 pub use vector::Point;
@@ -31,7 +32,7 @@ pub fn linspace(start: f32, stop: f32, num: i32) -> Vec<f32> {
         panic!("Encountered nan!{:?}", (start, stop, num))
     }
     (0..num)
-        .map(|i| (i as f32 / (num - 1).max(1) as f32))
+        .map(|i| i as f32 / (num - 1).max(1) as f32)
         .map(|c| start * (1. - c) + c * stop)
         .collect()
 }
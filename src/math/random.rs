@@ -0,0 +1,74 @@
+use numpy::Complex64;
+use std::f64::consts::TAU;
+
+/// A small seeded pseudo random number generator (SplitMix64).
+///
+/// Implemented here rather than pulled from a crate so that a given seed produces the same
+/// values regardless of dependency versions, which keeps saved networks reproducible
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly distributed in [0, 1)
+    pub fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normal distribution, using the Box-Muller transform
+    pub fn gaussian(&mut self) -> f64 {
+        self.complex_gaussian().re * std::f64::consts::SQRT_2
+    }
+
+    /// Circularly symmetric complex normal distribution with unit variance,
+    /// i.e. real and imaginary parts each have a variance of 1/2
+    pub fn complex_gaussian(&mut self) -> Complex64 {
+        // 1 - uniform is in (0, 1], avoiding ln(0)
+        let r = (-(1. - self.uniform()).ln()).sqrt();
+        Complex64::from_polar(r, TAU * self.uniform())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn deterministic() {
+        let a: Vec<_> = (0..8)
+            .scan(Rng::new(7), |r, _| Some(r.next_u64()))
+            .collect();
+        let b: Vec<_> = (0..8)
+            .scan(Rng::new(7), |r, _| Some(r.next_u64()))
+            .collect();
+        let c: Vec<_> = (0..8)
+            .scan(Rng::new(8), |r, _| Some(r.next_u64()))
+            .collect();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn gaussian_moments() {
+        let mut rng = Rng::new(1);
+        let n = 100_000;
+        let samples: Vec<_> = (0..n).map(|_| rng.gaussian()).collect();
+        let mean = samples.iter().sum::<f64>() / n as f64;
+        let var = samples.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n as f64;
+        assert!(mean.abs() < 0.02, "{mean}");
+        assert!((var - 1.).abs() < 0.02, "{var}");
+    }
+}
@@ -12,6 +12,10 @@ impl Vector {
         Self { x, y }
     }
 
+    pub fn length(self) -> f32 {
+        self.x.hypot(self.y)
+    }
+
     pub fn to_point(self) -> Point {
         Point {
             x: self.x,
@@ -1,52 +1,197 @@
-use std::{collections::HashSet, fs::read_to_string, iter::once, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::read_to_string,
+    iter::once,
+    path::PathBuf,
+    time::Instant,
+};
 
-use iced::keyboard::Modifiers;
+use derive_more::derive::Display;
+use iced::{keyboard::Modifiers, Size};
 use indexmap::IndexMap;
+use itertools::Itertools;
 use log::{error, warn};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     app::Action,
-    graph::{Graph, PortRef},
-    gui_node::GuiGraph,
-    math::Point,
-    nodes::{
-        port::{PortData, PortType},
-        status::NodeStatus,
-        NodeData, NodeTemplate,
-    },
+    graph::{GraphNode, PortRef, IO},
+    gui_node::{GUINode, GuiGraph},
+    history::{Edit, History},
+    interface::{dashboard::Dashboard, node::default_node_size},
+    math::{Point, Vector},
+    network::align::Align,
+    nodes::{port::PortType, NodeData, NodeTemplate},
     project::Project,
-    widget::{shapes::ShapeId, workspace},
+    widget::{
+        annotation::{Annotation, AnnotationId},
+        shapes::ShapeId,
+        workspace,
+    },
 };
 
-type UndoStash = Vec<(
-    Graph<NodeData, PortType, PortData>,
-    IndexMap<ShapeId, Point>,
-)>;
+pub mod align;
+mod annotations;
+mod export;
+mod layout;
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct Network {
     //// Persistant data
     pub graph: GuiGraph,
     pub shapes: workspace::State,
+    /// Notes and frames on the canvas
+    #[serde(default)]
+    pub annotations: IndexMap<AnnotationId, Annotation>,
+    /// Sizes of nodes that have been resized, overriding the size of their template
+    #[serde(default)]
+    pub node_sizes: IndexMap<ShapeId, Vector>,
+    /// Viewers and parameters shown in the dashboard window
+    #[serde(default)]
+    pub dashboard: Dashboard,
     //// Runtime data
     #[serde(skip)]
     pub file: Option<PathBuf>,
     #[serde(skip)]
     pub selected_shapes: HashSet<ShapeId>,
+    #[serde(skip)]
+    pub selected_annotations: HashSet<AnnotationId>,
     /// Nodes that are waiting for dependencies before executing
     /// TODO: make these cancleable
     #[serde(skip)]
     pub queued_nodes: HashSet<u32>,
+    /// When each node last delivered new data to its output wires, while the wires pulse
+    #[serde(skip)]
+    pub wire_pulses: HashMap<u32, Instant>,
     //#[serde(skip)]
     //pub compute_task_handles: HashMap<u32, iced::task::Handle>,
     #[serde(skip)]
-    pub undo_stack: UndoStash,
-    #[serde(skip)]
-    pub redo_stack: UndoStash,
+    pub history: History,
     #[serde(skip)]
     pub unsaved_changes: bool,
 }
+/// A group of nodes and the wires between them, as copied to the clipboard
+#[derive(Serialize, Deserialize)]
+struct Fragment {
+    nodes: Vec<(ShapeId, NodeTemplate, Point)>,
+    edges: Vec<(PortRef, PortRef)>,
+    #[serde(default)]
+    annotations: Vec<Annotation>,
+    /// Sizes of resized nodes, by their id in `nodes`
+    #[serde(default)]
+    sizes: Vec<(ShapeId, Vector)>,
+}
+
+impl Fragment {
+    /// Check that the nodes and wires of a fragment, which may have been edited by hand,
+    /// form a valid graph, before any of it is pasted
+    fn validate(&self) -> Result<(), PasteError> {
+        let invalid = |reason: String| Err(PasteError::Invalid(reason));
+        let nodes: HashMap<_, NodeData> = self
+            .nodes
+            .iter()
+            .map(|(id, template, _)| (*id, template.clone().into()))
+            .collect();
+        if nodes.len() != self.nodes.len() {
+            return invalid("more than one node with the same id".into());
+        }
+
+        let mut driven = HashSet::new();
+        for (from, to) in &self.edges {
+            let (Some(from_node), Some(to_node)) = (nodes.get(&from.node), nodes.get(&to.node))
+            else {
+                return invalid(format!(
+                    "wire {from:?} -> {to:?} to a node that is not pasted"
+                ));
+            };
+            if from.io != IO::Out
+                || to.io != IO::In
+                || !from_node.outputs().contains_key(&from.name)
+                || !to_node.inputs().contains_key(&to.name)
+            {
+                return invalid(format!("wire {from:?} -> {to:?} between missing ports"));
+            }
+            if !driven.insert(to) {
+                return invalid(format!("more than one wire into {to:?}"));
+            }
+        }
+
+        // Remove nodes without incoming wires until none are left, as in `Graph::topological_sort`
+        let mut edges = self.edges.clone();
+        let has_incoming =
+            |id: &ShapeId, edges: &[(PortRef, PortRef)]| edges.iter().any(|(_, to)| to.node == *id);
+        let mut no_incoming: Vec<_> = nodes
+            .keys()
+            .filter(|id| !has_incoming(id, &edges))
+            .copied()
+            .collect();
+        while let Some(id) = no_incoming.pop() {
+            let (outgoing, rest) = edges.into_iter().partition(|(from, _)| from.node == id);
+            edges = rest;
+            for (_, to) in outgoing {
+                if !has_incoming(&to.node, &edges) {
+                    no_incoming.push(to.node);
+                }
+            }
+        }
+        if edges.is_empty() {
+            Ok(())
+        } else {
+            invalid("wires form a cycle".into())
+        }
+    }
+}
+
+/// Why clipboard contents could not be pasted
+#[derive(Debug, Display)]
+pub enum PasteError {
+    #[display("{_0}")]
+    Parse(ron::error::SpannedError),
+    #[display("{_0}")]
+    Invalid(String),
+}
+
+/// Offset of duplicated items from the originals
+const DUPLICATE_OFFSET: f32 = 20.;
+
+/// Python nodes only store a path relative to their project.
+/// Resolve the absolute path, given the projects that we know are accessible.
+/// Currently We just take the first one found, but more complex resolution could be added
+fn resolve_source_path(template: &mut NodeTemplate, projects: &[Project]) {
+    if let NodeTemplate::PyNode(py_node) = template {
+        let found_path = projects
+            .iter()
+            // Calculate potential node source path
+            .map(|project| {
+                py_node
+                    .relative_path
+                    .to_logical_path(project.absolute_path.clone())
+            })
+            // Pick the first path that exists
+            .find(|path| path.is_file());
+        if let Some(path) = found_path {
+            py_node.absolute_path = path;
+        } else {
+            error!("Could not find source file for node \n{py_node}");
+        }
+    }
+}
+
+/// The first port of `node` that can be wired to a port of type `port_type`, on the `io` side of a wire
+pub fn compatible_port(node: &NodeData, io: &IO, port_type: &PortType) -> Option<String> {
+    match io {
+        IO::Out => node
+            .inputs()
+            .into_iter()
+            .find(|(_, input)| port_type.connects_to(input)),
+        IO::In => node
+            .outputs()
+            .into_iter()
+            .find(|(_, output)| output.connects_to(port_type)),
+    }
+    .map(|(name, _)| name)
+}
+
 pub enum NetworkLoadError {
     FileNotFound,
     CouldNotParse,
@@ -57,38 +202,18 @@ impl Network {
         match read_to_string(path).map(|s| ron::from_str::<Network>(&s)) {
             Ok(Ok(mut network)) => {
                 network.file = Some(path.clone());
+                // Forget sizes and dashboard items of nodes that no longer exist
+                let positions = &network.shapes.shape_positions;
+                network
+                    .node_sizes
+                    .retain(|id, _| positions.contains_key(id));
+                network
+                    .dashboard
+                    .items
+                    .retain(|item| positions.contains_key(&item.node));
                 let node_ids = network.graph.nodes_ref();
                 node_ids.into_iter().for_each(|nx| {
-                    match &mut network.graph.get_mut_node(nx).template {
-                        NodeTemplate::RustNode(ref _rust_node) => {}
-                        NodeTemplate::PyNode(ref mut py_node) => {
-                            // Resolve the absolute path, given the nodes we know are
-                            // accessible.
-                            // Currently We just take the first one found, but more complex
-                            // resolution could be added
-                            let found_path = projects
-                                .iter()
-                                // Calculate potential node source path
-                                .map(|project| {
-                                    py_node
-                                        .relative_path
-                                        .to_logical_path(project.absolute_path.clone())
-                                })
-                                // Pick the first path that exists
-                                .find_map(|path| {
-                                    if path.is_file() {
-                                        Some(path.clone())
-                                    } else {
-                                        None
-                                    }
-                                });
-                            if let Some(path) = found_path {
-                                py_node.absolute_path = path.to_path_buf();
-                            } else {
-                                error!("Could not find source file for node \n{py_node}");
-                            }
-                        }
-                    }
+                    resolve_source_path(&mut network.graph.get_mut_node(nx).template, projects)
                 });
                 Ok(network)
             }
@@ -104,44 +229,530 @@ impl Network {
         }
     }
 
+    /// Serialize the selected nodes, their positions, and the wires between them
+    pub fn copy_selection(&self) -> Option<String> {
+        let (_, fragment) = self.selection_fragment()?;
+        ron::ser::to_string_pretty(&fragment, ron::ser::PrettyConfig::default())
+            .inspect_err(|e| error!("Could not serialize selection {e}"))
+            .ok()
+    }
+
+    /// Paste a copy of the selection, offset from the original
+    pub fn duplicate_selection(&mut self, projects: &[Project]) -> Vec<ShapeId> {
+        let Some((origin, fragment)) = self.selection_fragment() else {
+            return vec![];
+        };
+        self.begin_edit("Duplicate nodes");
+        self.paste_fragment(
+            fragment,
+            origin + Vector::new(DUPLICATE_OFFSET, DUPLICATE_OFFSET),
+            projects,
+        )
+    }
+
+    /// The selected items, relative to the top left of the selection, and that top left corner
+    fn selection_fragment(&self) -> Option<(Point, Fragment)> {
+        let ids: Vec<_> = self
+            .selected_shapes
+            .iter()
+            .copied()
+            .filter(|id| self.shapes.shape_positions.contains_key(id))
+            .sorted()
+            .collect();
+        let positions: Vec<_> = ids
+            .iter()
+            .map(|id| self.shapes.shape_positions[id])
+            .collect();
+        let annotations: Vec<_> = self
+            .annotations
+            .iter()
+            .filter(|(id, _)| self.selected_annotations.contains(id))
+            .map(|(_, annotation)| annotation)
+            .collect();
+        // Positions are stored relative to the top left of the copied items
+        let origin = positions
+            .iter()
+            .copied()
+            .chain(annotations.iter().map(|annotation| annotation.position))
+            .reduce(|a, b| Point::new(a.x.min(b.x), a.y.min(b.y)))?;
+
+        let fragment = Fragment {
+            nodes: ids
+                .iter()
+                .zip(positions)
+                .map(|(id, position)| {
+                    (
+                        *id,
+                        self.graph.get_node(*id).template.duplicate(),
+                        (position - origin).to_point(),
+                    )
+                })
+                .collect(),
+            edges: self.graph.internal_edges(&ids),
+            sizes: ids
+                .iter()
+                .filter_map(|id| Some((*id, *self.node_sizes.get(id)?)))
+                .collect(),
+            annotations: annotations
+                .into_iter()
+                .map(|annotation| Annotation {
+                    position: (annotation.position - origin).to_point(),
+                    ..annotation.clone()
+                })
+                .collect(),
+        };
+        Some((origin, fragment))
+    }
+
+    /// Add the nodes, wires and annotations of a copied fragment,
+    /// with its top left corner at `position`. The pasted items become the selection.
+    /// Nothing is pasted if the fragment is not a valid graph
+    pub fn paste(
+        &mut self,
+        fragment: &str,
+        position: Point,
+        projects: &[Project],
+    ) -> Result<Vec<ShapeId>, PasteError> {
+        let fragment: Fragment = ron::from_str(fragment).map_err(PasteError::Parse)?;
+        fragment.validate()?;
+        self.begin_edit("Paste");
+        Ok(self.paste_fragment(fragment, position, projects))
+    }
+
+    fn paste_fragment(
+        &mut self,
+        fragment: Fragment,
+        position: Point,
+        projects: &[Project],
+    ) -> Vec<ShapeId> {
+        let new_ids: HashMap<_, _> = fragment
+            .nodes
+            .into_iter()
+            .map(|(old_id, mut template, offset)| {
+                resolve_source_path(&mut template, projects);
+                let new_id = self.add_node(template, position + offset.to_vector());
+                (old_id, new_id)
+            })
+            .collect();
+        for (from, to) in fragment.edges {
+            self.edit(Edit::AddEdge(
+                PortRef {
+                    node: new_ids[&from.node],
+                    ..from
+                },
+                PortRef {
+                    node: new_ids[&to.node],
+                    ..to
+                },
+            ));
+        }
+        for (id, size) in fragment.sizes {
+            if let Some(new_id) = new_ids.get(&id) {
+                self.set_node_size(*new_id, Some(size));
+            }
+        }
+
+        self.selected_annotations = fragment
+            .annotations
+            .into_iter()
+            .map(|annotation| {
+                self.add_annotation(Annotation {
+                    position: position + annotation.position.to_vector(),
+                    ..annotation
+                })
+            })
+            .collect();
+
+        self.selected_shapes = new_ids.values().copied().collect();
+        new_ids.into_values().collect()
+    }
+
+    /// Delete the selected nodes, and any wires connected to them, and the selected annotations
+    pub fn delete_selection(&mut self) {
+        self.begin_edit("Delete selection");
+        for id in std::mem::take(&mut self.selected_shapes) {
+            self.remove_node(id);
+        }
+        for id in std::mem::take(&mut self.selected_annotations) {
+            self.set_annotation(id, None);
+        }
+    }
+
     /// Add an edge from input to output, removing existing connected input edge if present
     pub fn add_edge(&mut self, input: &PortRef, output: &PortRef) {
-        self.stash_state();
-        self.graph.remove_edge(input);
-        self.graph.add_edge_from_ref(output, input);
-    }
-
-    /// Stash current app state, reset the redo stack, and mark unsaved changes
-    pub fn stash_state(&mut self) {
-        self.unsaved_changes = true;
-        let mut graph_snap_shot = self.graph.clone();
-        // We don't want to stash any node.status "running" values
-        let running_nodes: Vec<_> = graph_snap_shot
-            .nodes_ref()
-            .into_iter()
-            .filter(|nx| {
-                matches!(
-                    graph_snap_shot.get_node(*nx).status,
-                    NodeStatus::Running(..)
+        self.begin_edit("Connect wire");
+        self.disconnect(input);
+        self.edit(Edit::AddEdge(output.clone(), input.clone()));
+    }
+
+    /// The topmost node under `point`, in workspace coordinates
+    pub fn node_at(&self, point: Point) -> Option<ShapeId> {
+        self.shapes
+            .shape_positions
+            .iter()
+            .find(|(id, position)| {
+                let size = self.node_size(**id);
+                iced::Rectangle::new((**position).into(), size).contains(point.into())
+            })
+            .map(|(id, _)| *id)
+    }
+
+    /// Add a node next to `position`, with `wire` connected to the first of its ports that
+    /// accepts the wire's type. Returns the id of the new node
+    pub fn add_connected_node(
+        &mut self,
+        template: NodeTemplate,
+        wire: &PortRef,
+        position: Point,
+    ) -> ShapeId {
+        self.begin_edit(format!("Add {}", template.name()));
+        let size = template.node_size();
+        let node: NodeData = template.into();
+        let port = self
+            .port_type(wire)
+            .and_then(|port_type| compatible_port(&node, &wire.io, &port_type));
+        // The wire enters the top of nodes below an output, and leaves the bottom of nodes above an input
+        let offset = match wire.io {
+            IO::Out => Vector::new(size.width / 2., 0.),
+            IO::In => Vector::new(size.width / 2., size.height),
+        };
+        let id = self.add_node(node.template, position - offset);
+        self.selected_shapes = [id].into();
+
+        if let Some(name) = port {
+            let io = match wire.io {
+                IO::Out => IO::In,
+                IO::In => IO::Out,
+            };
+            let new = PortRef { node: id, name, io };
+            match wire.io {
+                IO::Out => self.edit(Edit::AddEdge(wire.clone(), new)),
+                IO::In => {
+                    self.disconnect(wire);
+                    self.edit(Edit::AddEdge(new, wire.clone()))
+                }
+            }
+        }
+        id
+    }
+
+    /// The type of an existing port
+    pub fn port_type(&self, port: &PortRef) -> Option<PortType> {
+        let node = self.graph.get_node(port.node);
+        match port.io {
+            IO::In => node.inputs(),
+            IO::Out => node.outputs(),
+        }
+        .get(&port.name)
+        .cloned()
+    }
+
+    /// Start recording an undo step, any edits until it is committed are part of the step
+    pub fn begin_edit(&mut self, label: impl Into<String>) {
+        self.history.begin(label);
+    }
+
+    //// Edits
+    // Changes to the network that can be undone are made through these,
+    // so that they are recorded in the undo step in progress
+
+    /// Apply an edit, and record it in the history
+    pub(crate) fn edit(&mut self, edit: Edit) {
+        self.history.record(edit.clone());
+        edit.apply(self);
+    }
+
+    /// Add a node above the other nodes, returns the id of the new node
+    pub fn add_node(&mut self, template: NodeTemplate, position: Point) -> ShapeId {
+        let id = self.graph.node(template.clone().into());
+        self.shapes.shape_positions.insert_before(0, id, position);
+        self.history.record(Edit::AddNode {
+            id,
+            template,
+            position,
+            index: 0,
+        });
+        id
+    }
+
+    /// Remove a node, the wires connected to it, and its size
+    pub fn remove_node(&mut self, id: ShapeId) {
+        let Some((index, _, position)) = self.shapes.shape_positions.get_full(&id) else {
+            return;
+        };
+        let position = *position;
+        let edges = self
+            .graph
+            .edges()
+            .iter()
+            .filter(|(from, to)| from.node == id || to.node == id)
+            .cloned()
+            .collect_vec();
+        for (from, to) in edges {
+            self.edit(Edit::RemoveEdge(from, to));
+        }
+        self.set_node_size(id, None);
+        self.edit(Edit::RemoveNode {
+            id,
+            template: self.graph.get_node(id).template.duplicate(),
+            position,
+            index,
+        });
+    }
+
+    /// Remove the wires connected to a port
+    pub fn disconnect(&mut self, port: &PortRef) {
+        let edges = self
+            .graph
+            .edges()
+            .iter()
+            .filter(|(from, to)| from == port || to == port)
+            .cloned()
+            .collect_vec();
+        for (from, to) in edges {
+            self.edit(Edit::RemoveEdge(from, to));
+        }
+    }
+
+    /// Replace the template of a node, removing wires from ports it no longer has.
+    /// The runtime state of the new template is kept, but only its persisted state is recorded
+    pub fn set_template(&mut self, id: ShapeId, template: NodeTemplate) {
+        let after = template.duplicate();
+        let before = std::mem::replace(&mut self.graph.get_mut_node(id).template, template);
+        self.history.record(Edit::ChangeNode {
+            id,
+            before: before.duplicate(),
+            after,
+        });
+
+        let node = self.graph.get_node(id);
+        let (inputs, outputs) = (node.inputs(), node.outputs());
+        let invalid = self
+            .graph
+            .edges()
+            .iter()
+            .filter(|(from, to)| {
+                (from.node == id && !outputs.contains_key(&from.name))
+                    || (to.node == id && !inputs.contains_key(&to.name))
+            })
+            .cloned()
+            .collect_vec();
+        for (from, to) in invalid {
+            self.edit(Edit::RemoveEdge(from, to));
+        }
+    }
+
+    pub fn move_node(&mut self, id: ShapeId, position: Point) {
+        if let Some(before) = self.shapes.shape_positions.get(&id) {
+            self.edit(Edit::MoveNode {
+                id,
+                before: *before,
+                after: position,
+            });
+        }
+    }
+
+    /// Set the size of a node, `None` resets it to the size of its template
+    pub fn set_node_size(&mut self, id: ShapeId, size: Option<Vector>) {
+        let before = self.node_sizes.get(&id).copied();
+        if before != size {
+            self.edit(Edit::ResizeNode {
+                id,
+                before,
+                after: size,
+            });
+        }
+    }
+
+    /// Add, replace, or remove (with `None`) an annotation
+    pub fn set_annotation(&mut self, id: AnnotationId, annotation: Option<Annotation>) {
+        let before = self.annotations.get(&id).cloned();
+        if before.is_some() || annotation.is_some() {
+            self.edit(Edit::SetAnnotation {
+                id,
+                before,
+                after: annotation,
+            });
+        }
+    }
+
+    /// Access the history alongside the network that it modifies
+    fn with_history<T>(&mut self, f: impl FnOnce(&mut History, &mut Network) -> T) -> T {
+        let mut history = std::mem::take(&mut self.history);
+        let result = f(&mut history, self);
+        self.history = history;
+        result
+    }
+
+    /// Record the changes made since `begin_edit` as an undo step, and mark unsaved changes.
+    /// Only the most recent `depth` steps are kept, if specified
+    pub fn commit_edit(&mut self, depth: Option<usize>) {
+        if self.history.commit(depth) {
+            self.unsaved_changes = true;
+        }
+    }
+
+    /// Undo or redo until `position` steps of the history are applied.
+    /// Returns true if the network changed
+    pub fn jump_to_history(&mut self, position: usize) -> bool {
+        let changed = self.with_history(|history, network| history.jump(position, network));
+        if changed {
+            self.unsaved_changes = true;
+            // Forget anything that no longer exists
+            let positions = &self.shapes.shape_positions;
+            self.selected_shapes.retain(|id| positions.contains_key(id));
+            self.queued_nodes.retain(|id| positions.contains_key(id));
+            let annotations = &self.annotations;
+            self.selected_annotations
+                .retain(|id| annotations.contains_key(id));
+        }
+        changed
+    }
+
+    pub fn remove_edge(&mut self, port: crate::graph::PortRef) {
+        self.begin_edit("Remove wire");
+        self.disconnect(&port);
+    }
+
+    /// Zoom and pan the camera to show all of the given shapes
+    pub fn zoom_to_shapes(&mut self, ids: &HashSet<ShapeId>, viewport: Size) {
+        let bounds = ids
+            .iter()
+            .filter_map(|id| {
+                let position = *self.shapes.shape_positions.get(id)?;
+                let size = self.node_size(*id);
+                Some((position, position + Vector::new(size.width, size.height)))
+            })
+            .reduce(|(a_min, a_max), (b_min, b_max)| {
+                (
+                    Point::new(a_min.x.min(b_min.x), a_min.y.min(b_min.y)),
+                    Point::new(a_max.x.max(b_max.x), a_max.y.max(b_max.y)),
                 )
+            });
+        if let Some((min, max)) = bounds {
+            self.shapes.camera.fit(min, max, viewport);
+        }
+    }
+
+    /// Arrange the selected nodes in layers following their wires,
+    /// or the whole network if fewer than two nodes are selected
+    pub fn auto_layout(&mut self) {
+        let ids: Vec<_> = if self.selected_shapes.len() > 1 {
+            self.selected_shapes.iter().copied().sorted().collect()
+        } else {
+            self.shapes
+                .shape_positions
+                .keys()
+                .copied()
+                .sorted()
+                .collect()
+        };
+        // Keep the layout where the nodes currently are
+        let Some(origin) = ids
+            .iter()
+            .map(|id| self.shapes.shape_positions[id])
+            .reduce(|a, b| Point::new(a.x.min(b.x), a.y.min(b.y)))
+        else {
+            return;
+        };
+
+        self.begin_edit("Auto layout");
+        let sizes = ids.iter().map(|id| (*id, self.node_size(*id))).collect();
+        for (id, position) in layout::layered_layout(&self.graph, &ids, &sizes, origin) {
+            self.move_node(id, position);
+        }
+    }
+
+    /// Line up the selected nodes
+    pub fn align_selection(&mut self, align: Align) {
+        let bounds: Vec<_> = self
+            .selected_shapes
+            .iter()
+            .filter_map(|id| {
+                let position = *self.shapes.shape_positions.get(id)?;
+                Some((*id, position, self.node_size(*id)))
             })
             .collect();
-        for nx in running_nodes {
-            graph_snap_shot.get_mut_node(nx).status = NodeStatus::Idle;
+        if bounds.len() < 2 {
+            return;
+        }
+        self.begin_edit(align.to_string());
+        for (id, position) in align::align(&bounds, align) {
+            self.move_node(id, position);
         }
+    }
 
-        self.undo_stack
-            .push((graph_snap_shot, self.shapes.shape_positions.clone()));
+    /// Size of a node, which is the size of its template unless the node has been resized
+    pub fn node_size(&self, id: ShapeId) -> Size {
+        match self.node_sizes.get(&id) {
+            Some(size) => Size::new(size.x, size.y),
+            None => self.graph.get_node(id).template.node_size(),
+        }
+    }
+
+    /// Move the bottom right corner of a node to the cursor (in workspace coordinates)
+    pub fn resize_node(&mut self, id: ShapeId, cursor: Point) {
+        if let Some(position) = self.shapes.shape_positions.get(&id) {
+            let min = default_node_size();
+            let size = Vector::new(
+                (cursor.x - position.x).max(min.width),
+                (cursor.y - position.y).max(min.height),
+            );
+            self.set_node_size(id, Some(size));
+        }
+    }
 
-        // Don't let the stack get too big
-        self.undo_stack.truncate(10);
+    /// Move the dragged nodes with the cursor (in workspace coordinates), keeping their offsets.
+    /// The node nearest the cursor snaps to the edges of other nodes within `snap_distance`,
+    /// and otherwise to the grid if one is given
+    pub fn drag_nodes(
+        &mut self,
+        offsets: &[(ShapeId, Vector)],
+        cursor: Point,
+        grid: Option<f32>,
+        snap_distance: Option<f32>,
+    ) {
+        let Some((anchor, anchor_offset)) = offsets
+            .iter()
+            .min_by(|a, b| a.1.length().total_cmp(&b.1.length()))
+        else {
+            return;
+        };
+        let position = cursor + *anchor_offset;
+        let mut snap = grid.map_or(Vector::new(0., 0.), |grid| {
+            align::snap_to_grid(position, grid)
+        });
+        if let Some(distance) = snap_distance {
+            let others = self
+                .shapes
+                .shape_positions
+                .iter()
+                .filter(|(id, _)| !offsets.iter().any(|(dragged, _)| dragged == *id))
+                .map(|(id, p)| (*p, self.node_size(*id)));
+            let size = self.node_size(*anchor);
+            let (x, y) = align::snap_to_edges(position, size, others, distance);
+            snap = Vector::new(x.unwrap_or(snap.x), y.unwrap_or(snap.y));
+        }
 
-        self.redo_stack.clear();
+        for (id, offset) in offsets {
+            self.move_node(*id, cursor + *offset + snap);
+        }
     }
 
-    pub fn remove_edge(&mut self, port: crate::graph::PortRef) {
-        self.stash_state();
-        self.graph.remove_edge(&port);
+    /// Select the shapes inside a selection box.
+    /// Shift adds them to the current selection, and command toggles their selection
+    pub(crate) fn box_select(&mut self, ids: Vec<ShapeId>, modifiers: Modifiers) {
+        let ids: HashSet<_> = ids.into_iter().collect();
+        self.selected_shapes = if modifiers.command() {
+            self.selected_shapes
+                .symmetric_difference(&ids)
+                .copied()
+                .collect()
+        } else if modifiers.shift() {
+            self.selected_shapes.union(&ids).copied().collect()
+        } else {
+            ids
+        };
     }
 
     pub(crate) fn select_node(
@@ -152,7 +763,7 @@ impl Network {
     ) -> Action {
         self.selected_shapes = if modifiers.command() {
             //// Create new nodes on Command + Click
-            self.stash_state();
+            self.begin_edit("Duplicate nodes");
             let selected_shapes = if self.selected_shapes.contains(&nx) {
                 // If clicked node is already selected, copy all selected nodes,
                 self.selected_shapes.clone()
@@ -164,12 +775,8 @@ impl Network {
                 .iter()
                 .map(|id| {
                     let pos = self.shapes.shape_positions[id] + [5., 5.].into();
-                    let new_node = self.graph.get_node(*id).template.duplicate().into();
-                    // *Mutably* add new node to graph
-                    let new_id = self.graph.node(new_node);
-                    // *Mutably* add new position
-                    self.shapes.shape_positions.insert(new_id, pos);
-                    new_id
+                    let template = self.graph.get_node(*id).template.duplicate();
+                    self.add_node(template, pos)
                 })
                 .collect()
         } else if modifiers.shift() {
@@ -194,7 +801,7 @@ impl Network {
                 (
                     *id,
                     (self.shapes.shape_positions[id]
-                        - (cursor_position + self.shapes.camera.position)),
+                        - self.shapes.camera.to_world(cursor_position)),
                 )
             })
             .collect();
@@ -207,7 +814,202 @@ impl Network {
                 .expect("id exists"),
             0,
         );
-        //// Start Drag
+        //// Start Drag, recorded as a move unless nodes were just duplicated
+        self.begin_edit("Move nodes");
         Action::DragNode(offsets)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::nodes::RustNode;
+
+    #[test]
+    fn copy_paste_keeps_internal_wires() {
+        let mut network = Network::default();
+        let constant = network
+            .graph
+            .node(NodeTemplate::RustNode(RustNode::Constant(1.)).into());
+        let cos = network
+            .graph
+            .node(NodeTemplate::RustNode(RustNode::Cos).into());
+        let sin = network
+            .graph
+            .node(NodeTemplate::RustNode(RustNode::Sin).into());
+        network.graph.connect((constant, "out"), (cos, "a"));
+        network.graph.connect((cos, "out"), (sin, "a"));
+        for (id, x) in [(constant, 0.), (cos, 100.), (sin, 200.)] {
+            network
+                .shapes
+                .shape_positions
+                .insert(id, Point::new(x, 50.));
+        }
+
+        // Copy the last two nodes, the wire from `constant` is not internal to the selection
+        network.selected_shapes = [cos, sin].into();
+        let copied = network.copy_selection().unwrap();
+        let pasted = network.paste(&copied, Point::new(0., 0.), &[]).unwrap();
+
+        assert_eq!(pasted.len(), 2);
+        assert_eq!(network.selected_shapes, pasted.iter().copied().collect());
+        let pasted_edges = network.graph.internal_edges(&pasted);
+        assert_eq!(pasted_edges.len(), 1);
+        let (from, to) = &pasted_edges[0];
+        assert_eq!(
+            network.graph.get_node(from.node).template.to_string(),
+            "Cos"
+        );
+        assert_eq!(network.graph.get_node(to.node).template.to_string(), "Sin");
+        // The pasted fragment keeps its layout, relative to the paste position
+        let mut positions: Vec<_> = pasted
+            .iter()
+            .map(|id| network.shapes.shape_positions[id].x)
+            .collect();
+        positions.sort_by(f32::total_cmp);
+        assert_eq!(positions, vec![0., 100.]);
+
+        // Fragments with wires to ports that don't exist are not pasted at all
+        let node_count = network.graph.nodes_ref().len();
+        let stale = copied.replace("\"out\"", "\"missing\"");
+        assert!(network.paste(&stale, Point::new(0., 0.), &[]).is_err());
+        assert_eq!(network.graph.nodes_ref().len(), node_count);
+    }
+
+    #[test]
+    fn paste_rejects_hostile_fragments() {
+        let mut network = Network::default();
+        let node = |id| {
+            (
+                id,
+                NodeTemplate::RustNode(RustNode::Cos),
+                Point::new(0., 0.),
+            )
+        };
+        let port = |node, name: &str, io| PortRef {
+            node,
+            name: name.into(),
+            io,
+        };
+        let fragment = |edges| {
+            ron::to_string(&Fragment {
+                nodes: vec![node(0), node(1)],
+                edges,
+                annotations: vec![],
+                sizes: vec![],
+            })
+            .unwrap()
+        };
+        let hostile = [
+            // Two wires into one input
+            vec![
+                (port(0, "out", IO::Out), port(1, "a", IO::In)),
+                (port(0, "out", IO::Out), port(1, "a", IO::In)),
+            ],
+            // A cycle, which would panic when the graph is sorted
+            vec![
+                (port(0, "out", IO::Out), port(1, "a", IO::In)),
+                (port(1, "out", IO::Out), port(0, "a", IO::In)),
+            ],
+            // A wire to a node outside the fragment
+            vec![(port(0, "out", IO::Out), port(7, "a", IO::In))],
+            // A wire with its ends swapped
+            vec![(port(0, "a", IO::In), port(1, "out", IO::Out))],
+        ];
+        for edges in hostile {
+            assert!(network
+                .paste(&fragment(edges), Point::new(0., 0.), &[])
+                .is_err());
+        }
+        assert!(network.graph.nodes_ref().is_empty());
+        assert!(network.graph.edges().is_empty());
+
+        let valid = fragment(vec![(port(0, "out", IO::Out), port(1, "a", IO::In))]);
+        let pasted = network.paste(&valid, Point::new(0., 0.), &[]).unwrap();
+        assert_eq!(network.graph.internal_edges(&pasted).len(), 1);
+        network.graph.topological_sort();
+    }
+
+    #[test]
+    fn wire_released_in_empty_space() {
+        let mut network = Network::default();
+        let constant = network
+            .graph
+            .node(NodeTemplate::RustNode(RustNode::Constant(1.)).into());
+        network
+            .shapes
+            .shape_positions
+            .insert(constant, Point::new(0., 0.));
+        let output = PortRef {
+            node: constant,
+            name: "out".into(),
+            io: IO::Out,
+        };
+
+        // Real wires connect to complex inputs, but not to a node without inputs
+        let magnitude: NodeData = NodeTemplate::RustNode(RustNode::Magnitude).into();
+        assert_eq!(
+            compatible_port(&magnitude, &IO::Out, &PortType::Real),
+            Some("a".into())
+        );
+        let linspace: NodeData =
+            NodeTemplate::RustNode(RustNode::Linspace(Default::default())).into();
+        assert_eq!(compatible_port(&linspace, &IO::Out, &PortType::Real), None);
+
+        let sin = network.add_connected_node(
+            NodeTemplate::RustNode(RustNode::Sin),
+            &output,
+            Point::new(0., 200.),
+        );
+        assert_eq!(
+            network.graph.incoming_edges(&sin),
+            vec![(
+                output,
+                PortRef {
+                    node: sin,
+                    name: "a".into(),
+                    io: IO::In,
+                }
+            )]
+        );
+        assert_eq!(network.node_at(Point::new(1., 1.)), Some(constant));
+        assert_eq!(network.node_at(Point::new(-1., -1.)), None);
+    }
+    #[test]
+    fn resized_nodes_keep_their_size() {
+        let mut network = Network::default();
+        let plot = network
+            .graph
+            .node(NodeTemplate::RustNode(RustNode::Plot(Default::default())).into());
+        network
+            .shapes
+            .shape_positions
+            .insert(plot, Point::new(10., 10.));
+        let template_size = network.graph.get_node(plot).template.node_size();
+        assert_eq!(network.node_size(plot), template_size);
+
+        network.begin_edit("Resize node");
+        network.resize_node(plot, Point::new(410., 310.));
+        network.commit_edit(None);
+        assert_eq!(network.node_size(plot), Size::new(400., 300.));
+        // Nodes are found using the new size
+        assert_eq!(network.node_at(Point::new(400., 300.)), Some(plot));
+        // Nodes can't be made smaller than the default size
+        network.begin_edit("Resize node");
+        network.resize_node(plot, Point::new(0., 0.));
+        network.commit_edit(None);
+        assert_eq!(network.node_size(plot), default_node_size());
+
+        network.jump_to_history(1);
+        assert_eq!(network.node_size(plot), Size::new(400., 300.));
+        network.jump_to_history(0);
+        assert_eq!(network.node_size(plot), template_size);
+
+        // Pasted nodes keep the size of the copies
+        network.resize_node(plot, Point::new(410., 310.));
+        network.selected_shapes = [plot].into();
+        let copied = network.copy_selection().unwrap();
+        let pasted = network.paste(&copied, Point::new(0., 500.), &[]).unwrap();
+        assert_eq!(network.node_size(pasted[0]), Size::new(400., 300.));
+    }
+}
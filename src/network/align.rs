@@ -0,0 +1,194 @@
+use derive_more::Display;
+use iced::Size;
+use itertools::Itertools;
+
+use crate::{
+    math::{Point, Vector},
+    widget::shapes::ShapeId,
+};
+
+/// Spacing of the workspace snap grid
+pub const GRID_SIZE: f32 = 20.;
+/// Distance on screen, within which dragged nodes snap to the edges of other nodes
+pub const SNAP_DISTANCE: f32 = 8.;
+
+/// Ways of lining up a group of nodes
+#[derive(Debug, Clone, Copy, PartialEq, Display)]
+pub enum Align {
+    #[display("Align left")]
+    Left,
+    #[display("Align horizontal centers")]
+    CenterX,
+    #[display("Align right")]
+    Right,
+    #[display("Align top")]
+    Top,
+    #[display("Align vertical centers")]
+    CenterY,
+    #[display("Align bottom")]
+    Bottom,
+    /// Even horizontal spacing between nodes
+    #[display("Distribute horizontally")]
+    DistributeX,
+    /// Even vertical spacing between nodes
+    #[display("Distribute vertically")]
+    DistributeY,
+}
+
+/// New positions of the nodes in `bounds`, lined up according to `align`
+pub fn align(bounds: &[(ShapeId, Point, Size)], align: Align) -> Vec<(ShapeId, Point)> {
+    let min = |f: fn(&(ShapeId, Point, Size)) -> f32| bounds.iter().map(f).fold(f32::MAX, f32::min);
+    let max = |f: fn(&(ShapeId, Point, Size)) -> f32| bounds.iter().map(f).fold(f32::MIN, f32::max);
+    let mean = |f: fn(&(ShapeId, Point, Size)) -> f32| {
+        bounds.iter().map(f).sum::<f32>() / bounds.len() as f32
+    };
+    match align {
+        Align::Left => {
+            let x = min(|(_, p, _)| p.x);
+            bounds
+                .iter()
+                .map(|(id, p, _)| (*id, Point::new(x, p.y)))
+                .collect()
+        }
+        Align::CenterX => {
+            let center = mean(|(_, p, s)| p.x + s.width / 2.);
+            bounds
+                .iter()
+                .map(|(id, p, s)| (*id, Point::new(center - s.width / 2., p.y)))
+                .collect()
+        }
+        Align::Right => {
+            let right = max(|(_, p, s)| p.x + s.width);
+            bounds
+                .iter()
+                .map(|(id, p, s)| (*id, Point::new(right - s.width, p.y)))
+                .collect()
+        }
+        Align::Top => {
+            let y = min(|(_, p, _)| p.y);
+            bounds
+                .iter()
+                .map(|(id, p, _)| (*id, Point::new(p.x, y)))
+                .collect()
+        }
+        Align::CenterY => {
+            let center = mean(|(_, p, s)| p.y + s.height / 2.);
+            bounds
+                .iter()
+                .map(|(id, p, s)| (*id, Point::new(p.x, center - s.height / 2.)))
+                .collect()
+        }
+        Align::Bottom => {
+            let bottom = max(|(_, p, s)| p.y + s.height);
+            bounds
+                .iter()
+                .map(|(id, p, s)| (*id, Point::new(p.x, bottom - s.height)))
+                .collect()
+        }
+        Align::DistributeX => distribute(bounds, |p| p.x, |s| s.width, |p, x| Point::new(x, p.y)),
+        Align::DistributeY => distribute(bounds, |p| p.y, |s| s.height, |p, y| Point::new(p.x, y)),
+    }
+}
+
+/// Keep the first and last node along an axis in place,
+/// and space the others so that the gaps between all nodes are equal
+fn distribute(
+    bounds: &[(ShapeId, Point, Size)],
+    start: fn(&Point) -> f32,
+    length: fn(&Size) -> f32,
+    with: fn(&Point, f32) -> Point,
+) -> Vec<(ShapeId, Point)> {
+    let sorted: Vec<_> = bounds
+        .iter()
+        .sorted_by(|a, b| start(&a.1).total_cmp(&start(&b.1)))
+        .collect();
+    let (Some(first), Some(last)) = (sorted.first(), sorted.last()) else {
+        return vec![];
+    };
+    let span = start(&last.1) + length(&last.2) - start(&first.1);
+    let gap = (span - sorted.iter().map(|(_, _, s)| length(s)).sum::<f32>())
+        / (sorted.len().max(2) - 1) as f32;
+
+    let mut next = start(&first.1);
+    sorted
+        .into_iter()
+        .map(|(id, p, s)| {
+            let position = with(p, next);
+            next += length(s) + gap;
+            (*id, position)
+        })
+        .collect()
+}
+
+/// Offset that moves the rectangle at `position` onto the grid
+pub fn snap_to_grid(position: Point, grid: f32) -> Vector {
+    Vector::new(
+        (position.x / grid).round() * grid - position.x,
+        (position.y / grid).round() * grid - position.y,
+    )
+}
+
+/// Offset that lines up an edge or center of a rectangle with the nearest edge or center of
+/// another, along each axis where one is closer than `distance`
+pub fn snap_to_edges(
+    position: Point,
+    size: Size,
+    others: impl Iterator<Item = (Point, Size)>,
+    distance: f32,
+) -> (Option<f32>, Option<f32>) {
+    let lines = |start: f32, length: f32| [start, start + length / 2., start + length];
+    let nearest = |current: [f32; 3], targets: &[[f32; 3]]| {
+        targets
+            .iter()
+            .flatten()
+            .flat_map(|target| current.iter().map(move |line| target - line))
+            .filter(|offset| offset.abs() < distance)
+            .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+    };
+    let (x_targets, y_targets): (Vec<_>, Vec<_>) = others
+        .map(|(p, s)| (lines(p.x, s.width), lines(p.y, s.height)))
+        .unzip();
+    (
+        nearest(lines(position.x, size.width), &x_targets),
+        nearest(lines(position.y, size.height), &y_targets),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn distribute_evenly() {
+        let size = Size::new(10., 10.);
+        let bounds = [
+            (0, Point::new(0., 5.), size),
+            (1, Point::new(100., 0.), size),
+            (2, Point::new(20., 3.), size),
+        ];
+        let aligned = align(&bounds, Align::DistributeX);
+        assert_eq!(
+            aligned,
+            [
+                (0, Point::new(0., 5.)),
+                (2, Point::new(50., 3.)),
+                (1, Point::new(100., 0.)),
+            ]
+        );
+        let aligned = align(&bounds, Align::Bottom);
+        assert!(aligned.iter().all(|(_, p)| p.y == 5.));
+    }
+
+    #[test]
+    fn snapping() {
+        let size = Size::new(100., 50.);
+        assert_eq!(
+            snap_to_grid(Point::new(29., -12.), 20.),
+            Vector::new(-9., -8.)
+        );
+        // Left edge snaps to the other node's right edge, y is too far away to snap
+        let other = (Point::new(0., 0.), size);
+        let snapped = snap_to_edges(Point::new(103., 200.), size, [other].into_iter(), 8.);
+        assert_eq!(snapped, (Some(-3.), None));
+    }
+}
@@ -0,0 +1,172 @@
+use iced::{keyboard::Modifiers, Size};
+
+use crate::{
+    app::Action,
+    math::{Point, Vector},
+    widget::annotation::{
+        Annotation, AnnotationColor, AnnotationId, AnnotationKind, AnnotationPart, MIN_SIZE,
+        TITLE_HEIGHT,
+    },
+};
+
+use super::Network;
+
+/// Space left between a new frame and the nodes it encloses
+const FRAME_PADDING: f32 = 20.;
+
+impl Network {
+    /// Add an annotation on top of the others, returns its id
+    pub fn add_annotation(&mut self, annotation: Annotation) -> AnnotationId {
+        let id = self.annotations.keys().max().map_or(0, |id| id + 1);
+        self.set_annotation(id, Some(annotation));
+        id
+    }
+
+    /// Change an existing annotation
+    fn update_annotation(&mut self, id: AnnotationId, f: impl FnOnce(&mut Annotation)) {
+        if let Some(mut annotation) = self.annotations.get(&id).cloned() {
+            f(&mut annotation);
+            self.set_annotation(id, Some(annotation));
+        }
+    }
+
+    /// Add a note centered at `center`, and select it
+    pub fn add_note(&mut self, center: Point) {
+        self.begin_edit("Add note");
+        let note = Annotation::note(center);
+        let position = center - Vector::new(note.width, note.height) * 0.5;
+        let id = self.add_annotation(Annotation { position, ..note });
+        self.selected_shapes.clear();
+        self.selected_annotations = [id].into();
+    }
+
+    /// Add a frame around the selected nodes, or centered at `center` if none are selected
+    pub fn add_frame(&mut self, center: Point) {
+        let bounds = self
+            .selected_shapes
+            .iter()
+            .filter_map(|id| {
+                let position = *self.shapes.shape_positions.get(id)?;
+                let size = self.node_size(*id);
+                Some((position, position + Vector::new(size.width, size.height)))
+            })
+            .reduce(|(a_min, a_max), (b_min, b_max)| {
+                (
+                    Point::new(a_min.x.min(b_min.x), a_min.y.min(b_min.y)),
+                    Point::new(a_max.x.max(b_max.x), a_max.y.max(b_max.y)),
+                )
+            });
+        let (position, size) = match bounds {
+            Some((min, max)) => (
+                min - Vector::new(FRAME_PADDING, FRAME_PADDING + TITLE_HEIGHT),
+                Size::new(
+                    max.x - min.x + 2. * FRAME_PADDING,
+                    max.y - min.y + 2. * FRAME_PADDING + TITLE_HEIGHT,
+                ),
+            ),
+            None => (center - Vector::new(150., 100.), Size::new(300., 200.)),
+        };
+
+        self.begin_edit("Add frame");
+        let id = self.add_annotation(Annotation::frame(position, size));
+        self.selected_shapes.clear();
+        self.selected_annotations = [id].into();
+    }
+
+    /// Select an annotation, and start moving or resizing it.
+    /// Frames move the nodes and annotations that they enclose
+    pub(crate) fn select_annotation(
+        &mut self,
+        id: AnnotationId,
+        part: AnnotationPart,
+        modifiers: Modifiers,
+        cursor: Point,
+    ) -> Action {
+        if modifiers.shift() {
+            self.selected_annotations.insert(id);
+        } else if !self.selected_annotations.contains(&id) {
+            self.selected_shapes.clear();
+            self.selected_annotations = [id].into();
+        }
+        let kind = match self.annotations[&id].kind {
+            AnnotationKind::Note => "note",
+            AnnotationKind::Frame => "frame",
+        };
+
+        if part == AnnotationPart::ResizeHandle {
+            self.begin_edit(format!("Resize {kind}"));
+            return Action::ResizeAnnotation(id);
+        }
+        self.begin_edit(format!("Move {kind}"));
+
+        let frames: Vec<_> = self
+            .selected_annotations
+            .iter()
+            .filter_map(|id| self.annotations.get(id))
+            .filter(|annotation| annotation.kind == AnnotationKind::Frame)
+            .collect();
+        let cursor = self.shapes.camera.to_world(cursor);
+        let nodes = self
+            .shapes
+            .shape_positions
+            .iter()
+            .filter(|(id, position)| {
+                let size = self.node_size(**id);
+                self.selected_shapes.contains(id)
+                    || frames.iter().any(|frame| frame.encloses(**position, size))
+            })
+            .map(|(id, position)| (*id, *position - cursor))
+            .collect();
+        let annotations = self
+            .annotations
+            .iter()
+            .filter(|(id, annotation)| {
+                self.selected_annotations.contains(id)
+                    || frames
+                        .iter()
+                        .any(|frame| frame.encloses(annotation.position, annotation.size()))
+            })
+            .map(|(id, annotation)| (*id, annotation.position - cursor))
+            .collect();
+        Action::DragAnnotations { annotations, nodes }
+    }
+
+    /// Move the dragged annotations with the cursor (in workspace coordinates)
+    pub fn drag_annotations(&mut self, offsets: &[(AnnotationId, Vector)], cursor: Point) {
+        for (id, offset) in offsets {
+            self.update_annotation(*id, |annotation| annotation.position = cursor + *offset);
+        }
+    }
+
+    /// Move the bottom right corner of an annotation to the cursor (in workspace coordinates)
+    pub fn resize_annotation(&mut self, id: AnnotationId, cursor: Point) {
+        self.update_annotation(id, |annotation| {
+            annotation.width = (cursor.x - annotation.position.x).max(MIN_SIZE.width);
+            annotation.height = (cursor.y - annotation.position.y).max(MIN_SIZE.height);
+        });
+    }
+
+    /// The annotation being edited, if exactly one annotation and no nodes are selected
+    pub fn edited_annotation(&self) -> Option<(AnnotationId, &Annotation)> {
+        match (
+            self.selected_shapes.is_empty(),
+            self.selected_annotations.len(),
+        ) {
+            (true, 1) => {
+                let id = *self.selected_annotations.iter().next()?;
+                Some((id, self.annotations.get(&id)?))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn set_annotation_text(&mut self, id: AnnotationId, text: String) {
+        self.begin_edit("Edit text");
+        self.update_annotation(id, |annotation| annotation.text = text);
+    }
+
+    pub fn set_annotation_color(&mut self, id: AnnotationId, color: AnnotationColor) {
+        self.begin_edit("Change color");
+        self.update_annotation(id, |annotation| annotation.color = color);
+    }
+}
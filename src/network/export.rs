@@ -0,0 +1,179 @@
+use std::path::Path;
+
+use itertools::Itertools;
+use log::{trace, warn};
+
+use crate::{
+    graph::Graph,
+    gui_node::GUINode,
+    nodes::{
+        figure::{ExportSize, FigureStyle},
+        status::{NodeError, NodeStatus},
+        NodeData,
+    },
+    widget::shapes::ShapeId,
+};
+
+use super::Network;
+
+impl Network {
+    /// Compute every node once, parents before children, and wait for the results.
+    /// Used to regenerate outputs without the application running
+    pub fn compute_blocking(&mut self) {
+        for nx in self.graph.topological_sort() {
+            let node = self.graph.get_node(nx).clone();
+            let (nx, result) = Graph::compute_node(nx, node, self.graph.get_input_data(&nx));
+            match result {
+                Ok((output, node)) => {
+                    trace!("Compute complete: {} #{nx}", node.template);
+                    self.graph.update_wire_data(nx, output);
+                    self.graph.set_node_data(
+                        nx,
+                        NodeData {
+                            status: match node.status {
+                                NodeStatus::Waiting => NodeStatus::Waiting,
+                                _ => NodeStatus::Idle,
+                            },
+                            run_time: None,
+                            template: node.template,
+                        },
+                    );
+                }
+                Err(node_error) => {
+                    let node = self.graph.get_mut_node(nx);
+                    warn!("Compute failed {} #{nx}, {node_error}", node.template);
+                    node.status = NodeStatus::Error(node_error);
+                    self.graph.update_wire_data(nx, [].into());
+                }
+            }
+        }
+    }
+
+    /// A node by its id, or by its name when no other node has the same name
+    pub fn find_node(&self, id_or_name: &str) -> Result<ShapeId, NodeError> {
+        let nodes = self.graph.nodes_ref();
+        if let Some(id) = id_or_name.parse().ok().filter(|id| nodes.contains(id)) {
+            return Ok(id);
+        }
+        let named: Vec<_> = nodes
+            .into_iter()
+            .filter(|nx| self.graph.get_node(*nx).template.name() == id_or_name)
+            .collect();
+        match named[..] {
+            [id] => Ok(id),
+            [] => Err(NodeError::Config(format!(
+                "No node with id or name '{id_or_name}'"
+            ))),
+            _ => Err(NodeError::Config(format!(
+                "There are {} nodes named '{id_or_name}', use one of their ids: {named:?}",
+                named.len()
+            ))),
+        }
+    }
+
+    /// Write what a viewer node shows to a PNG or SVG file, dark on white.
+    /// Fails rather than writing a blank figure when the viewer has no data to show
+    pub fn export_figure(
+        &self,
+        id: ShapeId,
+        path: &Path,
+        size: ExportSize,
+    ) -> Result<(), NodeError> {
+        let node = self.graph.get_node(id);
+        if let NodeStatus::Error(error) = &node.status {
+            return Err(error.clone());
+        }
+        if let Some((nx, error)) = self.graph.upstream(id).into_iter().sorted().find_map(|nx| {
+            match &self.graph.get_node(nx).status {
+                NodeStatus::Error(error) => Some((nx, error)),
+                _ => None,
+            }
+        }) {
+            return Err(NodeError::Input(format!(
+                "{} #{nx} failed: {error}",
+                self.graph.get_node(nx).template
+            )));
+        }
+        let inputs = self.graph.get_input_data(&id);
+        if inputs.is_empty() {
+            return Err(NodeError::Input(format!(
+                "{} #{id} has no input data",
+                node.template
+            )));
+        }
+        node.template
+            .figure(&inputs, size.layout_size(), FigureStyle::print())?
+            .export(path, size)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::nodes::{filters::GaussianConfig, plot::Plot, NodeTemplate, RustNode};
+
+    #[test]
+    fn export_without_the_app() {
+        let mut network = Network::default();
+        let linspace = network
+            .graph
+            .node(NodeTemplate::RustNode(RustNode::Linspace(Default::default())).into());
+        let plot = network
+            .graph
+            .node(NodeTemplate::RustNode(RustNode::Plot(Plot::default())).into());
+        network.graph.connect((linspace, "out"), (plot, "y"));
+        network.compute_blocking();
+
+        assert_eq!(network.find_node("Plot"), Ok(plot));
+        assert_eq!(network.find_node(&linspace.to_string()), Ok(linspace));
+        assert!(network.find_node("Volume").is_err());
+
+        let path = std::env::temp_dir().join(format!("foray_export_{}.svg", std::process::id()));
+        network
+            .export_figure(plot, &path, ExportSize::default())
+            .unwrap();
+        let svg = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(svg.contains("<polyline"));
+
+        let path = path.with_extension("txt");
+        assert!(network
+            .export_figure(plot, &path, ExportSize::default())
+            .is_err());
+        assert!(network
+            .export_figure(linspace, &path.with_extension("png"), ExportSize::default())
+            .is_err());
+    }
+
+    #[test]
+    fn export_fails_without_data() {
+        let mut network = Network::default();
+        let linspace = network
+            .graph
+            .node(NodeTemplate::RustNode(RustNode::Linspace(Default::default())).into());
+        let smooth = network.graph.node(
+            NodeTemplate::RustNode(RustNode::GaussianSmooth(GaussianConfig { sigma: 0. })).into(),
+        );
+        let plot = network
+            .graph
+            .node(NodeTemplate::RustNode(RustNode::Plot(Plot::default())).into());
+        let unconnected = network
+            .graph
+            .node(NodeTemplate::RustNode(RustNode::Plot(Plot::default())).into());
+        network.graph.connect((linspace, "out"), (smooth, "a"));
+        network.graph.connect((smooth, "out"), (plot, "y"));
+        network.compute_blocking();
+
+        let path = std::env::temp_dir().join(format!("foray_blank_{}.svg", std::process::id()));
+        let Err(NodeError::Input(error)) =
+            network.export_figure(plot, &path, ExportSize::default())
+        else {
+            panic!("expected the failed upstream node to be reported")
+        };
+        assert!(error.contains(&format!("#{smooth}")));
+        assert!(network
+            .export_figure(unconnected, &path, ExportSize::default())
+            .is_err());
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use iced::Size;
+
+use crate::{gui_node::GuiGraph, math::Point, widget::shapes::ShapeId};
+
+/// Space between layers, leaves room for the wires between output and input ports
+const LAYER_GAP: f32 = 60.;
+/// Space between nodes within a layer
+const NODE_GAP: f32 = 30.;
+/// Number of up and down sweeps used to reduce wire crossings
+const SWEEPS: usize = 4;
+
+/// Layered (Sugiyama-style) layout of the nodes `ids`, with wires flowing from top to bottom.
+/// Nodes are assigned to layers in topological order, ordered within their layer to reduce
+/// crossings, and then spaced according to their `sizes`.
+/// Returns new positions with the top left of the layout at `origin`
+pub(super) fn layered_layout(
+    graph: &GuiGraph,
+    ids: &[ShapeId],
+    sizes: &HashMap<ShapeId, Size>,
+    origin: Point,
+) -> HashMap<ShapeId, Point> {
+    let order: Vec<_> = graph
+        .topological_sort()
+        .into_iter()
+        .filter(|id| ids.contains(id))
+        .collect();
+    let parents = |id: &ShapeId| -> Vec<ShapeId> {
+        graph
+            .incoming_edges(id)
+            .into_iter()
+            .map(|(from, _to)| from.node)
+            .filter(|parent| ids.contains(parent))
+            .collect()
+    };
+    let children = |id: &ShapeId| -> Vec<ShapeId> {
+        graph
+            .outgoing_edges(id)
+            .into_iter()
+            .map(|to| to.node)
+            .filter(|child| ids.contains(child))
+            .collect()
+    };
+
+    //// Layer assignment, each node is placed one layer below its deepest parent
+    let mut layer_of: HashMap<ShapeId, usize> = HashMap::new();
+    for id in &order {
+        let layer = parents(id)
+            .iter()
+            .map(|parent| layer_of[parent] + 1)
+            .max()
+            .unwrap_or(0);
+        layer_of.insert(*id, layer);
+    }
+    let mut layers: Vec<Vec<ShapeId>> = vec![vec![]; layer_of.values().max().map_or(0, |l| l + 1)];
+    for id in &order {
+        layers[layer_of[id]].push(*id);
+    }
+
+    //// Crossing reduction, order nodes by the average position of their neighbours
+    let mut rank: HashMap<ShapeId, f32> = HashMap::new();
+    let update_rank = |rank: &mut HashMap<ShapeId, f32>, layer: &[ShapeId]| {
+        for (i, id) in layer.iter().enumerate() {
+            rank.insert(*id, i as f32);
+        }
+    };
+    layers
+        .iter()
+        .for_each(|layer| update_rank(&mut rank, layer));
+    for sweep in 0..SWEEPS {
+        let downward = sweep % 2 == 0;
+        let layer_indices: Vec<_> = match downward {
+            true => (1..layers.len()).collect(),
+            false => (0..layers.len().saturating_sub(1)).rev().collect(),
+        };
+        for l in layer_indices {
+            let barycenter = |id: &ShapeId| {
+                let neighbours = match downward {
+                    true => parents(id),
+                    false => children(id),
+                };
+                match neighbours.len() {
+                    // Nodes without neighbours keep their place
+                    0 => rank[id],
+                    n => neighbours.iter().map(|n| rank[n]).sum::<f32>() / n as f32,
+                }
+            };
+            let mut keyed: Vec<_> = layers[l].iter().map(|id| (barycenter(id), *id)).collect();
+            keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+            layers[l] = keyed.into_iter().map(|(_, id)| id).collect();
+            update_rank(&mut rank, &layers[l]);
+        }
+    }
+
+    //// Coordinate assignment, centre nodes under their parents without overlapping
+    let mut positions: HashMap<ShapeId, Point> = HashMap::new();
+    let mut y = origin.y;
+    for layer in &layers {
+        let mut min_x = origin.x;
+        let mut layer_height: f32 = 0.;
+        for id in layer {
+            let size = sizes[id];
+            let parent_centres: Vec<_> = parents(id)
+                .iter()
+                .filter_map(|parent| {
+                    let position = positions.get(parent)?;
+                    Some(position.x + sizes[parent].width / 2.)
+                })
+                .collect();
+            let x = match parent_centres.len() {
+                0 => min_x,
+                n => (parent_centres.iter().sum::<f32>() / n as f32 - size.width / 2.).max(min_x),
+            };
+            positions.insert(*id, Point::new(x, y));
+            min_x = x + size.width + NODE_GAP;
+            layer_height = layer_height.max(size.height);
+        }
+        y += layer_height + LAYER_GAP;
+    }
+    positions
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::gui_node::GUINode;
+    use crate::nodes::{NodeTemplate, RustNode};
+
+    #[test]
+    fn layers_follow_wires() {
+        let mut graph = GuiGraph::new();
+        let mut node = |rn| graph.node(NodeTemplate::RustNode(rn).into());
+        let (a, b, c, d) = (
+            node(RustNode::Constant(1.)),
+            node(RustNode::Constant(2.)),
+            node(RustNode::Add),
+            node(RustNode::Multiply),
+        );
+        graph.connect((a, "out"), (c, "a"));
+        graph.connect((b, "out"), (c, "b"));
+        graph.connect((c, "out"), (d, "a"));
+        graph.connect((a, "out"), (d, "b"));
+
+        let ids = [a, b, c, d];
+        let sizes = ids
+            .iter()
+            .map(|id| (*id, graph.get_node(*id).template.node_size()))
+            .collect();
+        let positions = layered_layout(&graph, &ids, &sizes, Point::new(10., 20.));
+
+        // Sources are in the first layer, and each node is below its parents
+        assert_eq!(positions[&a].y, 20.);
+        assert_eq!(positions[&b].y, 20.);
+        assert!(positions[&c].y > positions[&a].y);
+        assert!(positions[&d].y > positions[&c].y);
+        // Nodes in the same layer don't overlap
+        let width = sizes[&a].width;
+        assert!((positions[&a].x - positions[&b].x).abs() >= width + NODE_GAP);
+        assert_eq!(positions[&a].x.min(positions[&b].x), 10.);
+    }
+}
@@ -1,32 +1,53 @@
 use std::time::Duration;
 
+pub mod complex_nodes;
 pub mod constant;
+pub mod expression;
+pub mod figure;
+pub mod filters;
+pub mod generators;
 pub mod linspace;
 pub mod math_nodes;
 pub mod plot;
 pub mod plot_complex;
 pub mod port;
+pub mod reduction;
 pub mod status;
 pub mod vector_field;
+pub mod viewer;
+pub mod volume;
 
 use crate::app::Message;
 use crate::graph::GraphNode;
 use crate::gui_node::{GUINode, PortDataContainer, PortDataReference};
 use crate::interface::node::default_node_size;
+use crate::nodes::complex_nodes::{complex_to_real, complex_unary, real_to_complex};
+use crate::nodes::expression::ExpressionConfig;
+use crate::nodes::figure::{Figure, FigureStyle};
+use crate::nodes::filters::{
+    convolve_nd, ConvolveConfig, FirBand, FirConfig, GaussianConfig, Window, WindowConfig,
+};
+use crate::nodes::generators::{
+    meshgrid, ChirpConfig, ImpulseConfig, Noise, NoiseConfig, PhantomConfig,
+};
 use crate::nodes::linspace::LinspaceConfig;
 use crate::nodes::math_nodes::{binary_operation, unary_operation};
 use crate::nodes::plot::Plot;
 use crate::nodes::plot_complex::Plot2D;
+use crate::nodes::reduction::{HistogramConfig, Reduction, ReductionConfig};
 use crate::python::py_node::PyNode;
 use crate::StableMap;
 use derive_more::derive::{Debug, Display};
 use iced::widget::text;
 use iced::{Font, Size};
+use numpy::Complex64;
 use port::{PortData, PortType};
 use serde::{Deserialize, Serialize};
 use status::{NodeError, NodeStatus};
 use strum::{EnumIter, VariantNames};
 use vector_field::VectorField;
+use viewer::View;
+use volume::VolumeViewer;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct NodeData {
@@ -50,14 +71,71 @@ pub enum RustNode {
     Cos,
     Sin,
     Sinc,
+    Magnitude,
+    Phase,
+    RealPart,
+    ImagPart,
+    Conjugate,
+    ComplexFromRealImag,
+    ComplexFromPolar,
+    #[display("Expression")]
+    Expression(ExpressionConfig),
     #[display("Linspace")]
     Linspace(LinspaceConfig),
+    #[display("UniformNoise")]
+    UniformNoise(NoiseConfig),
+    #[display("GaussianNoise")]
+    GaussianNoise(NoiseConfig),
+    #[display("ComplexGaussianNoise")]
+    ComplexGaussianNoise(NoiseConfig),
+    Meshgrid,
+    #[display("Impulse")]
+    Impulse(ImpulseConfig),
+    #[display("Chirp")]
+    Chirp(ChirpConfig),
+    #[display("SheppLogan")]
+    SheppLogan(PhantomConfig),
+    Convolve,
+    #[display("Convolve1D")]
+    Convolve1D(ConvolveConfig),
+    #[display("GaussianSmooth")]
+    GaussianSmooth(GaussianConfig),
+    #[display("HannWindow")]
+    HannWindow(WindowConfig),
+    #[display("HammingWindow")]
+    HammingWindow(WindowConfig),
+    #[display("KaiserWindow")]
+    KaiserWindow(WindowConfig),
+    #[display("FirLowPass")]
+    FirLowPass(FirConfig),
+    #[display("FirHighPass")]
+    FirHighPass(FirConfig),
+    #[display("FirBandPass")]
+    FirBandPass(FirConfig),
     #[display("Plot")]
     Plot(Plot),
     #[display("Plot2D")]
     Plot2D(Plot2D),
     #[display("VectorField")]
     VectorField(VectorField),
+    #[display("VolumeViewer")]
+    VolumeViewer(VolumeViewer),
+    #[display("View")]
+    View(Box<View>),
+    #[display("Sum")]
+    Sum(ReductionConfig),
+    #[display("Mean")]
+    Mean(ReductionConfig),
+    #[display("Max")]
+    Max(ReductionConfig),
+    #[display("Min")]
+    Min(ReductionConfig),
+    #[display("Std")]
+    Std(ReductionConfig),
+    #[display("RootSumOfSquares")]
+    RootSumOfSquares(ReductionConfig),
+    #[display("Histogram")]
+    Histogram(HistogramConfig),
 }
 
 #[derive(Clone, Debug, Display, Serialize, Deserialize, PartialEq, PartialOrd)]
@@ -83,14 +161,41 @@ impl From<NodeTemplate> for NodeData {
     }
 }
 impl NodeTemplate {
+    /// A copy with only the persisted configuration, leaving out state derived from compute results
     pub fn duplicate(&self) -> Self {
         match self {
             NodeTemplate::RustNode(RustNode::Plot2D(plot2d)) => {
                 NodeTemplate::RustNode(RustNode::Plot2D(Plot2D {
                     image_handle: None,
+                    range: None,
                     ..plot2d.clone()
                 }))
             }
+            NodeTemplate::RustNode(RustNode::VolumeViewer(viewer)) => {
+                NodeTemplate::RustNode(RustNode::VolumeViewer(VolumeViewer {
+                    images: vec![],
+                    shape: vec![],
+                    range: None,
+                    ..viewer.clone()
+                }))
+            }
+            NodeTemplate::RustNode(RustNode::View(view)) => {
+                NodeTemplate::RustNode(RustNode::View(Box::new(View {
+                    detected: None,
+                    plot_2d: Plot2D {
+                        image_handle: None,
+                        range: None,
+                        ..view.plot_2d.clone()
+                    },
+                    volume: VolumeViewer {
+                        images: vec![],
+                        shape: vec![],
+                        range: None,
+                        ..view.volume.clone()
+                    },
+                    ..(**view).clone()
+                })))
+            }
             _ => self.clone(),
         }
     }
@@ -135,13 +240,55 @@ impl NodeData {
                         }),
                     )?,
 
+                    RustNode::Magnitude => complex_to_real(inputs, |a| a.norm())?,
+                    RustNode::Phase => complex_to_real(inputs, |a| a.arg())?,
+                    RustNode::RealPart => complex_to_real(inputs, |a| a.re)?,
+                    RustNode::ImagPart => complex_to_real(inputs, |a| a.im)?,
+                    RustNode::Conjugate => complex_unary(inputs, |a| a.conj())?,
+                    RustNode::ComplexFromRealImag => {
+                        real_to_complex(inputs, ("real", "imag"), Complex64::new)?
+                    }
+                    RustNode::ComplexFromPolar => {
+                        real_to_complex(inputs, ("mag", "phase"), Complex64::from_polar)?
+                    }
+                    RustNode::Expression(expression) => expression.compute(inputs)?,
+
                     RustNode::Linspace(linspace_config) => linspace_config.compute(inputs),
-                    RustNode::Plot(_) => [].into(),
+                    RustNode::UniformNoise(config) => Noise::Uniform.compute(config)?,
+                    RustNode::GaussianNoise(config) => Noise::Gaussian.compute(config)?,
+                    RustNode::ComplexGaussianNoise(config) => {
+                        Noise::ComplexGaussian.compute(config)?
+                    }
+                    RustNode::Meshgrid => meshgrid(inputs)?,
+                    RustNode::Impulse(config) => config.compute()?,
+                    RustNode::Chirp(config) => config.compute(),
+                    RustNode::SheppLogan(config) => config.compute(),
+                    RustNode::Convolve => convolve_nd(inputs)?,
+                    RustNode::Convolve1D(config) => config.compute(inputs)?,
+                    RustNode::GaussianSmooth(config) => config.compute(inputs)?,
+                    RustNode::HannWindow(config) => Window::Hann.compute(config),
+                    RustNode::HammingWindow(config) => Window::Hamming.compute(config),
+                    RustNode::KaiserWindow(config) => Window::Kaiser.compute(config),
+                    RustNode::FirLowPass(config) => FirBand::LowPass.compute(config, inputs)?,
+                    RustNode::FirHighPass(config) => FirBand::HighPass.compute(config, inputs)?,
+                    RustNode::FirBandPass(config) => FirBand::BandPass.compute(config, inputs)?,
+                    RustNode::Plot(plot) => plot.compute(inputs)?,
                     RustNode::Plot2D(plot_2d) => {
-                        let out = plot_2d.input_changed(inputs);
+                        let out = plot_2d.input_changed(inputs)?;
                         [("out".into(), out)].into()
                     }
                     RustNode::VectorField(_) => [].into(),
+                    RustNode::VolumeViewer(viewer) => viewer.input_changed(inputs)?,
+                    RustNode::View(view) => view.compute(inputs)?,
+                    RustNode::Histogram(histogram) => histogram.compute(inputs)?,
+                    RustNode::Sum(config) => Reduction::Sum.compute(config, inputs)?,
+                    RustNode::Mean(config) => Reduction::Mean.compute(config, inputs)?,
+                    RustNode::Max(config) => Reduction::Max.compute(config, inputs)?,
+                    RustNode::Min(config) => Reduction::Min.compute(config, inputs)?,
+                    RustNode::Std(config) => Reduction::Std.compute(config, inputs)?,
+                    RustNode::RootSumOfSquares(config) => {
+                        Reduction::RootSumOfSquares.compute(config, inputs)?
+                    }
                 },
 
                 NodeTemplate::PyNode(py_node) => py_node.compute(inputs)?,
@@ -158,6 +305,49 @@ impl RustNode {
     pub fn template_variants(&self) -> NodeData {
         NodeTemplate::RustNode(self.clone()).into()
     }
+
+    /// The reduction performed by a node, if it is a reduction node
+    pub fn reduction(&self) -> Option<(Reduction, ReductionConfig)> {
+        match self {
+            RustNode::Sum(config) => Some((Reduction::Sum, *config)),
+            RustNode::Mean(config) => Some((Reduction::Mean, *config)),
+            RustNode::Max(config) => Some((Reduction::Max, *config)),
+            RustNode::Min(config) => Some((Reduction::Min, *config)),
+            RustNode::Std(config) => Some((Reduction::Std, *config)),
+            RustNode::RootSumOfSquares(config) => Some((Reduction::RootSumOfSquares, *config)),
+            _ => None,
+        }
+    }
+
+    /// The distribution sampled by a node, if it is a noise node
+    pub fn noise(&self) -> Option<(Noise, &NoiseConfig)> {
+        match self {
+            RustNode::UniformNoise(config) => Some((Noise::Uniform, config)),
+            RustNode::GaussianNoise(config) => Some((Noise::Gaussian, config)),
+            RustNode::ComplexGaussianNoise(config) => Some((Noise::ComplexGaussian, config)),
+            _ => None,
+        }
+    }
+
+    /// The window function produced by a node, if it is a window node
+    pub fn window(&self) -> Option<(Window, WindowConfig)> {
+        match self {
+            RustNode::HannWindow(config) => Some((Window::Hann, *config)),
+            RustNode::HammingWindow(config) => Some((Window::Hamming, *config)),
+            RustNode::KaiserWindow(config) => Some((Window::Kaiser, *config)),
+            _ => None,
+        }
+    }
+
+    /// The pass band of a node, if it is an FIR filter node
+    pub fn fir(&self) -> Option<(FirBand, FirConfig)> {
+        match self {
+            RustNode::FirLowPass(config) => Some((FirBand::LowPass, *config)),
+            RustNode::FirHighPass(config) => Some((FirBand::HighPass, *config)),
+            RustNode::FirBandPass(config) => Some((FirBand::BandPass, *config)),
+            _ => None,
+        }
+    }
 }
 
 impl GraphNode<NodeData, PortType, PortData> for NodeData {
@@ -180,14 +370,66 @@ impl GraphNode<NodeData, PortType, PortData> for NodeData {
                 RustNode::Cos => unary_in,
                 RustNode::Sin => unary_in,
                 RustNode::Sinc => unary_in,
-                RustNode::Linspace(_) => [].into(),
-                RustNode::Plot(_) => [
-                    ("x".to_string(), PortType::Real),
-                    ("y".to_string(), PortType::Real),
+                // Real and integer inputs are treated as complex numbers
+                RustNode::Magnitude
+                | RustNode::Phase
+                | RustNode::RealPart
+                | RustNode::ImagPart
+                | RustNode::Conjugate => [("a".to_string(), PortType::Dynamic)].into(),
+                RustNode::ComplexFromRealImag => [
+                    ("real".to_string(), PortType::ArrayReal),
+                    ("imag".to_string(), PortType::ArrayReal),
+                ]
+                .into(),
+                RustNode::ComplexFromPolar => [
+                    ("mag".to_string(), PortType::ArrayReal),
+                    ("phase".to_string(), PortType::ArrayReal),
+                ]
+                .into(),
+                RustNode::Expression(expression) => expression
+                    .variables()
+                    .into_iter()
+                    .map(|name| (name, PortType::Dynamic))
+                    .collect(),
+                RustNode::Linspace(_)
+                | RustNode::UniformNoise(_)
+                | RustNode::GaussianNoise(_)
+                | RustNode::ComplexGaussianNoise(_)
+                | RustNode::Impulse(_)
+                | RustNode::Chirp(_)
+                | RustNode::SheppLogan(_)
+                | RustNode::HannWindow(_)
+                | RustNode::HammingWindow(_)
+                | RustNode::KaiserWindow(_) => [].into(),
+                // Complex signals and kernels are filtered as well as real ones
+                RustNode::Convolve | RustNode::Convolve1D(_) => [
+                    ("a".to_string(), PortType::Dynamic),
+                    ("kernel".to_string(), PortType::Dynamic),
+                ]
+                .into(),
+                RustNode::GaussianSmooth(_)
+                | RustNode::FirLowPass(_)
+                | RustNode::FirHighPass(_)
+                | RustNode::FirBandPass(_) => [("a".to_string(), PortType::Dynamic)].into(),
+                RustNode::Meshgrid => [
+                    ("x".to_string(), PortType::ArrayReal),
+                    ("y".to_string(), PortType::ArrayReal),
                 ]
                 .into(),
+                RustNode::Plot(plot) => plot.inputs(),
                 RustNode::Plot2D(_) => [("a".to_string(), PortType::ArrayReal)].into(),
                 RustNode::VectorField(_) => [("a".to_string(), PortType::ArrayReal)].into(),
+                RustNode::VolumeViewer(_) | RustNode::View(_) => {
+                    [("a".to_string(), PortType::Dynamic)].into()
+                }
+                // Real, integer and complex inputs are all accepted
+                RustNode::Sum(_)
+                | RustNode::Mean(_)
+                | RustNode::Max(_)
+                | RustNode::Min(_)
+                | RustNode::Std(_)
+                | RustNode::RootSumOfSquares(_)
+                | RustNode::Histogram(_) => [("a".to_string(), PortType::Dynamic)].into(),
             },
             NodeTemplate::PyNode(py_node) => py_node.ports.clone().unwrap_or_default().inputs,
         }
@@ -196,6 +438,7 @@ impl GraphNode<NodeData, PortType, PortData> for NodeData {
     fn outputs(&self) -> StableMap<String, PortType> {
         let real_out = [("out".to_string(), PortType::Real)].into();
         let array_out = [("out".to_string(), PortType::ArrayReal)].into();
+        let complex_out = [("out".to_string(), PortType::ArrayComplex)].into();
         match &self.template {
             NodeTemplate::RustNode(rn) => match rn {
                 RustNode::Identity => real_out,
@@ -207,10 +450,62 @@ impl GraphNode<NodeData, PortType, PortData> for NodeData {
                 RustNode::Cos => real_out,
                 RustNode::Sin => real_out,
                 RustNode::Sinc => real_out,
+                RustNode::Magnitude => array_out,
+                RustNode::Phase => array_out,
+                RustNode::RealPart => array_out,
+                RustNode::ImagPart => array_out,
+                RustNode::Conjugate => complex_out,
+                RustNode::ComplexFromRealImag => complex_out,
+                RustNode::ComplexFromPolar => complex_out,
+                RustNode::Expression(expression) => {
+                    [("out".to_string(), expression.output_type())].into()
+                }
                 RustNode::Linspace(_) => real_out,
+                RustNode::UniformNoise(_) => array_out,
+                RustNode::GaussianNoise(_) => array_out,
+                RustNode::ComplexGaussianNoise(_) => complex_out,
+                RustNode::Meshgrid => [
+                    ("x".to_string(), PortType::ArrayReal),
+                    ("y".to_string(), PortType::ArrayReal),
+                ]
+                .into(),
+                RustNode::Impulse(_) => array_out,
+                RustNode::Chirp(_) => array_out,
+                RustNode::SheppLogan(_) => array_out,
+                RustNode::Convolve => array_out,
+                RustNode::Convolve1D(_) => array_out,
+                RustNode::GaussianSmooth(_) => array_out,
+                RustNode::HannWindow(_)
+                | RustNode::HammingWindow(_)
+                | RustNode::KaiserWindow(_) => [
+                    ("n".to_string(), PortType::ArrayReal),
+                    ("out".to_string(), PortType::ArrayReal),
+                ]
+                .into(),
+                RustNode::FirLowPass(_) | RustNode::FirHighPass(_) | RustNode::FirBandPass(_) => [
+                    ("out".to_string(), PortType::ArrayReal),
+                    ("n".to_string(), PortType::ArrayReal),
+                    ("kernel".to_string(), PortType::ArrayReal),
+                ]
+                .into(),
                 RustNode::Plot(_) => array_out,
                 RustNode::Plot2D(_) => array_out,
                 RustNode::VectorField(_) => [].into(),
+                RustNode::VolumeViewer(_) | RustNode::View(_) => [].into(),
+                RustNode::Sum(_)
+                | RustNode::Mean(_)
+                | RustNode::Max(_)
+                | RustNode::Min(_)
+                | RustNode::Std(_)
+                | RustNode::RootSumOfSquares(_) => {
+                    let (reduction, config) = rn.reduction().expect("reduction node");
+                    [("out".to_string(), reduction.output_type(&config))].into()
+                }
+                RustNode::Histogram(_) => [
+                    ("x".to_string(), PortType::ArrayReal),
+                    ("y".to_string(), PortType::ArrayReal),
+                ]
+                .into(),
             },
             NodeTemplate::PyNode(py_node) => py_node.ports.clone().unwrap_or_default().outputs,
         }
@@ -226,7 +521,31 @@ impl GraphNode<NodeData, PortType, PortData> for NodeData {
             .map(|k| (k.clone(), inputs[k].read().unwrap()))
             .collect();
 
-        self.fallible_compute(data)
+        let (outputs, node) = self.fallible_compute(data)?;
+        //// Check outputs against their declared shapes
+        let declared = node.outputs();
+        for (name, data) in &outputs {
+            if let Some(port_type) = declared.get(name) {
+                port_type.validate(name, data)?;
+            }
+        }
+        Ok((outputs, node))
+    }
+
+    fn compute_unconnected(self) -> Result<(StableMap<String, PortData>, NodeData), NodeError> {
+        // FIR filters design their kernel without a signal to filter
+        if let NodeTemplate::RustNode(rn) = &self.template {
+            if rn.fir().is_some() {
+                return self.compute([].into());
+            }
+        }
+        Ok((
+            [].into(),
+            NodeData {
+                status: NodeStatus::Waiting,
+                ..self
+            },
+        ))
     }
 }
 
@@ -243,10 +562,43 @@ impl GUINode for NodeTemplate {
                 RustNode::Cos => "cos".to_string(),
                 RustNode::Sin => "sin".to_string(),
                 RustNode::Sinc => "sinc".to_string(),
+                RustNode::Magnitude => "Magnitude".to_string(),
+                RustNode::Phase => "Phase".to_string(),
+                RustNode::RealPart => "Real".to_string(),
+                RustNode::ImagPart => "Imaginary".to_string(),
+                RustNode::Conjugate => "Conjugate".to_string(),
+                RustNode::ComplexFromRealImag => "Complex (Real, Imag)".to_string(),
+                RustNode::ComplexFromPolar => "Complex (Mag, Phase)".to_string(),
+                RustNode::Expression(_) => "Expression".to_string(),
                 RustNode::Linspace(_linspace_config) => "Linspace".to_string(),
+                RustNode::UniformNoise(_) => "Uniform Noise".to_string(),
+                RustNode::GaussianNoise(_) => "Gaussian Noise".to_string(),
+                RustNode::ComplexGaussianNoise(_) => "Complex Gaussian Noise".to_string(),
+                RustNode::Meshgrid => "Meshgrid".to_string(),
+                RustNode::Impulse(_) => "Impulse".to_string(),
+                RustNode::Chirp(_) => "Chirp".to_string(),
+                RustNode::SheppLogan(_) => "Shepp-Logan Phantom".to_string(),
+                RustNode::Convolve => "Convolve".to_string(),
+                RustNode::Convolve1D(_) => "Convolve 1D".to_string(),
+                RustNode::GaussianSmooth(_) => "Gaussian Smooth".to_string(),
+                RustNode::HannWindow(_) => "Hann Window".to_string(),
+                RustNode::HammingWindow(_) => "Hamming Window".to_string(),
+                RustNode::KaiserWindow(_) => "Kaiser Window".to_string(),
+                RustNode::FirLowPass(_) => "FIR Low-pass".to_string(),
+                RustNode::FirHighPass(_) => "FIR High-pass".to_string(),
+                RustNode::FirBandPass(_) => "FIR Band-pass".to_string(),
                 RustNode::Plot(_) => "Plot".to_string(),
                 RustNode::Plot2D(_) => "Plot 2D".to_string(),
                 RustNode::VectorField(_) => "Plot Vector Field".to_string(),
+                RustNode::VolumeViewer(_) => "Volume Viewer".to_string(),
+                RustNode::View(_) => "View".to_string(),
+                RustNode::Sum(_) => "Sum".to_string(),
+                RustNode::Mean(_) => "Mean".to_string(),
+                RustNode::Max(_) => "Max".to_string(),
+                RustNode::Min(_) => "Min".to_string(),
+                RustNode::Std(_) => "Standard Deviation".to_string(),
+                RustNode::RootSumOfSquares(_) => "Root Sum of Squares".to_string(),
+                RustNode::Histogram(_) => "Histogram".to_string(),
             },
             NodeTemplate::PyNode(py_node) => py_node
                 .absolute_path
@@ -261,27 +613,40 @@ impl GUINode for NodeTemplate {
         &self,
         id: u32,
         input_data: StableMap<String, PortDataContainer>,
-    ) -> iced::Element<Message> {
+    ) -> iced::Element<'_, Message> {
         let operation = |s| {
             text(s)
                 .font(Font::with_name("DejaVu Math TeX Gyre"))
                 .size(30)
                 .into()
         };
-        let trig = |s| {
+        fn trig<'a>(s: impl text::IntoFragment<'a>) -> iced::Element<'a, Message> {
             text(s)
                 .size(20)
                 .font(Font::with_name("DejaVu Math TeX Gyre"))
                 .into()
-        };
+        }
 
         match self {
             NodeTemplate::RustNode(rn) => match rn {
                 RustNode::Constant(value) => constant::view(id, *value),
                 RustNode::Linspace(linspace_config) => linspace_config.view(id),
+                RustNode::Expression(expression) => expression.view(id),
+                RustNode::Impulse(config) => config.view(id),
+                RustNode::Chirp(config) => config.view(id),
+                RustNode::SheppLogan(config) => config.view(id),
+                RustNode::Meshgrid => trig("meshgrid"),
+                RustNode::Convolve => operation("∗"),
+                RustNode::Convolve1D(config) => trig(match config.axis {
+                    Some(axis) => format!("∗ axis {axis}"),
+                    None => "∗".to_string(),
+                }),
+                RustNode::GaussianSmooth(config) => config.view(id),
                 RustNode::Plot(plot) => plot.view(id, input_data),
                 RustNode::Plot2D(plot) => plot.view(id, input_data),
                 RustNode::VectorField(vf) => vf.view(id, input_data),
+                RustNode::VolumeViewer(viewer) => viewer.view(),
+                RustNode::View(view) => view.view(id, input_data),
                 RustNode::Add => operation("+"),
                 RustNode::Subtract => operation("−"),
                 RustNode::Multiply => operation("×"),
@@ -289,7 +654,44 @@ impl GUINode for NodeTemplate {
                 RustNode::Cos => trig("cos(α)"),
                 RustNode::Sin => trig("sin(α)"),
                 RustNode::Sinc => trig("sinc(α)"),
-
+                RustNode::Magnitude => trig("|α|"),
+                RustNode::Phase => trig("∠α"),
+                RustNode::RealPart => trig("Re(α)"),
+                RustNode::ImagPart => trig("Im(α)"),
+                RustNode::Conjugate => trig("α*"),
+                RustNode::ComplexFromRealImag => trig("a + ib"),
+                RustNode::ComplexFromPolar => trig("r·eⁱᶿ"),
+                RustNode::Histogram(histogram) => trig(format!("hist[{}]", histogram.bins)),
+                RustNode::Sum(config)
+                | RustNode::Mean(config)
+                | RustNode::Max(config)
+                | RustNode::Min(config)
+                | RustNode::Std(config)
+                | RustNode::RootSumOfSquares(config) => {
+                    let (op, _) = rn.reduction().expect("reduction node");
+                    trig(match config.axis {
+                        Some(axis) => format!("{} axis {axis}", op.symbol()),
+                        None => op.symbol().to_string(),
+                    })
+                }
+                RustNode::UniformNoise(config)
+                | RustNode::GaussianNoise(config)
+                | RustNode::ComplexGaussianNoise(config) => {
+                    let (noise, _) = rn.noise().expect("noise node");
+                    noise.view(id, config)
+                }
+                RustNode::HannWindow(config)
+                | RustNode::HammingWindow(config)
+                | RustNode::KaiserWindow(config) => {
+                    let (window, _) = rn.window().expect("window node");
+                    window.view(id, *config)
+                }
+                RustNode::FirLowPass(config)
+                | RustNode::FirHighPass(config)
+                | RustNode::FirBandPass(config) => {
+                    let (band, _) = rn.fir().expect("FIR filter node");
+                    band.view(id, *config)
+                }
                 _ => text(self.name()).into(),
             },
             NodeTemplate::PyNode(_) => text(self.name()).into(),
@@ -300,26 +702,87 @@ impl GUINode for NodeTemplate {
         match self {
             NodeTemplate::RustNode(rn) => match rn {
                 RustNode::Linspace(_) => Size::new(dft.width * 2., dft.height),
+                RustNode::Expression(_) => Size::new(dft.width * 2., dft.height),
+                RustNode::Impulse(_)
+                | RustNode::Chirp(_)
+                | RustNode::SheppLogan(_)
+                | RustNode::UniformNoise(_)
+                | RustNode::GaussianNoise(_)
+                | RustNode::ComplexGaussianNoise(_)
+                | RustNode::GaussianSmooth(_)
+                | RustNode::HannWindow(_)
+                | RustNode::HammingWindow(_)
+                | RustNode::KaiserWindow(_)
+                | RustNode::FirLowPass(_)
+                | RustNode::FirHighPass(_)
+                | RustNode::FirBandPass(_) => Size::new(dft.width * 2., dft.height),
                 RustNode::Plot(_) => dft * 2.,
                 RustNode::Plot2D(_) => (dft.width * 2., dft.width * 2.).into(),
                 RustNode::VectorField(_) => (dft.width * 2., dft.width * 2.).into(),
+                RustNode::VolumeViewer(viewer) => match viewer.images.len() > 1 {
+                    true => (dft.width * 3., dft.width * 2.).into(),
+                    false => (dft.width * 2., dft.width * 2.).into(),
+                },
+                RustNode::View(view) => view.node_size(),
                 _ => dft,
             },
             NodeTemplate::PyNode(_) => dft,
         }
     }
 
+    fn is_viewer(&self) -> bool {
+        matches!(
+            self,
+            NodeTemplate::RustNode(
+                RustNode::Plot(_)
+                    | RustNode::Plot2D(_)
+                    | RustNode::VectorField(_)
+                    | RustNode::VolumeViewer(_)
+                    | RustNode::View(_)
+            )
+        )
+    }
+
+    fn figure(
+        &self,
+        input_data: &StableMap<String, PortDataContainer>,
+        size: Size,
+        style: FigureStyle,
+    ) -> Result<Figure, NodeError> {
+        match self {
+            NodeTemplate::RustNode(RustNode::Plot(plot)) => plot.figure(input_data, size, style),
+            NodeTemplate::RustNode(RustNode::Plot2D(plot)) => plot.figure(input_data, size, style),
+            NodeTemplate::RustNode(RustNode::VectorField(plot)) => {
+                plot.figure(input_data, size, style)
+            }
+            NodeTemplate::RustNode(RustNode::VolumeViewer(viewer)) => {
+                viewer.figure(input_data, size, style)
+            }
+            NodeTemplate::RustNode(RustNode::View(view)) => view.figure(input_data, size, style),
+            _ => Err(NodeError::Config(format!(
+                "{} can't be exported",
+                self.name()
+            ))),
+        }
+    }
+
     fn config_view(
         &self,
         id: u32,
         input_data: StableMap<String, PortDataContainer>,
-    ) -> Option<iced::Element<Message>> {
+    ) -> Option<iced::Element<'_, Message>> {
         match &self {
             NodeTemplate::RustNode(rn) => match rn {
                 RustNode::Plot(plot) => plot.config_view(id, input_data),
                 RustNode::Plot2D(plot) => plot.config_view(id, input_data),
                 RustNode::VectorField(plot) => plot.config_view(id, input_data),
-                _ => None,
+                RustNode::VolumeViewer(viewer) => viewer.config_view(id),
+                RustNode::View(view) => view.config_view(id, input_data),
+                RustNode::Histogram(histogram) => Some(histogram.config_view(id)),
+                RustNode::Convolve1D(config) => Some(config.config_view(id)),
+                rn => rn
+                    .reduction()
+                    .map(|(reduction, config)| reduction.config_view(id, config)),
             },
             NodeTemplate::PyNode(pn) => pn.config_view(id, input_data),
         }
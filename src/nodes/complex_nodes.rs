@@ -0,0 +1,171 @@
+use crate::{gui_node::PortDataReference, nodes::NodeError, StableMap};
+use ndarray::{ArrayD, Zip};
+use numpy::{Complex64, IxDyn};
+
+use super::{expression::broadcast_shape, port::PortData};
+
+/// Apply `f` element-wise to a complex (or real) input, producing a real output.
+/// Scalar inputs produce scalar outputs
+pub fn complex_to_real(
+    inputs: StableMap<String, PortDataReference>,
+    f: fn(Complex64) -> f64,
+) -> Result<StableMap<String, PortData>, NodeError> {
+    let out = match &**inputs.get("a").ok_or(NodeError::input_error("a"))? {
+        PortData::Complex(a) => PortData::Real(f(*a)),
+        PortData::Real(a) => PortData::Real(f((*a).into())),
+        PortData::Integer(a) => PortData::Real(f((*a as f64).into())),
+        PortData::ArrayComplex(a) => PortData::ArrayReal(a.mapv(f)),
+        PortData::ArrayReal(a) | PortData::Dynamic(a) => {
+            PortData::ArrayReal(a.mapv(|v| f(v.into())))
+        }
+        PortData::ArrayInteger(a) => PortData::ArrayReal(a.mapv(|v| f((v as f64).into()))),
+        PortData::Object(_) => return Err(object_error()),
+    };
+
+    Ok([("out".into(), out)].into())
+}
+
+/// Apply `f` element-wise to a complex (or real) input, producing a complex output.
+/// Scalar inputs produce scalar outputs
+pub fn complex_unary(
+    inputs: StableMap<String, PortDataReference>,
+    f: fn(Complex64) -> Complex64,
+) -> Result<StableMap<String, PortData>, NodeError> {
+    let out = match &**inputs.get("a").ok_or(NodeError::input_error("a"))? {
+        PortData::Complex(a) => PortData::Complex(f(*a)),
+        PortData::Real(a) => PortData::Complex(f((*a).into())),
+        PortData::Integer(a) => PortData::Complex(f((*a as f64).into())),
+        PortData::ArrayComplex(a) => PortData::ArrayComplex(a.mapv(f)),
+        PortData::ArrayReal(a) | PortData::Dynamic(a) => {
+            PortData::ArrayComplex(a.mapv(|v| f(v.into())))
+        }
+        PortData::ArrayInteger(a) => PortData::ArrayComplex(a.mapv(|v| f((v as f64).into()))),
+        PortData::Object(_) => return Err(object_error()),
+    };
+
+    Ok([("out".into(), out)].into())
+}
+
+/// Build a complex output from two real inputs, e.g. (real, imag) or (mag, phase).
+/// Arrays are broadcast against each other, and scalar inputs produce a scalar output
+pub fn real_to_complex(
+    inputs: StableMap<String, PortDataReference>,
+    (a_name, b_name): (&str, &str),
+    f: fn(f64, f64) -> Complex64,
+) -> Result<StableMap<String, PortData>, NodeError> {
+    let a = &**inputs.get(a_name).ok_or(NodeError::input_error(a_name))?;
+    let b = &**inputs.get(b_name).ok_or(NodeError::input_error(b_name))?;
+
+    let out = match (real_scalar(a), real_scalar(b)) {
+        (Some(a), Some(b)) => PortData::Complex(f(a, b)),
+        _ => {
+            let (a, b) = (real_array(a, a_name)?, real_array(b, b_name)?);
+            let shape = broadcast_shape(a.shape(), b.shape()).ok_or_else(|| {
+                NodeError::Input(format!(
+                    "'{a_name}' with shape {:?} can't be combined with '{b_name}' with shape {:?}",
+                    a.shape(),
+                    b.shape()
+                ))
+            })?;
+            PortData::ArrayComplex(
+                Zip::from(a.broadcast(shape.clone()).expect("shape is broadcastable"))
+                    .and(b.broadcast(shape).expect("shape is broadcastable"))
+                    .map_collect(|a, b| f(*a, *b)),
+            )
+        }
+    };
+
+    Ok([("out".into(), out)].into())
+}
+
+fn real_scalar(data: &PortData) -> Option<f64> {
+    match data {
+        PortData::Real(v) => Some(*v),
+        PortData::Integer(v) => Some(*v as f64),
+        _ => None,
+    }
+}
+
+fn real_array(data: &PortData, port_name: &str) -> Result<ArrayD<f64>, NodeError> {
+    match data {
+        PortData::Real(v) => Ok(ArrayD::from_elem(IxDyn(&[1]), *v)),
+        PortData::Integer(v) => Ok(ArrayD::from_elem(IxDyn(&[1]), *v as f64)),
+        PortData::ArrayReal(a) | PortData::Dynamic(a) => Ok(a.clone()),
+        PortData::ArrayInteger(a) => Ok(a.mapv(|v| v as f64)),
+        _ => Err(NodeError::Input(format!(
+            "Input '{port_name}' must be real valued"
+        ))),
+    }
+}
+
+fn object_error() -> NodeError {
+    NodeError::Input("Complex operations are not supported for Object inputs".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::GraphNode;
+    use crate::nodes::{filters::GaussianConfig, port::PortType, NodeData, NodeTemplate, RustNode};
+    use ndarray::array;
+    use std::f64::consts::PI;
+    use std::sync::RwLock;
+
+    #[test]
+    fn real_and_complex_inputs_connect() {
+        for template in [
+            RustNode::Magnitude,
+            RustNode::Conjugate,
+            RustNode::Convolve,
+            RustNode::GaussianSmooth(GaussianConfig::default()),
+        ] {
+            let node: NodeData = NodeTemplate::RustNode(template).into();
+            for (_, input) in node.inputs() {
+                assert!(PortType::ArrayReal.connects_to(&input));
+                assert!(PortType::ArrayComplex.connects_to(&input));
+            }
+        }
+    }
+
+    #[test]
+    fn broadcast() {
+        let a = array![[1., 2.], [3., 4.]].into_dyn();
+        let row = array![10., 20.].into_dyn();
+        assert_eq!(broadcast_shape(a.shape(), row.shape()), Some(a.raw_dim()));
+        assert_eq!(broadcast_shape(row.shape(), a.shape()), Some(a.raw_dim()));
+        assert_eq!(broadcast_shape(a.shape(), &[3]), None);
+
+        // Both operands can be stretched
+        let real = RwLock::new(PortData::ArrayReal(array![[1.], [2.]].into_dyn()));
+        let imag = RwLock::new(PortData::ArrayReal(array![[1., 2., 3.]].into_dyn()));
+        let inputs = [
+            ("real".to_string(), real.read().unwrap()),
+            ("imag".to_string(), imag.read().unwrap()),
+        ]
+        .into();
+        let out = real_to_complex(inputs, ("real", "imag"), Complex64::new).unwrap();
+        let PortData::ArrayComplex(out) = &out["out"] else {
+            panic!("expected complex array output")
+        };
+        assert_eq!(out.shape(), &[2, 3]);
+        assert_eq!(out[[1, 2]], Complex64::new(2., 3.));
+    }
+
+    #[test]
+    fn from_polar() {
+        let mag = RwLock::new(PortData::ArrayReal(array![1., 2.].into_dyn()));
+        let phase = RwLock::new(PortData::Real(PI / 2.));
+        let inputs = [
+            ("mag".to_string(), mag.read().unwrap()),
+            ("phase".to_string(), phase.read().unwrap()),
+        ]
+        .into();
+
+        let out = real_to_complex(inputs, ("mag", "phase"), Complex64::from_polar).unwrap();
+        let PortData::ArrayComplex(out) = &out["out"] else {
+            panic!("expected complex array output")
+        };
+        assert!((out[[0]] - Complex64::new(0., 1.)).norm() < 1e-12);
+        assert!((out[[1]] - Complex64::new(0., 2.)).norm() < 1e-12);
+    }
+}
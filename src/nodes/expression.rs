@@ -0,0 +1,645 @@
+use std::collections::BTreeSet;
+
+use crate::app::Message;
+use crate::gui_node::PortDataReference;
+use crate::interface::numeric_input::styled_text_input;
+use crate::nodes::{NodeError, NodeTemplate, RustNode};
+use crate::StableMap;
+use iced::widget::{container, text_input};
+use iced::Element;
+use ndarray::{ArrayD, Zip};
+use numpy::{Complex64, IxDyn};
+use serde::{Deserialize, Serialize};
+
+use super::port::{PortData, PortType};
+
+/// A user typed formula, evaluated element-wise over the node's inputs.
+/// Inputs are inferred from the variable names used in the formula
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
+pub struct ExpressionConfig {
+    pub formula: String,
+    /// The last formula that parsed, which the ports follow while a formula is half typed
+    #[serde(default)]
+    parsed: String,
+}
+
+impl Default for ExpressionConfig {
+    fn default() -> Self {
+        Self::new("a * b")
+    }
+}
+
+impl ExpressionConfig {
+    pub fn new(formula: impl Into<String>) -> Self {
+        let formula = formula.into();
+        Self {
+            parsed: formula.clone(),
+            formula,
+        }
+    }
+
+    /// Replace the formula, keeping the current ports if it doesn't parse yet
+    pub fn with_formula(&self, formula: String) -> Self {
+        match parse(&formula) {
+            Ok(_) => Self::new(formula),
+            Err(_) => Self {
+                formula,
+                parsed: self.parsed.clone(),
+            },
+        }
+    }
+
+    /// The formula, or the last one that parsed
+    fn expr(&self) -> Option<Expr> {
+        parse(&self.formula).or_else(|_| parse(&self.parsed)).ok()
+    }
+
+    /// Names of the variables used in the formula, which become the node's inputs
+    pub fn variables(&self) -> BTreeSet<String> {
+        self.expr().map(|expr| expr.variables()).unwrap_or_default()
+    }
+
+    /// Formulas with complex constants are complex and ones that only pass their inputs
+    /// through real valued functions are real, otherwise the output follows the inputs
+    pub fn output_type(&self) -> PortType {
+        match self.expr() {
+            Some(expr) if expr.is_complex() => PortType::ArrayComplex,
+            Some(expr) if expr.is_real() => PortType::ArrayReal,
+            _ => PortType::Dynamic,
+        }
+    }
+
+    pub fn compute(
+        &self,
+        inputs: StableMap<String, PortDataReference>,
+    ) -> Result<StableMap<String, PortData>, NodeError> {
+        let expr = parse(&self.formula)?;
+        let variables = expr
+            .variables()
+            .into_iter()
+            .map(|name| {
+                let data = inputs.get(&name).ok_or(NodeError::input_error(&name))?;
+                Ok((name, Value::try_from(&**data)?))
+            })
+            .collect::<Result<StableMap<_, _>, NodeError>>()?;
+
+        Ok([("out".into(), expr.evaluate(&variables)?.into())].into())
+    }
+
+    pub fn view(&self, id: u32) -> Element<'_, Message> {
+        container(styled_text_input(
+            text_input("a * b", &self.formula).on_input(move |formula| {
+                Message::UpdateNodeTemplate(
+                    id,
+                    NodeTemplate::RustNode(RustNode::Expression(self.with_formula(formula))),
+                )
+            }),
+        ))
+        .padding([0., 10.])
+        .into()
+    }
+}
+
+// Parsing
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Imaginary(f64),
+    Identifier(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Caret,
+    LeftParen,
+    RightParen,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Exp,
+    Log,
+    Sqrt,
+    Sin,
+    Cos,
+    Tan,
+    Abs,
+    Angle,
+    Real,
+    Imag,
+    Conj,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "exp" => Function::Exp,
+            "log" => Function::Log,
+            "sqrt" => Function::Sqrt,
+            "sin" => Function::Sin,
+            "cos" => Function::Cos,
+            "tan" => Function::Tan,
+            "abs" => Function::Abs,
+            "angle" => Function::Angle,
+            "real" => Function::Real,
+            "imag" => Function::Imag,
+            "conj" => Function::Conj,
+            _ => return None,
+        })
+    }
+
+    /// Functions that always produce real values
+    fn is_real_valued(&self) -> bool {
+        matches!(
+            self,
+            Function::Abs | Function::Angle | Function::Real | Function::Imag
+        )
+    }
+
+    fn real(&self, v: f64) -> f64 {
+        match self {
+            Function::Exp => v.exp(),
+            Function::Log => v.ln(),
+            Function::Sqrt => v.sqrt(),
+            Function::Sin => v.sin(),
+            Function::Cos => v.cos(),
+            Function::Tan => v.tan(),
+            Function::Abs => v.abs(),
+            Function::Angle => Complex64::from(v).arg(),
+            Function::Real => v,
+            Function::Imag => 0.0,
+            Function::Conj => v,
+        }
+    }
+
+    fn complex(&self, v: Complex64) -> Complex64 {
+        match self {
+            Function::Exp => v.exp(),
+            Function::Log => v.ln(),
+            Function::Sqrt => v.sqrt(),
+            Function::Sin => v.sin(),
+            Function::Cos => v.cos(),
+            Function::Tan => v.tan(),
+            Function::Abs => v.norm().into(),
+            Function::Angle => v.arg().into(),
+            Function::Real => v.re.into(),
+            Function::Imag => v.im.into(),
+            Function::Conj => v.conj(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Imaginary(f64),
+    Variable(String),
+    Negate(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Function, Box<Expr>),
+}
+
+impl Expr {
+    fn variables(&self) -> BTreeSet<String> {
+        match self {
+            Expr::Number(_) | Expr::Imaginary(_) => [].into(),
+            Expr::Variable(name) => [name.clone()].into(),
+            Expr::Negate(e) | Expr::Call(_, e) => e.variables(),
+            Expr::Binary(_, a, b) => a.variables().into_iter().chain(b.variables()).collect(),
+        }
+    }
+
+    /// Real whatever the inputs are
+    fn is_real(&self) -> bool {
+        match self {
+            Expr::Number(_) => true,
+            Expr::Imaginary(_) | Expr::Variable(_) => false,
+            Expr::Negate(e) => e.is_real(),
+            Expr::Call(f, e) => f.is_real_valued() || e.is_real(),
+            Expr::Binary(_, a, b) => a.is_real() && b.is_real(),
+        }
+    }
+
+    /// Complex whatever the inputs are
+    fn is_complex(&self) -> bool {
+        match self {
+            Expr::Number(_) | Expr::Variable(_) => false,
+            Expr::Imaginary(_) => true,
+            Expr::Negate(e) => e.is_complex(),
+            Expr::Call(f, e) => !f.is_real_valued() && e.is_complex(),
+            Expr::Binary(_, a, b) => a.is_complex() || b.is_complex(),
+        }
+    }
+
+    fn evaluate(&self, variables: &StableMap<String, Value>) -> Result<Value, NodeError> {
+        Ok(match self {
+            Expr::Number(v) => Value::Real(ArrayD::from_elem(IxDyn(&[]), *v)),
+            Expr::Imaginary(v) => {
+                Value::Complex(ArrayD::from_elem(IxDyn(&[]), Complex64::new(0., *v)))
+            }
+            Expr::Variable(name) => variables
+                .get(name)
+                .cloned()
+                .ok_or(NodeError::input_error(name))?,
+            Expr::Negate(e) => match e.evaluate(variables)? {
+                Value::Real(a) => Value::Real(-a),
+                Value::Complex(a) => Value::Complex(-a),
+            },
+            Expr::Call(f, e) => match e.evaluate(variables)? {
+                Value::Real(a) => Value::Real(a.mapv(|v| f.real(v))),
+                Value::Complex(a) if f.is_real_valued() => Value::Real(a.mapv(|v| f.complex(v).re)),
+                Value::Complex(a) => Value::Complex(a.mapv(|v| f.complex(v))),
+            },
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.evaluate(variables)?, b.evaluate(variables)?);
+                match (a, b) {
+                    (Value::Real(a), Value::Real(b)) => {
+                        Value::Real(broadcast_zip(&a, &b, |a, b| match op {
+                            BinaryOp::Add => a + b,
+                            BinaryOp::Subtract => a - b,
+                            BinaryOp::Multiply => a * b,
+                            BinaryOp::Divide => a / b,
+                            BinaryOp::Power => a.powf(b),
+                        })?)
+                    }
+                    (a, b) => Value::Complex(broadcast_zip(
+                        &a.into_complex(),
+                        &b.into_complex(),
+                        |a, b| match op {
+                            BinaryOp::Add => a + b,
+                            BinaryOp::Subtract => a - b,
+                            BinaryOp::Multiply => a * b,
+                            BinaryOp::Divide => a / b,
+                            BinaryOp::Power => a.powc(b),
+                        },
+                    )?),
+                }
+            }
+        })
+    }
+}
+
+fn tokenize(formula: &str) -> Result<Vec<Token>, NodeError> {
+    let mut tokens = vec![];
+    let mut chars = formula.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '0'..='9' | '.' => {
+                let mut number = String::new();
+                while let Some(&c) = chars.peek() {
+                    // Allow exponents like 1e-3
+                    let is_exponent_sign = (c == '-' || c == '+')
+                        && number.ends_with(['e', 'E'])
+                        && !number.is_empty();
+                    if c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || is_exponent_sign {
+                        number.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let value = number.parse::<f64>().map_err(|_| {
+                    NodeError::Config(format!("Could not understand number '{number}'"))
+                })?;
+                if chars.peek() == Some(&'j') {
+                    chars.next();
+                    tokens.push(Token::Imaginary(value));
+                } else {
+                    tokens.push(Token::Number(value));
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut identifier = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' {
+                        identifier.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Identifier(identifier));
+            }
+            _ => {
+                chars.next();
+                tokens.push(match c {
+                    '+' => Token::Plus,
+                    '-' => Token::Minus,
+                    '*' if chars.peek() == Some(&'*') => {
+                        chars.next();
+                        Token::Caret
+                    }
+                    '*' => Token::Star,
+                    '/' => Token::Slash,
+                    '^' => Token::Caret,
+                    '(' => Token::LeftParen,
+                    ')' => Token::RightParen,
+                    _ => {
+                        return Err(NodeError::Config(format!(
+                            "Unexpected character '{c}' in formula"
+                        )))
+                    }
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parse a formula into an expression tree
+///
+/// expr  := term (('+' | '-') term)*
+/// term  := unary (('*' | '/') unary)*
+/// unary := ('-' | '+') unary | power
+/// power := atom (('^' | '**') unary)?
+/// atom  := number | imaginary | identifier | function '(' expr ')' | '(' expr ')'
+fn parse(formula: &str) -> Result<Expr, NodeError> {
+    let tokens = tokenize(formula)?;
+    let mut parser = Parser { tokens, index: 0 };
+    let expr = parser.expr()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(NodeError::Config(format!(
+            "Unexpected {token:?} in formula"
+        ))),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), NodeError> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(NodeError::Config(format!(
+                "Expected {expected:?}, found {token:?}"
+            ))),
+            None => Err(NodeError::Config(format!(
+                "Expected {expected:?}, found end of formula"
+            ))),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, NodeError> {
+        let mut lhs = self.term()?;
+        while let Some(op) = match self.peek() {
+            Some(Token::Plus) => Some(BinaryOp::Add),
+            Some(Token::Minus) => Some(BinaryOp::Subtract),
+            _ => None,
+        } {
+            self.next();
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.term()?));
+        }
+        Ok(lhs)
+    }
+
+    fn term(&mut self) -> Result<Expr, NodeError> {
+        let mut lhs = self.unary()?;
+        while let Some(op) = match self.peek() {
+            Some(Token::Star) => Some(BinaryOp::Multiply),
+            Some(Token::Slash) => Some(BinaryOp::Divide),
+            _ => None,
+        } {
+            self.next();
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, NodeError> {
+        match self.peek() {
+            Some(Token::Minus) => {
+                self.next();
+                Ok(Expr::Negate(Box::new(self.unary()?)))
+            }
+            Some(Token::Plus) => {
+                self.next();
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Result<Expr, NodeError> {
+        let base = self.atom()?;
+        if self.peek() == Some(&Token::Caret) {
+            self.next();
+            Ok(Expr::Binary(
+                BinaryOp::Power,
+                Box::new(base),
+                Box::new(self.unary()?),
+            ))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expr, NodeError> {
+        match self.next() {
+            Some(Token::Number(v)) => Ok(Expr::Number(v)),
+            Some(Token::Imaginary(v)) => Ok(Expr::Imaginary(v)),
+            Some(Token::Identifier(name)) => {
+                if self.peek() == Some(&Token::LeftParen) {
+                    let f = Function::from_name(&name)
+                        .ok_or_else(|| NodeError::Config(format!("Unknown function '{name}'")))?;
+                    self.next();
+                    let arg = self.expr()?;
+                    self.expect(Token::RightParen)?;
+                    Ok(Expr::Call(f, Box::new(arg)))
+                } else if name == "pi" {
+                    Ok(Expr::Number(std::f64::consts::PI))
+                } else {
+                    Ok(Expr::Variable(name))
+                }
+            }
+            Some(Token::LeftParen) => {
+                let expr = self.expr()?;
+                self.expect(Token::RightParen)?;
+                Ok(expr)
+            }
+            Some(token) => Err(NodeError::Config(format!(
+                "Unexpected {token:?} in formula"
+            ))),
+            None => Err(NodeError::Config("Formula is incomplete".to_string())),
+        }
+    }
+}
+
+// Evaluation
+
+/// Intermediate values, scalars are represented as 0 dimensional arrays
+#[derive(Debug, Clone)]
+pub(super) enum Value {
+    Real(ArrayD<f64>),
+    Complex(ArrayD<Complex64>),
+}
+
+impl Value {
+    pub(super) fn into_complex(self) -> ArrayD<Complex64> {
+        match self {
+            Value::Real(a) => a.mapv(Complex64::from),
+            Value::Complex(a) => a,
+        }
+    }
+}
+
+impl TryFrom<&PortData> for Value {
+    type Error = NodeError;
+
+    fn try_from(data: &PortData) -> Result<Self, Self::Error> {
+        let scalar = |v| ArrayD::from_elem(IxDyn(&[]), v);
+        Ok(match data {
+            PortData::Integer(v) => Value::Real(scalar(*v as f64)),
+            PortData::Real(v) => Value::Real(scalar(*v)),
+            PortData::Complex(v) => Value::Complex(ArrayD::from_elem(IxDyn(&[]), *v)),
+            PortData::ArrayInteger(a) => Value::Real(a.mapv(|v| v as f64)),
+            PortData::ArrayReal(a) | PortData::Dynamic(a) => Value::Real(a.clone()),
+            PortData::ArrayComplex(a) => Value::Complex(a.clone()),
+            PortData::Object(_) => {
+                return Err(NodeError::Input(
+                    "Object inputs can't be used in numeric operations".to_string(),
+                ))
+            }
+        })
+    }
+}
+
+impl From<Value> for PortData {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Real(a) if a.ndim() == 0 => PortData::Real(a.into_iter().next().unwrap_or(0.)),
+            Value::Complex(a) if a.ndim() == 0 => {
+                PortData::Complex(a.into_iter().next().unwrap_or_default())
+            }
+            Value::Real(a) => PortData::ArrayReal(a),
+            Value::Complex(a) => PortData::ArrayComplex(a),
+        }
+    }
+}
+
+/// Combine two arrays element-wise, broadcasting them using numpy's rules
+fn broadcast_zip<T: Copy>(
+    a: &ArrayD<T>,
+    b: &ArrayD<T>,
+    f: impl Fn(T, T) -> T,
+) -> Result<ArrayD<T>, NodeError> {
+    let shape = broadcast_shape(a.shape(), b.shape()).ok_or_else(|| {
+        NodeError::Input(format!(
+            "Shapes {:?} and {:?} can't be broadcast together",
+            a.shape(),
+            b.shape()
+        ))
+    })?;
+    let a = a.broadcast(shape.clone()).expect("shape is broadcastable");
+    let b = b.broadcast(shape).expect("shape is broadcastable");
+    Ok(Zip::from(a).and(b).map_collect(|a, b| f(*a, *b)))
+}
+
+/// The shape two arrays broadcast to under numpy's rules, if they are compatible
+pub(super) fn broadcast_shape(a: &[usize], b: &[usize]) -> Option<IxDyn> {
+    let ndim = a.len().max(b.len());
+    let dim = |shape: &[usize], i: usize| {
+        (i + shape.len())
+            .checked_sub(ndim)
+            .map(|i| shape[i])
+            .unwrap_or(1)
+    };
+    (0..ndim)
+        .map(|i| match (dim(a, i), dim(b, i)) {
+            (a, b) if a == b || b == 1 => Some(a),
+            (1, b) => Some(b),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(|shape| IxDyn(&shape))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ndarray::array;
+
+    fn eval(formula: &str, variables: &[(&str, Value)]) -> PortData {
+        let variables = variables
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect();
+        parse(formula).unwrap().evaluate(&variables).unwrap().into()
+    }
+
+    #[test]
+    fn precedence() {
+        let PortData::Real(v) = eval("1 + 2 * 3 ^ 2 - -4 / 2", &[]) else {
+            panic!("expected real scalar")
+        };
+        assert_eq!(v, 21.);
+        let PortData::Real(v) = eval("-2 ** 2", &[]) else {
+            panic!("expected real scalar")
+        };
+        assert_eq!(v, -4.);
+    }
+
+    #[test]
+    fn inferred_inputs() {
+        let config = ExpressionConfig::new("a * exp(1j * b) + 2 * pi");
+        assert_eq!(config.variables(), ["a".into(), "b".into()].into());
+        assert_eq!(config.output_type(), PortType::ArrayComplex);
+        assert_eq!(
+            ExpressionConfig::new("abs(a) + 1").output_type(),
+            PortType::ArrayReal
+        );
+        // Complex inputs make `a * b` complex
+        assert_eq!(
+            ExpressionConfig::new("a * b").output_type(),
+            PortType::Dynamic
+        );
+
+        // Ports are kept while the formula is being typed
+        let typing = config.with_formula("a * ".to_string());
+        assert_eq!(typing.variables(), config.variables());
+        assert_eq!(typing.output_type(), PortType::ArrayComplex);
+        let typed = typing.with_formula("a * c".to_string());
+        assert_eq!(typed.variables(), ["a".into(), "c".into()].into());
+
+        assert!(parse("a * (b + ").is_err());
+        assert!(parse("foo(a)").is_err());
+        assert!(parse("a $ b").is_err());
+    }
+
+    #[test]
+    fn complex_broadcast() {
+        let a = Value::Real(array![[1.], [2.]].into_dyn());
+        let b = Value::Real(array![0., std::f64::consts::PI].into_dyn());
+        let PortData::ArrayComplex(out) = eval("a * exp(1j * b)", &[("a", a), ("b", b)]) else {
+            panic!("expected complex array")
+        };
+        assert_eq!(out.shape(), &[2, 2]);
+        assert!((out[[1, 1]] - Complex64::new(-2., 0.)).norm() < 1e-12);
+    }
+}
@@ -0,0 +1,693 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, SwashCache};
+use derive_more::Display;
+use iced::alignment::{Horizontal, Vertical};
+use iced::widget::canvas::{self, path::Builder, Stroke};
+use iced::widget::image::{FilterMethod, Handle};
+use iced::{Color, Point, Rectangle, Size, Theme, Vector};
+use tiny_skia::{FillRule, Mask, Paint, PathBuilder, Pixmap, PixmapPaint, Transform};
+
+use super::status::NodeError;
+
+/// Exported figures are laid out at this width, and scaled to the chosen resolution,
+/// so that text and lines keep their proportions at any resolution
+const LAYOUT_WIDTH: f32 = 600.;
+/// Text height relative to its size, matching the default line height of iced
+const LINE_HEIGHT: f32 = 1.3;
+
+/// Resolution of exported PNG files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[display("{width}x{height}")]
+pub struct ExportSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ExportSize {
+    pub const PRESETS: [ExportSize; 4] = [
+        ExportSize::new(800, 600),
+        ExportSize::new(1200, 900),
+        ExportSize::new(1600, 1200),
+        ExportSize::new(3200, 2400),
+    ];
+
+    pub const fn new(width: u32, height: u32) -> Self {
+        ExportSize { width, height }
+    }
+
+    /// Parse a size written as `<width>x<height>`
+    pub fn parse(size: &str) -> Option<Self> {
+        let (width, height) = size.split_once('x')?;
+        let size = ExportSize::new(width.trim().parse().ok()?, height.trim().parse().ok()?);
+        (size.width > 0 && size.height > 0).then_some(size)
+    }
+
+    /// Pixels per unit of the figure's layout
+    pub fn scale(&self) -> f32 {
+        self.width as f32 / LAYOUT_WIDTH
+    }
+
+    /// Size that figures are laid out at
+    pub fn layout_size(&self) -> Size {
+        Size::new(LAYOUT_WIDTH, self.height as f32 / self.scale())
+    }
+}
+
+impl Default for ExportSize {
+    fn default() -> Self {
+        ExportSize::PRESETS[1]
+    }
+}
+
+/// Colors of the parts of a figure that aren't data
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FigureStyle {
+    pub background: Option<Color>,
+    pub text: Color,
+    pub grid: Color,
+}
+
+impl FigureStyle {
+    pub fn from_theme(theme: &Theme) -> Self {
+        let palette = theme.extended_palette();
+        FigureStyle {
+            background: None,
+            text: palette.background.base.text,
+            grid: palette.secondary.strong.color.scale_alpha(0.5),
+        }
+    }
+
+    /// Dark on white, for exported figures
+    pub fn print() -> Self {
+        FigureStyle {
+            background: Some(Color::WHITE),
+            text: Color::BLACK,
+            grid: Color::from_rgba(0.4, 0.4, 0.4, 0.5),
+        }
+    }
+}
+
+/// Something drawn in a figure, in pixels from the top left of the figure
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mark {
+    /// Connected line segments
+    Line {
+        points: Vec<Point>,
+        color: Color,
+        width: f32,
+    },
+    /// Outline of a rectangle
+    Rectangle {
+        bounds: Rectangle,
+        color: Color,
+        width: f32,
+    },
+    Dot {
+        center: Point,
+        radius: f32,
+        color: Color,
+    },
+    Text {
+        content: String,
+        position: Point,
+        size: f32,
+        color: Color,
+        horizontal: Horizontal,
+        vertical: Vertical,
+    },
+    /// RGBA pixels of an image, stretched over `bounds`
+    Image {
+        bounds: Rectangle,
+        columns: u32,
+        rows: u32,
+        pixels: Vec<u8>,
+    },
+    /// Marks that are only drawn inside `bounds`
+    Clip { bounds: Rectangle, marks: Vec<Mark> },
+}
+
+/// What a viewer node draws, independent of where it is drawn.
+/// The canvas of the node, and exported SVG and PNG files are all drawn from the same figure
+#[derive(Debug, Clone, PartialEq)]
+pub struct Figure {
+    pub size: Size,
+    pub background: Option<Color>,
+    pub marks: Vec<Mark>,
+}
+
+impl Figure {
+    pub fn new(size: Size, style: FigureStyle) -> Self {
+        Figure {
+            size,
+            background: style.background,
+            marks: vec![],
+        }
+    }
+
+    pub fn push(&mut self, mark: Mark) {
+        self.marks.push(mark);
+    }
+
+    //// Canvas
+
+    pub fn draw(&self, frame: &mut canvas::Frame) {
+        if let Some(background) = self.background {
+            frame.fill_rectangle(Point::ORIGIN, self.size, background);
+        }
+        draw_marks(frame, &self.marks, Vector::ZERO);
+    }
+
+    //// SVG
+
+    /// The figure as an SVG document, which can't contain images
+    pub fn svg(&self) -> Result<String, NodeError> {
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+            self.size.width, self.size.height
+        );
+        svg.push('\n');
+        if let Some(background) = self.background {
+            let _ = writeln!(
+                svg,
+                r#"<rect width="100%" height="100%" {}/>"#,
+                svg_paint("fill", background)
+            );
+        }
+        let mut clip_count = 0;
+        write_svg_marks(&mut svg, &self.marks, &mut clip_count)?;
+        svg.push_str("</svg>\n");
+        Ok(svg)
+    }
+
+    //// PNG
+
+    /// The figure as a PNG image, with `scale` pixels per unit of the figure
+    pub fn png(&self, scale: f32) -> Result<Vec<u8>, NodeError> {
+        let error = |e: String| NodeError::Output(format!("Can't render PNG: {e}"));
+        let width = (self.size.width * scale).round() as u32;
+        let height = (self.size.height * scale).round() as u32;
+        let mut pixmap = Pixmap::new(width, height)
+            .ok_or_else(|| error(format!("invalid size {width}x{height}")))?;
+        if let Some(background) = self.background {
+            pixmap.fill(skia_color(background));
+        }
+        let mut text = TextRenderer::new();
+        let transform = Transform::from_scale(scale, scale);
+        raster_marks(&mut pixmap, &mut text, &self.marks, transform, None);
+        pixmap.encode_png().map_err(|e| error(e.to_string()))
+    }
+
+    /// Write the figure to `path`, as SVG or PNG depending on its extension
+    pub fn export(&self, path: &Path, size: ExportSize) -> Result<(), NodeError> {
+        let contents = match path.extension().and_then(|e| e.to_str()) {
+            Some("svg") => self.svg()?.into_bytes(),
+            Some("png") => self.png(size.scale())?,
+            _ => {
+                return Err(NodeError::FileSys(format!(
+                    "Can't export to {path:?}, use a .png or .svg file"
+                )))
+            }
+        };
+        std::fs::write(path, contents)
+            .map_err(|e| NodeError::FileSys(format!("Can't write {path:?}: {e}")))
+    }
+}
+
+fn draw_marks(frame: &mut canvas::Frame, marks: &[Mark], offset: Vector) {
+    for mark in marks {
+        match mark {
+            Mark::Line {
+                points,
+                color,
+                width,
+            } => {
+                let mut builder = Builder::new();
+                for (i, point) in points.iter().enumerate() {
+                    match i {
+                        0 => builder.move_to(*point + offset),
+                        _ => builder.line_to(*point + offset),
+                    }
+                }
+                frame.stroke(
+                    &builder.build(),
+                    Stroke::default().with_color(*color).with_width(*width),
+                );
+            }
+            Mark::Rectangle {
+                bounds,
+                color,
+                width,
+            } => frame.stroke_rectangle(
+                bounds.position() + offset,
+                bounds.size(),
+                Stroke::default().with_color(*color).with_width(*width),
+            ),
+            Mark::Dot {
+                center,
+                radius,
+                color,
+            } => frame.fill(&canvas::Path::circle(*center + offset, *radius), *color),
+            Mark::Text {
+                content,
+                position,
+                size,
+                color,
+                horizontal,
+                vertical,
+            } => frame.fill_text(canvas::Text {
+                content: content.clone(),
+                position: *position + offset,
+                color: *color,
+                size: (*size).into(),
+                horizontal_alignment: *horizontal,
+                vertical_alignment: *vertical,
+                ..Default::default()
+            }),
+            Mark::Image {
+                bounds,
+                columns,
+                rows,
+                pixels,
+            } => frame.draw_image(
+                *bounds + offset,
+                canvas::Image::new(Handle::from_rgba(*columns, *rows, pixels.clone()))
+                    .filter_method(FilterMethod::Nearest),
+            ),
+            // The clipped frame's origin is at the corner of the clipped area
+            Mark::Clip { bounds, marks } => frame.with_clip(*bounds + offset, |frame| {
+                draw_marks(frame, marks, Vector::new(-bounds.x, -bounds.y))
+            }),
+        }
+    }
+}
+
+fn svg_paint(attribute: &str, color: Color) -> String {
+    let [r, g, b, a] = color.into_rgba8();
+    format!(
+        r#"{attribute}="rgb({r},{g},{b})" {attribute}-opacity="{:.3}""#,
+        a as f32 / 255.
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn write_svg_marks(
+    svg: &mut String,
+    marks: &[Mark],
+    clip_count: &mut usize,
+) -> Result<(), NodeError> {
+    for mark in marks {
+        // Writing to a String can't fail
+        let _ = match mark {
+            Mark::Line {
+                points,
+                color,
+                width,
+            } => writeln!(
+                svg,
+                r#"<polyline points="{}" fill="none" {} stroke-width="{width}"/>"#,
+                points
+                    .iter()
+                    .map(|p| format!("{:.2},{:.2}", p.x, p.y))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                svg_paint("stroke", *color)
+            ),
+            Mark::Rectangle {
+                bounds,
+                color,
+                width,
+            } => writeln!(
+                svg,
+                r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="none" {} stroke-width="{width}"/>"#,
+                bounds.x,
+                bounds.y,
+                bounds.width,
+                bounds.height,
+                svg_paint("stroke", *color)
+            ),
+            Mark::Dot {
+                center,
+                radius,
+                color,
+            } => writeln!(
+                svg,
+                r#"<circle cx="{:.2}" cy="{:.2}" r="{radius}" {}/>"#,
+                center.x,
+                center.y,
+                svg_paint("fill", *color)
+            ),
+            Mark::Text {
+                content,
+                position,
+                size,
+                color,
+                horizontal,
+                vertical,
+            } => {
+                let anchor = match horizontal {
+                    Horizontal::Left => "start",
+                    Horizontal::Center => "middle",
+                    Horizontal::Right => "end",
+                };
+                let baseline = match vertical {
+                    Vertical::Top => "hanging",
+                    Vertical::Center => "middle",
+                    Vertical::Bottom => "text-after-edge",
+                };
+                writeln!(
+                    svg,
+                    r#"<text x="{:.2}" y="{:.2}" font-family="monospace" font-size="{size}" text-anchor="{anchor}" dominant-baseline="{baseline}" {}>{}</text>"#,
+                    position.x,
+                    position.y,
+                    svg_paint("fill", *color),
+                    escape_xml(content)
+                )
+            }
+            Mark::Image { .. } => {
+                return Err(NodeError::Output(
+                    "Images can only be exported as PNG".to_string(),
+                ))
+            }
+            Mark::Clip { bounds, marks } => {
+                *clip_count += 1;
+                let _ = writeln!(
+                    svg,
+                    r#"<clipPath id="clip{clip_count}"><rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}"/></clipPath>"#,
+                    bounds.x, bounds.y, bounds.width, bounds.height
+                );
+                let _ = writeln!(svg, r#"<g clip-path="url(#clip{clip_count})">"#);
+                write_svg_marks(svg, marks, clip_count)?;
+                writeln!(svg, "</g>")
+            }
+        };
+    }
+    Ok(())
+}
+
+fn skia_color(color: Color) -> tiny_skia::Color {
+    tiny_skia::Color::from_rgba(color.r, color.g, color.b, color.a)
+        .unwrap_or(tiny_skia::Color::BLACK)
+}
+
+fn skia_paint(color: Color) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color(skia_color(color));
+    paint.anti_alias = true;
+    paint
+}
+
+fn raster_marks(
+    pixmap: &mut Pixmap,
+    text: &mut TextRenderer,
+    marks: &[Mark],
+    transform: Transform,
+    mask: Option<&Mask>,
+) {
+    let stroke = |width: f32| tiny_skia::Stroke {
+        width,
+        ..Default::default()
+    };
+    for mark in marks {
+        match mark {
+            Mark::Line {
+                points,
+                color,
+                width,
+            } => {
+                let mut builder = PathBuilder::new();
+                for (i, point) in points.iter().enumerate() {
+                    match i {
+                        0 => builder.move_to(point.x, point.y),
+                        _ => builder.line_to(point.x, point.y),
+                    }
+                }
+                if let Some(path) = builder.finish() {
+                    pixmap.stroke_path(
+                        &path,
+                        &skia_paint(*color),
+                        &stroke(*width),
+                        transform,
+                        mask,
+                    );
+                }
+            }
+            Mark::Rectangle {
+                bounds,
+                color,
+                width,
+            } => {
+                if let Some(rect) =
+                    tiny_skia::Rect::from_xywh(bounds.x, bounds.y, bounds.width, bounds.height)
+                {
+                    let path = PathBuilder::from_rect(rect);
+                    pixmap.stroke_path(
+                        &path,
+                        &skia_paint(*color),
+                        &stroke(*width),
+                        transform,
+                        mask,
+                    );
+                }
+            }
+            Mark::Dot {
+                center,
+                radius,
+                color,
+            } => {
+                if let Some(path) = PathBuilder::from_circle(center.x, center.y, *radius) {
+                    let paint = skia_paint(*color);
+                    pixmap.fill_path(&path, &paint, FillRule::Winding, transform, mask);
+                }
+            }
+            Mark::Text {
+                content,
+                position,
+                size,
+                color,
+                horizontal,
+                vertical,
+            } => text.draw(
+                pixmap,
+                content,
+                *position,
+                *size,
+                *color,
+                (*horizontal, *vertical),
+                transform,
+                mask,
+            ),
+            Mark::Image {
+                bounds,
+                columns,
+                rows,
+                pixels,
+            } => {
+                let size = tiny_skia::IntSize::from_wh(*columns, *rows);
+                // Pixels are premultiplied by their alpha in a pixmap
+                let premultiplied = pixels
+                    .chunks_exact(4)
+                    .flat_map(|p| {
+                        let alpha = |c: u8| (c as u16 * p[3] as u16 / 255) as u8;
+                        [alpha(p[0]), alpha(p[1]), alpha(p[2]), p[3]]
+                    })
+                    .collect();
+                if let Some(image) = size.and_then(|size| Pixmap::from_vec(premultiplied, size)) {
+                    let paint = PixmapPaint {
+                        quality: tiny_skia::FilterQuality::Nearest,
+                        ..Default::default()
+                    };
+                    let stretch = Transform::from_row(
+                        bounds.width / *columns as f32,
+                        0.,
+                        0.,
+                        bounds.height / *rows as f32,
+                        bounds.x,
+                        bounds.y,
+                    );
+                    pixmap.draw_pixmap(
+                        0,
+                        0,
+                        image.as_ref(),
+                        &paint,
+                        transform.pre_concat(stretch),
+                        mask,
+                    );
+                }
+            }
+            Mark::Clip { bounds, marks } => {
+                let clip =
+                    tiny_skia::Rect::from_xywh(bounds.x, bounds.y, bounds.width, bounds.height)
+                        .zip(Mask::new(pixmap.width(), pixmap.height()));
+                if let Some((rect, mut clip_mask)) = clip {
+                    let path = PathBuilder::from_rect(rect);
+                    clip_mask.fill_path(&path, FillRule::Winding, false, transform);
+                    raster_marks(pixmap, text, marks, transform, Some(&clip_mask));
+                }
+            }
+        }
+    }
+}
+
+/// Lays out and rasterizes text with the application's font, without a window
+struct TextRenderer {
+    fonts: FontSystem,
+    cache: SwashCache,
+    family: String,
+}
+
+impl TextRenderer {
+    fn new() -> Self {
+        let mut db = fontdb::Database::new();
+        db.load_font_data(include_bytes!("../../data/CaskaydiaCove.ttf").to_vec());
+        let family = db
+            .faces()
+            .next()
+            .and_then(|face| face.families.first())
+            .map(|(name, _)| name.clone())
+            .unwrap_or_default();
+        TextRenderer {
+            fonts: FontSystem::new_with_locale_and_db("en-US".to_string(), db),
+            cache: SwashCache::new(),
+            family,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn draw(
+        &mut self,
+        pixmap: &mut Pixmap,
+        content: &str,
+        position: Point,
+        size: f32,
+        color: Color,
+        (horizontal, vertical): (Horizontal, Vertical),
+        transform: Transform,
+        mask: Option<&Mask>,
+    ) {
+        // Text is laid out in pixels, so that glyphs are rasterized at the final resolution
+        let scale = transform.sx;
+        let metrics = Metrics::new(size * scale, size * LINE_HEIGHT * scale);
+        let mut buffer = Buffer::new(&mut self.fonts, metrics);
+        buffer.set_text(
+            &mut self.fonts,
+            content,
+            Attrs::new().family(Family::Name(&self.family)),
+            Shaping::Advanced,
+        );
+        buffer.shape_until_scroll(&mut self.fonts, false);
+        let width = buffer
+            .layout_runs()
+            .map(|run| run.line_w)
+            .fold(0., f32::max);
+        let height = metrics.line_height;
+
+        let origin = Point::new(
+            position.x * scale + transform.tx,
+            position.y * scale + transform.ty,
+        );
+        let x = origin.x
+            - match horizontal {
+                Horizontal::Left => 0.,
+                Horizontal::Center => width / 2.,
+                Horizontal::Right => width,
+            };
+        let y = origin.y
+            - match vertical {
+                Vertical::Top => 0.,
+                Vertical::Center => height / 2.,
+                Vertical::Bottom => height,
+            };
+
+        let [r, g, b, a] = color.into_rgba8();
+        let text_color = cosmic_text::Color::rgba(r, g, b, a);
+        buffer.draw(
+            &mut self.fonts,
+            &mut self.cache,
+            text_color,
+            |glyph_x, glyph_y, w, h, color| {
+                let rect = tiny_skia::Rect::from_xywh(
+                    x + glyph_x as f32,
+                    y + glyph_y as f32,
+                    w as f32,
+                    h as f32,
+                );
+                if let Some(rect) = rect {
+                    let mut paint = Paint::default();
+                    paint.set_color_rgba8(color.r(), color.g(), color.b(), color.a());
+                    pixmap.fill_rect(rect, &paint, Transform::identity(), mask);
+                }
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn figure() -> Figure {
+        let mut figure = Figure::new(Size::new(100., 50.), FigureStyle::print());
+        figure.push(Mark::Clip {
+            bounds: Rectangle::new(Point::new(10., 0.), Size::new(90., 40.)),
+            marks: vec![Mark::Line {
+                points: vec![Point::new(10., 40.), Point::new(100., 0.)],
+                color: Color::from_rgb(1., 0., 0.),
+                width: 1.5,
+            }],
+        });
+        figure.push(Mark::Text {
+            content: "x < 1".to_string(),
+            position: Point::new(50., 50.),
+            size: 10.,
+            color: Color::BLACK,
+            horizontal: Horizontal::Center,
+            vertical: Vertical::Bottom,
+        });
+        figure
+    }
+
+    #[test]
+    fn svg_from_marks() {
+        let svg = figure().svg().unwrap();
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="100""#));
+        assert!(svg.contains(r#"<polyline points="10.00,40.00 100.00,0.00""#));
+        assert!(svg.contains(r#"clip-path="url(#clip1)""#));
+        assert!(svg.contains(r#"text-anchor="middle""#));
+        assert!(svg.contains(">x &lt; 1</text>"));
+
+        let mut image = figure();
+        image.push(Mark::Image {
+            bounds: Rectangle::new(Point::ORIGIN, image.size),
+            columns: 1,
+            rows: 1,
+            pixels: vec![0, 0, 0, 255],
+        });
+        assert!(image.svg().is_err());
+    }
+
+    #[test]
+    fn png_at_resolution() {
+        let size = ExportSize::parse("400x200").unwrap();
+        assert_eq!(size.layout_size(), Size::new(600., 300.));
+        assert_eq!(ExportSize::parse("400"), None);
+
+        let png = figure().png(2.).unwrap();
+        let pixmap = Pixmap::decode_png(&png).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (200, 100));
+        // The line is drawn inside the clip area, and the background outside of it
+        let pixel = |x, y| pixmap.pixel(x, y).unwrap();
+        assert_eq!(
+            pixel(5, 5),
+            tiny_skia::Color::WHITE.premultiply().to_color_u8()
+        );
+        assert!(pixel(110, 40).red() > pixel(110, 40).green());
+    }
+}
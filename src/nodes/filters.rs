@@ -0,0 +1,616 @@
+use std::f64::consts::{PI, TAU};
+
+use crate::app::Message;
+use crate::gui_node::PortDataReference;
+use crate::interface::numeric_input::styled_text_input;
+use crate::nodes::expression::Value;
+use crate::nodes::{NodeError, NodeTemplate, RustNode};
+use crate::StableMap;
+use iced::{
+    widget::{horizontal_space, row, text, text_input},
+    Alignment::Center,
+    Element,
+    Length::Fill,
+};
+use ndarray::{Array1, ArrayD, Axis, LinalgScalar, Slice, Zip};
+use numpy::{Complex64, IxDyn};
+use serde::{Deserialize, Serialize};
+
+use super::port::PortData;
+
+/// Convolve `a` with `kernel`, which must have the same number of dimensions.
+/// The output has the shape of `a` (numpy's "same" mode) and the edges are zero padded
+pub fn convolve<T: LinalgScalar>(a: &ArrayD<T>, kernel: &ArrayD<T>) -> ArrayD<T> {
+    let mut out = ArrayD::zeros(a.raw_dim());
+    for (k_index, &k) in kernel.indexed_iter() {
+        // output index i reads from input index i + shift
+        let shift = |axis: usize| (kernel.shape()[axis] as isize - 1) / 2 - k_index[axis] as isize;
+        let range = |axis: usize, len: usize| {
+            let lo = (-shift(axis)).clamp(0, len as isize);
+            let hi = (len as isize - shift(axis)).clamp(lo, len as isize);
+            (lo, hi)
+        };
+
+        let source = a.slice_each_axis(|ax| {
+            let (lo, hi) = range(ax.axis.index(), ax.len);
+            let s = shift(ax.axis.index());
+            Slice::from(lo + s..hi + s)
+        });
+        let mut target = out.slice_each_axis_mut(|ax| {
+            let (lo, hi) = range(ax.axis.index(), ax.len);
+            Slice::from(lo..hi)
+        });
+        Zip::from(&mut target)
+            .and(&source)
+            .for_each(|o, &v| *o = *o + v * k);
+    }
+    out
+}
+
+/// Convolve every lane of `a` along `axis` with a 1-D kernel
+pub fn convolve_axis<T: LinalgScalar>(a: &ArrayD<T>, kernel: &Array1<T>, axis: usize) -> ArrayD<T> {
+    let mut shape = vec![1; a.ndim()];
+    shape[axis] = kernel.len();
+    let kernel = kernel
+        .clone()
+        .into_shape_with_order(IxDyn(&shape))
+        .expect("kernel length is unchanged");
+    convolve(a, &kernel)
+}
+
+/// Apply a real valued filter to a real or complex signal
+fn filter_value(
+    a: Value,
+    kernel: &Array1<f64>,
+    f: impl Fn(&ArrayD<f64>, &Array1<f64>) -> ArrayD<f64>,
+    g: impl Fn(&ArrayD<Complex64>, &Array1<Complex64>) -> ArrayD<Complex64>,
+) -> PortData {
+    match a {
+        Value::Real(a) => PortData::ArrayReal(f(&a, kernel)),
+        Value::Complex(a) => PortData::ArrayComplex(g(&a, &kernel.mapv(Complex64::from))),
+    }
+}
+
+fn input_value(
+    inputs: &StableMap<String, PortDataReference>,
+    name: &str,
+) -> Result<Value, NodeError> {
+    Value::try_from(&**inputs.get(name).ok_or(NodeError::input_error(name))?)
+}
+
+/// N-D convolution of `a` with a kernel of the same dimensionality
+pub fn convolve_nd(
+    inputs: StableMap<String, PortDataReference>,
+) -> Result<StableMap<String, PortData>, NodeError> {
+    let (a, kernel) = (input_value(&inputs, "a")?, input_value(&inputs, "kernel")?);
+    let (a_ndim, kernel_ndim) = (ndim(&a), ndim(&kernel));
+    if a_ndim != kernel_ndim {
+        return Err(NodeError::Input(format!(
+            "A {kernel_ndim}-D kernel can't be convolved with a {a_ndim}-D signal"
+        )));
+    }
+    // Promote to complex if either input is complex
+    let out = match (a, kernel) {
+        (Value::Real(a), Value::Real(kernel)) => PortData::ArrayReal(convolve(&a, &kernel)),
+        (a, kernel) => PortData::ArrayComplex(convolve(&a.into_complex(), &kernel.into_complex())),
+    };
+    Ok([("out".into(), out)].into())
+}
+
+fn ndim(value: &Value) -> usize {
+    match value {
+        Value::Real(a) => a.ndim(),
+        Value::Complex(a) => a.ndim(),
+    }
+}
+
+/// Axis that a 1-D operation is applied along, the last axis when not specified
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, PartialOrd)]
+pub struct ConvolveConfig {
+    pub axis: Option<usize>,
+}
+
+impl ConvolveConfig {
+    /// Convolve `a` along the configured axis with a 1-D kernel
+    pub fn compute(
+        &self,
+        inputs: StableMap<String, PortDataReference>,
+    ) -> Result<StableMap<String, PortData>, NodeError> {
+        let (a, kernel) = (input_value(&inputs, "a")?, input_value(&inputs, "kernel")?);
+        if ndim(&kernel) != 1 {
+            return Err(NodeError::Input(
+                "The kernel must be one dimensional".into(),
+            ));
+        }
+        let axis = self.checked_axis(ndim(&a))?;
+        let out = match (a, kernel) {
+            (Value::Real(a), Value::Real(kernel)) => {
+                PortData::ArrayReal(convolve_axis(&a, &flat(&kernel), axis))
+            }
+            (a, kernel) => PortData::ArrayComplex(convolve_axis(
+                &a.into_complex(),
+                &flat(&kernel.into_complex()),
+                axis,
+            )),
+        };
+        Ok([("out".into(), out)].into())
+    }
+
+    fn checked_axis(&self, ndim: usize) -> Result<usize, NodeError> {
+        let axis = self.axis.unwrap_or(ndim.saturating_sub(1));
+        if axis >= ndim {
+            return Err(NodeError::Config(format!(
+                "axis {axis} is out of range for a {ndim}-D input"
+            )));
+        }
+        Ok(axis)
+    }
+
+    pub fn config_view<'a>(&self, id: u32) -> Element<'a, Message> {
+        row![
+            text("axis:"),
+            horizontal_space(),
+            styled_text_input(
+                text_input(
+                    "last",
+                    &self.axis.map(|a| a.to_string()).unwrap_or_default()
+                )
+                .on_input(move |value| {
+                    Message::UpdateNodeTemplate(
+                        id,
+                        NodeTemplate::RustNode(RustNode::Convolve1D(ConvolveConfig {
+                            axis: value.parse().ok(),
+                        })),
+                    )
+                })
+            ),
+        ]
+        .align_y(Center)
+        .spacing(4.)
+        .into()
+    }
+}
+
+fn flat<T: Clone>(a: &ArrayD<T>) -> Array1<T> {
+    a.iter().cloned().collect()
+}
+
+/// Separable Gaussian smoothing along every axis, with the kernel truncated at 4σ
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, PartialOrd)]
+pub struct GaussianConfig {
+    pub sigma: f64,
+}
+
+impl Default for GaussianConfig {
+    fn default() -> Self {
+        Self { sigma: 2. }
+    }
+}
+
+impl GaussianConfig {
+    pub fn compute(
+        &self,
+        inputs: StableMap<String, PortDataReference>,
+    ) -> Result<StableMap<String, PortData>, NodeError> {
+        if self.sigma.is_nan() || self.sigma <= 0. {
+            return Err(NodeError::Config("σ must be positive".into()));
+        }
+        // Taps further than the length of an axis never reach any data
+        let kernel = |len: usize| gaussian_kernel(self.sigma, len.saturating_sub(1));
+        let out = match input_value(&inputs, "a")? {
+            Value::Real(a) => PortData::ArrayReal(smooth(&a, kernel)),
+            Value::Complex(a) => {
+                PortData::ArrayComplex(smooth(&a, |len| kernel(len).mapv(Complex64::from)))
+            }
+        };
+        Ok([("out".into(), out)].into())
+    }
+
+    pub fn view(&self, id: u32) -> Element<'_, Message> {
+        row![
+            text("σ"),
+            styled_text_input(
+                text_input("2", &self.sigma.to_string()).on_input(move |value| {
+                    Message::UpdateNodeTemplate(
+                        id,
+                        NodeTemplate::RustNode(RustNode::GaussianSmooth(GaussianConfig {
+                            sigma: value.parse().unwrap_or(0.),
+                        })),
+                    )
+                })
+            ),
+        ]
+        .width(Fill)
+        .align_y(Center)
+        .padding(5.)
+        .spacing(2.)
+        .into()
+    }
+}
+
+/// Convolve along every axis, with a kernel made for the length of that axis
+fn smooth<T: LinalgScalar>(a: &ArrayD<T>, kernel: impl Fn(usize) -> Array1<T>) -> ArrayD<T> {
+    (0..a.ndim()).fold(a.clone(), |a, axis| {
+        convolve_axis(&a, &kernel(a.len_of(Axis(axis))), axis)
+    })
+}
+
+/// Normalized Gaussian kernel, truncated at 4σ or `max_radius` taps from its center
+fn gaussian_kernel(sigma: f64, max_radius: usize) -> Array1<f64> {
+    let radius = ((4. * sigma).ceil() as usize).min(max_radius) as isize;
+    let kernel = Array1::from_iter(
+        (-radius..=radius).map(|x| (-(x as f64).powi(2) / (2. * sigma * sigma)).exp()),
+    );
+    let sum = kernel.sum();
+    kernel / sum
+}
+
+/// Symmetric window functions, e.g. for tapering signals or designing FIR filters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Window {
+    Hann,
+    Hamming,
+    Kaiser,
+}
+
+impl Window {
+    pub fn wrap(&self, config: WindowConfig) -> RustNode {
+        match self {
+            Window::Hann => RustNode::HannWindow(config),
+            Window::Hamming => RustNode::HammingWindow(config),
+            Window::Kaiser => RustNode::KaiserWindow(config),
+        }
+    }
+
+    /// `beta` is only used by the Kaiser window
+    pub fn samples(&self, num: usize, beta: f64) -> Array1<f64> {
+        // A single sample window passes its sample unchanged, like numpy's
+        if num <= 1 {
+            return Array1::ones(num);
+        }
+        let m = (num - 1) as f64;
+        Array1::from_shape_fn(num, |n| {
+            let n = n as f64;
+            match self {
+                Window::Hann => 0.5 - 0.5 * (TAU * n / m).cos(),
+                Window::Hamming => 0.54 - 0.46 * (TAU * n / m).cos(),
+                Window::Kaiser => {
+                    let r = 2. * n / m - 1.;
+                    bessel_i0(beta * (1. - r * r).max(0.).sqrt()) / bessel_i0(beta)
+                }
+            }
+        })
+    }
+
+    /// Outputs the sample indices as `n`, so the window can be wired into a `Plot` node
+    pub fn compute(&self, config: &WindowConfig) -> StableMap<String, PortData> {
+        [
+            ("n".into(), PortData::ArrayReal(sample_indices(config.num))),
+            (
+                "out".into(),
+                PortData::ArrayReal(self.samples(config.num, config.beta).into_dyn()),
+            ),
+        ]
+        .into()
+    }
+
+    pub fn view<'a>(&self, id: u32, config: WindowConfig) -> Element<'a, Message> {
+        let window = *self;
+        let update = move |config| {
+            Message::UpdateNodeTemplate(id, NodeTemplate::RustNode(window.wrap(config)))
+        };
+        let num = styled_text_input(text_input("64", &config.num.to_string()).on_input(
+            move |value| {
+                update(WindowConfig {
+                    num: value.parse().unwrap_or(0),
+                    ..config
+                })
+            },
+        ));
+        let content = match self {
+            Window::Kaiser => row![
+                text("#"),
+                num,
+                text("β"),
+                styled_text_input(text_input("8.6", &config.beta.to_string()).on_input(
+                    move |value| update(WindowConfig {
+                        beta: value.parse().unwrap_or(0.),
+                        ..config
+                    })
+                ))
+            ],
+            Window::Hann | Window::Hamming => row![text("#"), num],
+        };
+        content
+            .width(Fill)
+            .align_y(Center)
+            .padding(5.)
+            .spacing(2.)
+            .into()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, PartialOrd)]
+pub struct WindowConfig {
+    pub num: usize,
+    pub beta: f64,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self { num: 64, beta: 8.6 }
+    }
+}
+
+/// Zeroth order modified Bessel function of the first kind, by its power series
+fn bessel_i0(x: f64) -> f64 {
+    let (mut sum, mut term, mut k) = (1., 1., 1.);
+    while term > sum * 1e-16 {
+        term *= (x / (2. * k)).powi(2);
+        sum += term;
+        k += 1.;
+    }
+    sum
+}
+
+fn sample_indices(num: usize) -> ArrayD<f64> {
+    Array1::from_shape_fn(num, |n| n as f64).into_dyn()
+}
+
+/// Pass bands of windowed-sinc FIR filters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FirBand {
+    LowPass,
+    HighPass,
+    BandPass,
+}
+
+impl FirBand {
+    pub fn wrap(&self, config: FirConfig) -> RustNode {
+        match self {
+            FirBand::LowPass => RustNode::FirLowPass(config),
+            FirBand::HighPass => RustNode::FirHighPass(config),
+            FirBand::BandPass => RustNode::FirBandPass(config),
+        }
+    }
+
+    /// Hamming windowed-sinc design, with cutoffs given as a fraction of the Nyquist frequency.
+    /// The kernel is scaled to unit gain at the center of the pass band
+    pub fn design(&self, config: &FirConfig) -> Result<Array1<f64>, NodeError> {
+        let FirConfig { taps, f1, f2 } = *config;
+        if taps == 0 {
+            return Err(NodeError::Config("A filter needs at least 1 tap".into()));
+        }
+        let in_range = |f: f64| f > 0. && f < 1.;
+        if !in_range(f1) || (*self == FirBand::BandPass && !(in_range(f2) && f1 < f2)) {
+            return Err(NodeError::Config(
+                "Cutoffs must satisfy 0 < f1 < f2 < 1, relative to Nyquist".into(),
+            ));
+        }
+        if *self == FirBand::HighPass && taps % 2 == 0 {
+            return Err(NodeError::Config(
+                "A high-pass filter needs an odd number of taps".into(),
+            ));
+        }
+
+        let center = (taps - 1) as f64 / 2.;
+        let sinc = |x: f64| match x {
+            0. => 1.,
+            _ => (PI * x).sin() / (PI * x),
+        };
+        let low_pass =
+            |fc: f64| Array1::from_shape_fn(taps, |n| fc * sinc(fc * (n as f64 - center)));
+        let (ideal, scale_frequency) = match self {
+            FirBand::LowPass => (low_pass(f1), 0.),
+            FirBand::HighPass => {
+                let mut h = -low_pass(f1);
+                h[taps / 2] += 1.;
+                (h, 1.)
+            }
+            FirBand::BandPass => (low_pass(f2) - low_pass(f1), (f1 + f2) / 2.),
+        };
+
+        let kernel = ideal * Window::Hamming.samples(taps, 0.);
+        let gain: f64 = kernel
+            .iter()
+            .enumerate()
+            .map(|(n, h)| h * (PI * scale_frequency * (n as f64 - center)).cos())
+            .sum();
+        Ok(kernel / gain)
+    }
+
+    /// Outputs the designed `kernel` along with its sample indices `n`,
+    /// and the filtered signal as `out` when a signal is connected.
+    /// The signal is filtered along its last axis
+    pub fn compute(
+        &self,
+        config: &FirConfig,
+        inputs: StableMap<String, PortDataReference>,
+    ) -> Result<StableMap<String, PortData>, NodeError> {
+        let kernel = self.design(config)?;
+        let mut out: StableMap<String, PortData> = [
+            (
+                "n".into(),
+                PortData::ArrayReal(sample_indices(kernel.len())),
+            ),
+            (
+                "kernel".into(),
+                PortData::ArrayReal(kernel.clone().into_dyn()),
+            ),
+        ]
+        .into();
+
+        if inputs.contains_key("a") {
+            let a = input_value(&inputs, "a")?;
+            let axis = ndim(&a)
+                .checked_sub(1)
+                .ok_or(NodeError::Input("Input 'a' must be an array".into()))?;
+            out.insert(
+                "out".into(),
+                filter_value(
+                    a,
+                    &kernel,
+                    |a, k| convolve_axis(a, k, axis),
+                    |a, k| convolve_axis(a, k, axis),
+                ),
+            );
+        }
+        Ok(out)
+    }
+
+    pub fn view<'a>(&self, id: u32, config: FirConfig) -> Element<'a, Message> {
+        let band = *self;
+        let update = move |config| {
+            Message::UpdateNodeTemplate(id, NodeTemplate::RustNode(band.wrap(config)))
+        };
+        let f1 = styled_text_input(text_input("0.2", &config.f1.to_string()).on_input(
+            move |value| {
+                update(FirConfig {
+                    f1: value.parse().unwrap_or(0.),
+                    ..config
+                })
+            },
+        ));
+        let taps = styled_text_input(text_input("51", &config.taps.to_string()).on_input(
+            move |value| {
+                update(FirConfig {
+                    taps: value.parse().unwrap_or(0),
+                    ..config
+                })
+            },
+        ));
+        let content = match self {
+            FirBand::BandPass => row![
+                f1,
+                text(".."),
+                styled_text_input(text_input("0.5", &config.f2.to_string()).on_input(
+                    move |value| update(FirConfig {
+                        f2: value.parse().unwrap_or(0.),
+                        ..config
+                    })
+                )),
+                text("#"),
+                taps
+            ],
+            FirBand::LowPass | FirBand::HighPass => row![text("fc"), f1, text("#"), taps],
+        };
+        content
+            .width(Fill)
+            .align_y(Center)
+            .padding(5.)
+            .spacing(2.)
+            .into()
+    }
+}
+
+/// Low and high-pass filters use `f1` as their cutoff, band-pass filters pass `f1..f2`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, PartialOrd)]
+pub struct FirConfig {
+    pub taps: usize,
+    pub f1: f64,
+    pub f2: f64,
+}
+
+impl Default for FirConfig {
+    fn default() -> Self {
+        Self {
+            taps: 51,
+            f1: 0.2,
+            f2: 0.5,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ndarray::array;
+    use std::sync::RwLock;
+
+    #[test]
+    fn same_mode() {
+        let a = array![0., 0., 1., 0., 0.].into_dyn();
+        let k = array![1., 2., 3.].into_dyn();
+        assert_eq!(convolve(&a, &k), array![0., 1., 2., 3., 0.].into_dyn());
+        // Matches numpy.convolve([1, 2, 3], [0, 1, 0.5], "same")
+        let a = array![1., 2., 3.].into_dyn();
+        let k = array![0., 1., 0.5].into_dyn();
+        assert_eq!(convolve(&a, &k), array![1., 2.5, 4.].into_dyn());
+    }
+
+    #[test]
+    fn along_axis() {
+        let a = array![[1., 2., 3.], [4., 5., 6.]].into_dyn();
+        let out = convolve_axis(&a, &array![1., 1.], 0);
+        assert_eq!(out, array![[1., 2., 3.], [5., 7., 9.]].into_dyn());
+    }
+
+    #[test]
+    fn windows() {
+        let hann = Window::Hann.samples(5, 0.);
+        assert!((hann - array![0., 0.5, 1., 0.5, 0.])
+            .iter()
+            .all(|v| v.abs() < 1e-12));
+        // A Kaiser window with β = 0 is rectangular
+        assert!(Window::Kaiser
+            .samples(4, 0.)
+            .iter()
+            .all(|v| (v - 1.).abs() < 1e-12));
+        assert!((bessel_i0(1.) - 1.2660658777520084).abs() < 1e-12);
+        for window in [Window::Hann, Window::Hamming, Window::Kaiser] {
+            assert_eq!(window.samples(1, 8.6), array![1.]);
+            assert_eq!(window.samples(0, 8.6).len(), 0);
+        }
+    }
+
+    #[test]
+    fn fir_gain() {
+        let config = FirConfig::default();
+        let low = FirBand::LowPass.design(&config).unwrap();
+        assert!((low.sum() - 1.).abs() < 1e-12);
+        let high = FirBand::HighPass.design(&config).unwrap();
+        assert!(high.sum().abs() < 1e-2);
+        assert!(FirBand::HighPass
+            .design(&FirConfig { taps: 50, ..config })
+            .is_err());
+        assert!(FirBand::BandPass
+            .design(&FirConfig { f2: 0.1, ..config })
+            .is_err());
+        assert_eq!(
+            FirBand::LowPass.design(&FirConfig { taps: 0, ..config }),
+            Err(NodeError::Config("A filter needs at least 1 tap".into()))
+        );
+    }
+
+    #[test]
+    fn fir_design_without_signal() {
+        use crate::graph::Graph;
+        use crate::nodes::{status::NodeStatus, NodeData};
+
+        let node: NodeData =
+            NodeTemplate::RustNode(RustNode::FirLowPass(FirConfig::default())).into();
+        let (_, result) = Graph::compute_node(0, node, [].into());
+        let (outputs, node) = result.unwrap();
+        assert_ne!(node.status, NodeStatus::Waiting);
+        assert!(outputs.contains_key("kernel") && outputs.contains_key("n"));
+        assert!(!outputs.contains_key("out"));
+    }
+
+    #[test]
+    fn gaussian() {
+        let kernel = gaussian_kernel(1.5, usize::MAX);
+        assert_eq!(kernel.len(), 13);
+        assert!((kernel.sum() - 1.).abs() < 1e-12);
+
+        // A huge σ is limited by the length of the input
+        assert_eq!(gaussian_kernel(1e12, 2).len(), 5);
+        let a = RwLock::new(PortData::ArrayReal(ArrayD::ones(IxDyn(&[3, 4]))));
+        let out = GaussianConfig { sigma: 1e12 }
+            .compute([("a".to_string(), a.read().unwrap())].into())
+            .unwrap();
+        let PortData::ArrayReal(out) = &out["out"] else {
+            panic!("expected a real array")
+        };
+        assert_eq!(out.shape(), &[3, 4]);
+    }
+}
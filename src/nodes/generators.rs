@@ -0,0 +1,481 @@
+use std::f64::consts::{PI, TAU};
+
+use crate::app::Message;
+use crate::gui_node::PortDataReference;
+use crate::interface::numeric_input::styled_text_input;
+use crate::math::random::Rng;
+use crate::nodes::{NodeError, NodeTemplate, RustNode};
+use crate::StableMap;
+use iced::{
+    widget::{column, row, text, text_input},
+    Alignment::Center,
+    Element,
+    Length::Fill,
+};
+use ndarray::{Array1, Array2, ArrayD};
+use numpy::IxDyn;
+use serde::{Deserialize, Serialize};
+
+use super::port::PortData;
+
+/// Distributions that can be sampled by a noise node
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Noise {
+    Uniform,
+    Gaussian,
+    ComplexGaussian,
+}
+
+impl Noise {
+    pub fn wrap(&self, config: NoiseConfig) -> RustNode {
+        match self {
+            Noise::Uniform => RustNode::UniformNoise(config),
+            Noise::Gaussian => RustNode::GaussianNoise(config),
+            Noise::ComplexGaussian => RustNode::ComplexGaussianNoise(config),
+        }
+    }
+
+    /// Labels for the two distribution parameters, `a` and `b`
+    fn parameter_labels(&self) -> (&'static str, &'static str) {
+        match self {
+            Noise::Uniform => ("", ".."),
+            Noise::Gaussian | Noise::ComplexGaussian => ("μ", "σ"),
+        }
+    }
+
+    pub fn compute(&self, config: &NoiseConfig) -> Result<StableMap<String, PortData>, NodeError> {
+        let shape = parse_shape(&config.shape)?;
+        let mut rng = Rng::new(config.seed);
+        let (a, b) = (config.a, config.b);
+        let size = shape.iter().product();
+
+        let out = match self {
+            Noise::Uniform => PortData::ArrayReal(from_shape(
+                &shape,
+                (0..size).map(|_| a + (b - a) * rng.uniform()).collect(),
+            )),
+            Noise::Gaussian => PortData::ArrayReal(from_shape(
+                &shape,
+                (0..size).map(|_| a + b * rng.gaussian()).collect(),
+            )),
+            Noise::ComplexGaussian => PortData::ArrayComplex(
+                ArrayD::from_shape_vec(
+                    IxDyn(&shape),
+                    (0..size).map(|_| a + b * rng.complex_gaussian()).collect(),
+                )
+                .expect("length matches shape"),
+            ),
+        };
+        Ok([("out".into(), out)].into())
+    }
+
+    pub fn view<'a>(&self, id: u32, config: &NoiseConfig) -> Element<'a, Message> {
+        let noise = *self;
+        let (a_label, b_label) = self.parameter_labels();
+        let update = move |config: NoiseConfig| {
+            Message::UpdateNodeTemplate(id, NodeTemplate::RustNode(noise.wrap(config)))
+        };
+        let (a_config, b_config, shape_config, seed_config) = (
+            config.clone(),
+            config.clone(),
+            config.clone(),
+            config.clone(),
+        );
+
+        column![
+            row![
+                text(a_label),
+                styled_text_input(
+                    text_input("0", &config.a.to_string()).on_input(move |value| update(
+                        NoiseConfig {
+                            a: value.parse().unwrap_or(0.),
+                            ..a_config.clone()
+                        }
+                    ))
+                ),
+                text(b_label),
+                styled_text_input(
+                    text_input("1", &config.b.to_string()).on_input(move |value| update(
+                        NoiseConfig {
+                            b: value.parse().unwrap_or(0.),
+                            ..b_config.clone()
+                        }
+                    ))
+                ),
+            ]
+            .width(Fill)
+            .align_y(Center)
+            .padding(5.)
+            .spacing(2.),
+            row![
+                styled_text_input(text_input("shape", &config.shape).on_input(move |shape| {
+                    update(NoiseConfig {
+                        shape,
+                        ..shape_config.clone()
+                    })
+                })),
+                text("seed"),
+                styled_text_input(text_input("0", &config.seed.to_string()).on_input(
+                    move |value| update(NoiseConfig {
+                        seed: value.parse().unwrap_or(0),
+                        ..seed_config.clone()
+                    })
+                )),
+            ]
+            .align_y(Center)
+            .padding([0.0, 5.0])
+            .spacing(2.),
+        ]
+        .into()
+    }
+}
+
+/// Seeded noise. `a` and `b` are the range for uniform noise, or the mean and standard
+/// deviation for Gaussian noise. The shape is a comma separated list of axis lengths
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
+pub struct NoiseConfig {
+    pub shape: String,
+    pub seed: u64,
+    pub a: f64,
+    pub b: f64,
+}
+
+impl Default for NoiseConfig {
+    fn default() -> Self {
+        Self {
+            shape: "100".to_string(),
+            seed: 0,
+            a: 0.,
+            b: 1.,
+        }
+    }
+}
+
+/// A signal of zeros, except for a single one at `position`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, PartialOrd)]
+pub struct ImpulseConfig {
+    pub num: usize,
+    pub position: usize,
+}
+
+impl Default for ImpulseConfig {
+    fn default() -> Self {
+        Self {
+            num: 100,
+            position: 50,
+        }
+    }
+}
+
+impl ImpulseConfig {
+    pub fn compute(&self) -> Result<StableMap<String, PortData>, NodeError> {
+        if self.position >= self.num {
+            return Err(NodeError::Config(format!(
+                "Impulse position {} is outside of a signal of length {}",
+                self.position, self.num
+            )));
+        }
+        let mut out = Array1::zeros(self.num);
+        out[self.position] = 1.;
+        Ok([("out".into(), PortData::ArrayReal(out.into_dyn()))].into())
+    }
+
+    pub fn view(&self, id: u32) -> Element<'_, Message> {
+        let update = move |config| {
+            Message::UpdateNodeTemplate(id, NodeTemplate::RustNode(RustNode::Impulse(config)))
+        };
+        row![
+            text("#"),
+            styled_text_input(
+                text_input("100", &self.num.to_string()).on_input(move |value| {
+                    update(ImpulseConfig {
+                        num: value.parse().unwrap_or(0),
+                        ..*self
+                    })
+                })
+            ),
+            text("at"),
+            styled_text_input(text_input("50", &self.position.to_string()).on_input(
+                move |value| update(ImpulseConfig {
+                    position: value.parse().unwrap_or(0),
+                    ..*self
+                })
+            )),
+        ]
+        .width(Fill)
+        .align_y(Center)
+        .padding(5.)
+        .spacing(2.)
+        .into()
+    }
+}
+
+/// A linear chirp, sweeping from `f0` to `f1` cycles per signal length
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, PartialOrd)]
+pub struct ChirpConfig {
+    pub f0: f64,
+    pub f1: f64,
+    pub num: usize,
+}
+
+impl Default for ChirpConfig {
+    fn default() -> Self {
+        Self {
+            f0: 0.,
+            f1: 20.,
+            num: 500,
+        }
+    }
+}
+
+impl ChirpConfig {
+    pub fn compute(&self) -> StableMap<String, PortData> {
+        let ChirpConfig { f0, f1, num } = *self;
+        let out = Array1::from_shape_fn(num, |i| {
+            let t = i as f64 / num as f64;
+            (TAU * (f0 * t + (f1 - f0) * t * t / 2.)).cos()
+        });
+        [("out".into(), PortData::ArrayReal(out.into_dyn()))].into()
+    }
+
+    pub fn view(&self, id: u32) -> Element<'_, Message> {
+        let update = move |config| {
+            Message::UpdateNodeTemplate(id, NodeTemplate::RustNode(RustNode::Chirp(config)))
+        };
+        column![
+            row![
+                styled_text_input(
+                    text_input("0", &self.f0.to_string()).on_input(move |value| update(
+                        ChirpConfig {
+                            f0: value.parse().unwrap_or(0.),
+                            ..*self
+                        }
+                    ))
+                ),
+                text("→"),
+                styled_text_input(
+                    text_input("20", &self.f1.to_string()).on_input(move |value| update(
+                        ChirpConfig {
+                            f1: value.parse().unwrap_or(0.),
+                            ..*self
+                        }
+                    ))
+                ),
+            ]
+            .width(Fill)
+            .align_y(Center)
+            .padding(5.)
+            .spacing(2.),
+            row![
+                text("#"),
+                styled_text_input(text_input("500", &self.num.to_string()).on_input(
+                    move |value| {
+                        update(ChirpConfig {
+                            num: value.parse().unwrap_or(0),
+                            ..*self
+                        })
+                    }
+                ))
+            ]
+            .align_y(Center)
+            .padding([0.0, 20.0])
+        ]
+        .into()
+    }
+}
+
+/// The modified Shepp-Logan head phantom, sampled on a square grid
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, PartialOrd)]
+pub struct PhantomConfig {
+    pub size: usize,
+}
+
+impl Default for PhantomConfig {
+    fn default() -> Self {
+        Self { size: 128 }
+    }
+}
+
+impl PhantomConfig {
+    pub fn compute(&self) -> StableMap<String, PortData> {
+        [(
+            "out".into(),
+            PortData::ArrayReal(shepp_logan(self.size).into_dyn()),
+        )]
+        .into()
+    }
+
+    pub fn view(&self, id: u32) -> Element<'_, Message> {
+        row![
+            text("size"),
+            styled_text_input(
+                text_input("128", &self.size.to_string()).on_input(move |value| {
+                    Message::UpdateNodeTemplate(
+                        id,
+                        NodeTemplate::RustNode(RustNode::SheppLogan(PhantomConfig {
+                            size: value.parse().unwrap_or(0),
+                        })),
+                    )
+                })
+            ),
+        ]
+        .width(Fill)
+        .align_y(Center)
+        .padding(5.)
+        .spacing(2.)
+        .into()
+    }
+}
+
+/// Expand 1-D `x` and `y` coordinates into 2-D grids, following numpy's default `xy` indexing
+/// where both outputs have the shape `[y.len(), x.len()]`
+pub fn meshgrid(
+    inputs: StableMap<String, PortDataReference>,
+) -> Result<StableMap<String, PortData>, NodeError> {
+    let axis = |name: &str| -> Result<Array1<f64>, NodeError> {
+        let data = inputs.get(name).ok_or(NodeError::input_error(name))?;
+        let array = match &**data {
+            PortData::ArrayReal(a) | PortData::Dynamic(a) => a.clone(),
+            PortData::ArrayInteger(a) => a.mapv(|v| v as f64),
+            _ => {
+                return Err(NodeError::Input(format!(
+                    "Input '{name}' must be a real valued array"
+                )))
+            }
+        };
+        array
+            .into_dimensionality()
+            .map_err(|_| NodeError::Input(format!("Input '{name}' must be one dimensional")))
+    };
+    let (x, y) = (axis("x")?, axis("y")?);
+    let shape = (y.len(), x.len());
+
+    Ok([
+        (
+            "x".into(),
+            PortData::ArrayReal(Array2::from_shape_fn(shape, |(_, j)| x[j]).into_dyn()),
+        ),
+        (
+            "y".into(),
+            PortData::ArrayReal(Array2::from_shape_fn(shape, |(i, _)| y[i]).into_dyn()),
+        ),
+    ]
+    .into())
+}
+
+/// Parse a comma separated list of axis lengths, e.g. "64, 64"
+fn parse_shape(shape: &str) -> Result<Vec<usize>, NodeError> {
+    let shape = shape
+        .split(',')
+        .map(|s| s.trim().parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| NodeError::Config(format!("Invalid shape '{shape}'")))?;
+    Ok(shape)
+}
+
+fn from_shape(shape: &[usize], data: Vec<f64>) -> ArrayD<f64> {
+    ArrayD::from_shape_vec(IxDyn(shape), data).expect("length matches shape")
+}
+
+/// (intensity, semi-axis a, semi-axis b, center x, center y, rotation in degrees),
+/// with the contrast improved intensities from Toft's modified phantom
+const SHEPP_LOGAN_ELLIPSES: [(f64, f64, f64, f64, f64, f64); 10] = [
+    (1.0, 0.69, 0.92, 0.0, 0.0, 0.0),
+    (-0.8, 0.6624, 0.874, 0.0, -0.0184, 0.0),
+    (-0.2, 0.11, 0.31, 0.22, 0.0, -18.0),
+    (-0.2, 0.16, 0.41, -0.22, 0.0, 18.0),
+    (0.1, 0.21, 0.25, 0.0, 0.35, 0.0),
+    (0.1, 0.046, 0.046, 0.0, 0.1, 0.0),
+    (0.1, 0.046, 0.046, 0.0, -0.1, 0.0),
+    (0.1, 0.046, 0.023, -0.08, -0.605, 0.0),
+    (0.1, 0.023, 0.023, 0.0, -0.606, 0.0),
+    (0.1, 0.023, 0.046, 0.06, -0.605, 0.0),
+];
+
+/// Rows run from the top (y = 1) to the bottom (y = -1) of the phantom
+fn shepp_logan(size: usize) -> Array2<f64> {
+    let coordinate = |i: usize| match size {
+        1 => 0.,
+        _ => -1. + 2. * i as f64 / (size - 1) as f64,
+    };
+    Array2::from_shape_fn((size, size), |(row, col)| {
+        let (x, y) = (coordinate(col), -coordinate(row));
+        SHEPP_LOGAN_ELLIPSES
+            .iter()
+            .filter(|(_, a, b, x0, y0, phi)| {
+                let (sin, cos) = (phi * PI / 180.).sin_cos();
+                let (dx, dy) = (x - x0, y - y0);
+                let (xr, yr) = (dx * cos + dy * sin, -dx * sin + dy * cos);
+                (xr / a).powi(2) + (yr / b).powi(2) <= 1.
+            })
+            .map(|(intensity, ..)| intensity)
+            .sum()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ndarray::array;
+    use std::sync::RwLock;
+
+    fn real(data: &PortData) -> &ArrayD<f64> {
+        let PortData::ArrayReal(data) = data else {
+            panic!("expected real array output")
+        };
+        data
+    }
+
+    #[test]
+    fn seeded_noise() {
+        let config = NoiseConfig {
+            shape: "4, 3".to_string(),
+            ..Default::default()
+        };
+        let a = Noise::Gaussian.compute(&config).unwrap();
+        let b = Noise::Gaussian.compute(&config).unwrap();
+        let c = Noise::Gaussian
+            .compute(&NoiseConfig { seed: 1, ..config })
+            .unwrap();
+        assert_eq!(real(&a["out"]), real(&b["out"]));
+        assert_ne!(real(&a["out"]), real(&c["out"]));
+        assert_eq!(real(&a["out"]).shape(), &[4, 3]);
+    }
+
+    #[test]
+    fn invalid_shape() {
+        let config = NoiseConfig {
+            shape: "4,".to_string(),
+            ..Default::default()
+        };
+        assert!(Noise::Uniform.compute(&config).is_err());
+    }
+
+    #[test]
+    fn grid() {
+        let x = RwLock::new(PortData::ArrayReal(array![1., 2., 3.].into_dyn()));
+        let y = RwLock::new(PortData::ArrayReal(array![10., 20.].into_dyn()));
+        let inputs = [
+            ("x".to_string(), x.read().unwrap()),
+            ("y".to_string(), y.read().unwrap()),
+        ]
+        .into();
+        let out = meshgrid(inputs).unwrap();
+        assert_eq!(
+            real(&out["x"]),
+            array![[1., 2., 3.], [1., 2., 3.]].into_dyn()
+        );
+        assert_eq!(
+            real(&out["y"]),
+            array![[10., 10., 10.], [20., 20., 20.]].into_dyn()
+        );
+    }
+
+    #[test]
+    fn phantom() {
+        let phantom = shepp_logan(65);
+        // Center of the brain and outside of the skull
+        assert!((phantom[[32, 32]] - 0.2).abs() < 1e-12);
+        assert_eq!(phantom[[0, 0]], 0.);
+    }
+}
@@ -1,21 +1,47 @@
 use super::{PortData, RustNode};
 use crate::app::Message;
-use crate::gui_node::PortDataContainer;
+use crate::gui_node::{PortDataContainer, PortDataReference};
 use crate::interface::node::{INNER_NODE_HEIGHT, INNER_NODE_WIDTH, NODE_BORDER_WIDTH};
-use crate::math::{linspace_delta, Vector};
+use crate::math::round_nice::round_nice;
+use crate::math::Vector;
+use crate::nodes::figure::{Figure, FigureStyle, Mark};
+use crate::nodes::port::PortType;
+use crate::nodes::status::NodeError;
 use crate::nodes::NodeTemplate;
 use crate::StableMap;
-use iced::widget::canvas::{Path, Stroke};
-use iced::widget::{container, horizontal_space, row, text, text_input};
-use iced::Alignment::Center;
-use iced::{mouse, Point};
-use iced::{
-    widget::{canvas, column},
-    Element,
+use derive_more::Display;
+use iced::alignment::{Horizontal, Vertical};
+use iced::mouse::Event::{ButtonPressed, ButtonReleased, CursorMoved, WheelScrolled};
+use iced::mouse::ScrollDelta;
+use iced::widget::{
+    button, canvas, checkbox, container, horizontal_space, pick_list, row, text, text_input,
 };
-use iced::{Rectangle, Renderer, Theme};
-use itertools::Itertools;
+use iced::Alignment::Center;
+use iced::{color, event, mouse, Color, Point, Rectangle, Renderer, Size, Theme};
+use iced::{widget::column, Element, Length::Fill};
+use numpy::Complex64;
 use serde::{Deserialize, Serialize};
+use strum::{EnumIter, IntoEnumIterator};
+
+/// Maximum number of `y` inputs
+const MAX_SERIES: usize = 8;
+/// Approximate number of ticks along each axis
+const TICK_COUNT: f32 = 4.;
+const LABEL_SIZE: f32 = 10.;
+/// Space around the plot area for tick labels, in pixels
+const LEFT_MARGIN: f32 = 34.;
+const BOTTOM_MARGIN: f32 = 14.;
+const EDGE_MARGIN: f32 = 4.;
+/// Hover readout snaps to points within this distance, in pixels
+const HOVER_DISTANCE: f32 = 12.;
+const SERIES_COLORS: [Color; 6] = [
+    color!(0x4c9be8),
+    color!(0xf28e2b),
+    color!(0x59a14f),
+    color!(0xe15759),
+    color!(0xb07aa1),
+    color!(0xedc948),
+];
 
 // Rectanlge specified by center position, width and height
 // y is up
@@ -50,49 +76,186 @@ impl Default for Rect {
     }
 }
 
-#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, PartialOrd)]
+/// Real value taken from each complex value
+type ComplexPart = fn(&Complex64) -> f64;
+
+/// Which two real lines a complex series is plotted as
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize, Display, EnumIter,
+)]
+pub enum ComplexParts {
+    #[default]
+    #[display("real/imag")]
+    RealImag,
+    #[display("mag/phase")]
+    MagPhase,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
 pub struct Plot {
+    /// Visible range, in plot space (log10 of the values on log axes)
     rect: Rect,
+    /// Number of `y` inputs, each plotted against the shared `x` input
+    #[serde(default = "default_series")]
+    pub series: usize,
+    #[serde(default)]
+    pub complex: ComplexParts,
+    #[serde(default)]
+    pub log_x: bool,
+    #[serde(default)]
+    pub log_y: bool,
+    #[serde(default)]
+    pub x_label: String,
+    #[serde(default)]
+    pub y_label: String,
+}
+
+fn default_series() -> usize {
+    1
+}
+
+impl Default for Plot {
+    fn default() -> Self {
+        Plot {
+            rect: Rect::default(),
+            series: default_series(),
+            complex: ComplexParts::default(),
+            log_x: false,
+            log_y: false,
+            x_label: String::new(),
+            y_label: String::new(),
+        }
+    }
+}
+
+/// A line to draw, with points in plot space
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    pub name: String,
+    pub points: Vec<(f32, f32)>,
 }
 
 impl Plot {
+    /// `x`, and a `y` input for each series
+    pub fn inputs(&self) -> StableMap<String, PortType> {
+        std::iter::once(("x".to_string(), PortType::ArrayReal))
+            .chain(self.y_names().map(|name| (name, PortType::Dynamic)))
+            .collect()
+    }
+
+    fn y_names(&self) -> impl Iterator<Item = String> {
+        (1..=self.series.clamp(1, MAX_SERIES)).map(|i| match i {
+            1 => "y".to_string(),
+            i => format!("y{i}"),
+        })
+    }
+
+    /// Check that the inputs can be plotted
+    pub fn compute(
+        &self,
+        inputs: StableMap<String, PortDataReference>,
+    ) -> Result<StableMap<String, PortData>, NodeError> {
+        let ys: Vec<_> = self
+            .y_names()
+            .filter_map(|name| inputs.get(&name).map(|data| (name, &**data)))
+            .collect();
+        self.traces(inputs.get("x").map(|x| &**x), &ys)?;
+        Ok([].into())
+    }
+
+    /// Lines for each `y` input, complex inputs are split in two
+    pub fn traces(
+        &self,
+        x: Option<&PortData>,
+        ys: &[(String, &PortData)],
+    ) -> Result<Vec<Trace>, NodeError> {
+        let x = match x {
+            Some(x) => Some(real_values(x).ok_or(NodeError::Input(format!(
+                "Can't plot {} as x",
+                type_name(x)
+            )))?),
+            None => None,
+        };
+        let trace = |name: String, y: Vec<f64>| {
+            let points = match &x {
+                Some(x) => x
+                    .iter()
+                    .zip(&y)
+                    .map(|(x, y)| (scale(*x, self.log_x), scale(*y, self.log_y)))
+                    .collect(),
+                None => y
+                    .iter()
+                    .enumerate()
+                    .map(|(i, y)| (scale(i as f64, self.log_x), scale(*y, self.log_y)))
+                    .collect(),
+            };
+            Trace { name, points }
+        };
+
+        let mut traces = vec![];
+        for (name, y) in ys {
+            if let Some(values) = real_values(y) {
+                traces.push(trace(name.clone(), values));
+                continue;
+            }
+            let values: Vec<_> = match y {
+                PortData::Complex(v) => vec![*v],
+                PortData::ArrayComplex(a) => a.iter().copied().collect(),
+                other => {
+                    return Err(NodeError::Input(format!(
+                        "Can't plot {} as {name}",
+                        type_name(other)
+                    )))
+                }
+            };
+            let parts: [(String, ComplexPart); 2] = match self.complex {
+                ComplexParts::RealImag => [
+                    (format!("Re {name}"), |v| v.re),
+                    (format!("Im {name}"), |v| v.im),
+                ],
+                ComplexParts::MagPhase => [
+                    (format!("|{name}|"), |v| v.norm()),
+                    (format!("∠{name}"), |v| v.arg()),
+                ],
+            };
+            for (name, part) in parts {
+                traces.push(trace(name, values.iter().map(part).collect()));
+            }
+        }
+        Ok(traces)
+    }
+
+    /// Read the inputs, plotting nothing if they can't be plotted
+    fn input_traces(&self, input_data: &StableMap<String, PortDataContainer>) -> Vec<Trace> {
+        self.read_traces(input_data).unwrap_or_default()
+    }
+
+    fn read_traces(
+        &self,
+        input_data: &StableMap<String, PortDataContainer>,
+    ) -> Result<Vec<Trace>, NodeError> {
+        let x = input_data.get("x").map(|x| x.read().unwrap());
+        let ys: Vec<_> = self
+            .y_names()
+            .filter_map(|name| input_data.get(&name).map(|y| (name, y.read().unwrap())))
+            .collect();
+        let ys: Vec<_> = ys.iter().map(|(name, y)| (name.clone(), &**y)).collect();
+        self.traces(x.as_deref(), &ys)
+    }
+
     pub fn view<'a>(
         &self,
-        _id: u32,
+        id: u32,
         input_data: StableMap<String, PortDataContainer>,
     ) -> Element<'a, Message> {
-        let (x, y) =
-            if let (Some(x_port), Some(y_port)) = (input_data.get("x"), input_data.get("y")) {
-                if let (PortData::ArrayReal(x), PortData::ArrayReal(y)) = (
-                    x_port.read().unwrap().clone(),
-                    y_port.read().unwrap().clone(),
-                ) {
-                    (
-                        x.into_raw_vec_and_offset()
-                            .0
-                            .into_iter()
-                            .map(|f| f as f32)
-                            .collect(),
-                        y.into_raw_vec_and_offset()
-                            .0
-                            .into_iter()
-                            .map(|f| f as f32)
-                            .collect(),
-                    )
-                } else {
-                    panic!("unsuported plot types ")
-                }
-            } else {
-                (vec![], vec![])
-            };
         container(
             canvas(PlotCanvas {
-                x,
-                y,
-                config: *self,
+                id,
+                traces: self.input_traces(&input_data),
+                config: self.clone(),
             })
-            .width(INNER_NODE_WIDTH * 2.)
-            .height(INNER_NODE_HEIGHT * 2.),
+            .width(Fill)
+            .height(Fill),
         )
         .padding(NODE_BORDER_WIDTH)
         .into()
@@ -101,16 +264,86 @@ impl Plot {
     pub fn config_view(
         &self,
         id: u32,
-        _input_data: StableMap<String, PortDataContainer>,
+        input_data: StableMap<String, PortDataContainer>,
     ) -> Option<Element<'_, Message>> {
         let center = self.rect.center;
         let width = self.rect.width;
         let height = self.rect.height;
+        let update = move |plot| {
+            Message::UpdateNodeTemplate(id, NodeTemplate::RustNode(RustNode::Plot(plot)))
+        };
         let message = move |rect| {
-            Message::UpdateNodeTemplate(id, NodeTemplate::RustNode(RustNode::Plot(Plot { rect })))
+            update(Plot {
+                rect,
+                ..self.clone()
+            })
         };
+        let fit = fit_rect(&self.input_traces(&input_data));
+        // Switching scales refits, as the visible range is in the scaled space
+        let toggle_log_x = Plot {
+            log_x: !self.log_x,
+            ..self.clone()
+        }
+        .refit(&input_data);
+        let toggle_log_y = Plot {
+            log_y: !self.log_y,
+            ..self.clone()
+        }
+        .refit(&input_data);
         Some(
             column![
+                row![
+                    text("series:"),
+                    horizontal_space(),
+                    pick_list(
+                        (1..=MAX_SERIES).collect::<Vec<_>>(),
+                        Some(self.series),
+                        move |series| {
+                            update(Plot {
+                                series,
+                                ..self.clone()
+                            })
+                        }
+                    ),
+                ]
+                .align_y(Center),
+                row![
+                    text("complex:"),
+                    horizontal_space(),
+                    pick_list(
+                        ComplexParts::iter().collect::<Vec<_>>(),
+                        Some(self.complex),
+                        move |complex| {
+                            update(Plot {
+                                complex,
+                                ..self.clone()
+                            })
+                        }
+                    ),
+                ]
+                .align_y(Center),
+                row![
+                    text("x label"),
+                    text_input("", &self.x_label).on_input(move |x_label| update(Plot {
+                        x_label,
+                        ..self.clone()
+                    })),
+                    text("y label"),
+                    text_input("", &self.y_label).on_input(move |y_label| update(Plot {
+                        y_label,
+                        ..self.clone()
+                    })),
+                ]
+                .align_y(Center)
+                .spacing(4.),
+                row![
+                    checkbox("log x", self.log_x).on_toggle(move |_| update(toggle_log_x.clone())),
+                    checkbox("log y", self.log_y).on_toggle(move |_| update(toggle_log_y.clone())),
+                    horizontal_space(),
+                    button("fit").on_press_maybe(fit.map(message)),
+                ]
+                .align_y(Center)
+                .spacing(8.),
                 row![
                     text("center:"),
                     horizontal_space(),
@@ -154,170 +387,553 @@ impl Plot {
             .into(),
         )
     }
+
+    /// The plot of the inputs at `size`, failing if they can't be plotted
+    pub fn figure(
+        &self,
+        input_data: &StableMap<String, PortDataContainer>,
+        size: Size,
+        style: FigureStyle,
+    ) -> Result<Figure, NodeError> {
+        let traces = self.read_traces(input_data)?;
+        Ok(self.traces_figure(&traces, size, style))
+    }
+
+    fn mapping(&self, size: Size) -> Mapping {
+        let bottom = BOTTOM_MARGIN
+            + match self.x_label.is_empty() {
+                true => 0.,
+                false => LABEL_SIZE + 2.,
+            };
+        Mapping {
+            rect: self.rect,
+            area: Rectangle {
+                x: LEFT_MARGIN,
+                y: EDGE_MARGIN,
+                width: (size.width - LEFT_MARGIN - EDGE_MARGIN).max(1.),
+                height: (size.height - bottom - EDGE_MARGIN).max(1.),
+            },
+        }
+    }
+
+    /// Grid, labels, lines and legend of the plot
+    fn traces_figure(&self, traces: &[Trace], size: Size, style: FigureStyle) -> Figure {
+        let mut figure = Figure::new(size, style);
+        let mapping = self.mapping(size);
+        let area = mapping.area;
+        let rect = self.rect;
+        let label = |content: String, position: Point, horizontal, vertical| Mark::Text {
+            content,
+            position,
+            size: LABEL_SIZE,
+            color: style.text,
+            horizontal,
+            vertical,
+        };
+        let grid_line = |from: Point, to: Point| Mark::Line {
+            points: vec![from, to],
+            color: style.grid,
+            width: 0.5,
+        };
+
+        //// Grid and tick labels
+        let (x_spacing, x_ticks) = ticks(rect.left(), rect.right());
+        for x in x_ticks {
+            let screen_x = mapping.to_screen((x, rect.top())).x;
+            figure.push(grid_line(
+                Point::new(screen_x, area.y),
+                Point::new(screen_x, area.y + area.height),
+            ));
+            figure.push(label(
+                tick_label(x, x_spacing, self.log_x),
+                Point::new(screen_x, area.y + area.height + 2.),
+                Horizontal::Center,
+                Vertical::Top,
+            ));
+        }
+        let (y_spacing, y_ticks) = ticks(rect.bottom(), rect.top());
+        for y in y_ticks {
+            let screen_y = mapping.to_screen((rect.left(), y)).y;
+            figure.push(grid_line(
+                Point::new(area.x, screen_y),
+                Point::new(area.x + area.width, screen_y),
+            ));
+            figure.push(label(
+                tick_label(y, y_spacing, self.log_y),
+                Point::new(area.x - 3., screen_y),
+                Horizontal::Right,
+                Vertical::Center,
+            ));
+        }
+        figure.push(Mark::Rectangle {
+            bounds: area,
+            color: style.grid,
+            width: 1.,
+        });
+
+        //// Axis labels
+        if !self.x_label.is_empty() {
+            figure.push(label(
+                self.x_label.clone(),
+                Point::new(area.center_x(), size.height - 1.),
+                Horizontal::Center,
+                Vertical::Bottom,
+            ));
+        }
+        if !self.y_label.is_empty() {
+            figure.push(label(
+                self.y_label.clone(),
+                Point::new(area.x + 3., area.y + 2.),
+                Horizontal::Left,
+                Vertical::Top,
+            ));
+        }
+
+        //// Lines, broken where values are not finite
+        let mut lines = vec![];
+        for (i, trace) in traces.iter().enumerate() {
+            let color = SERIES_COLORS[i % SERIES_COLORS.len()];
+            for segment in trace
+                .points
+                .split(|(x, y)| !(x.is_finite() && y.is_finite()))
+                .filter(|segment| !segment.is_empty())
+            {
+                lines.push(Mark::Line {
+                    points: segment.iter().map(|p| mapping.to_screen(*p)).collect(),
+                    color,
+                    width: 1.5,
+                });
+            }
+        }
+        figure.push(Mark::Clip {
+            bounds: area,
+            marks: lines,
+        });
+
+        //// Legend
+        if traces.len() > 1 {
+            for (i, trace) in traces.iter().enumerate() {
+                let y = area.y + 6. + i as f32 * (LABEL_SIZE + 2.);
+                let right = area.x + area.width - 4.;
+                figure.push(Mark::Line {
+                    points: vec![Point::new(right - 10., y), Point::new(right, y)],
+                    color: SERIES_COLORS[i % SERIES_COLORS.len()],
+                    width: 2.,
+                });
+                figure.push(label(
+                    trace.name.clone(),
+                    Point::new(right - 13., y),
+                    Horizontal::Right,
+                    Vertical::Center,
+                ));
+            }
+        }
+        figure
+    }
+
+    /// Fit the visible range to the data
+    fn refit(self, input_data: &StableMap<String, PortDataContainer>) -> Self {
+        match fit_rect(&self.input_traces(input_data)) {
+            Some(rect) => Plot { rect, ..self },
+            None => self,
+        }
+    }
 }
 
-#[derive(Debug)]
-struct PlotCanvas {
-    x: Vec<f32>,
-    y: Vec<f32>,
-    config: Plot,
+/// Values of real or integer data, as a flat list
+fn real_values(data: &PortData) -> Option<Vec<f64>> {
+    match data {
+        PortData::Integer(v) => Some(vec![*v as f64]),
+        PortData::Real(v) => Some(vec![*v]),
+        PortData::ArrayInteger(a) => Some(a.iter().map(|v| *v as f64).collect()),
+        PortData::ArrayReal(a) | PortData::Dynamic(a) => Some(a.iter().copied().collect()),
+        _ => None,
+    }
 }
 
-impl<Message> canvas::Program<Message> for PlotCanvas {
-    // No internal state
-    type State = ();
+fn type_name(data: &PortData) -> String {
+    format!(
+        "{:?}",
+        crate::nodes::port::PortDataDiscriminants::from(data)
+    )
+}
 
-    fn draw(
-        &self,
-        _state: &(),
-        renderer: &Renderer,
-        theme: &Theme,
-        bounds: Rectangle,
-        _cursor: mouse::Cursor,
-    ) -> Vec<canvas::Geometry> {
-        // We prepare a new `Frame`
-        let mut frame = canvas::Frame::new(renderer, bounds.size());
+/// Values on a log axis are plotted as their log10, and non-positive values are left out
+fn scale(v: f64, log: bool) -> f32 {
+    match log {
+        true if v > 0. => v.log10() as f32,
+        true => f32::NAN,
+        false => v as f32,
+    }
+}
 
-        let node_width = bounds.width;
-        let node_height = bounds.height;
-        let aspect = node_width / node_height;
+/// The smallest rectangle containing every finite point, with a small margin
+pub fn fit_rect(traces: &[Trace]) -> Option<Rect> {
+    let (mut lo, mut hi) = (
+        (f32::INFINITY, f32::INFINITY),
+        (f32::NEG_INFINITY, f32::NEG_INFINITY),
+    );
+    for (x, y) in traces.iter().flat_map(|trace| &trace.points) {
+        if x.is_finite() && y.is_finite() {
+            lo = (lo.0.min(*x), lo.1.min(*y));
+            hi = (hi.0.max(*x), hi.1.max(*y));
+        }
+    }
+    if lo.0 > hi.0 {
+        return None;
+    }
+    // Flat data still gets a visible range
+    let size = |lo: f32, hi: f32| match hi - lo {
+        0. => 1.,
+        size => size * 1.1,
+    };
+    Some(Rect {
+        center: Vector::new((lo.0 + hi.0) / 2., (lo.1 + hi.1) / 2.),
+        width: size(lo.0, hi.0),
+        height: size(lo.1, hi.1),
+    })
+}
 
-        frame.push_transform();
-        //TODO: figure out how ot rephrase this in math, so I can actually
-        //predict/undestand how to switch between plot space and screen space
-        //// center canvas on the origin, y up
-        frame.translate([frame.center().x, frame.center().y].into());
-        frame.scale_nonuniform([1., -1.]);
+/// Evenly spaced "nice" values in `lo..=hi`, and their spacing
+pub fn ticks(lo: f32, hi: f32) -> (f32, Vec<f32>) {
+    let spacing = round_nice((hi - lo) / TICK_COUNT);
+    if !spacing.is_finite() || spacing <= 0. {
+        return (spacing, vec![]);
+    }
+    let first = (lo / spacing).ceil() as i64;
+    let last = (hi / spacing).floor() as i64;
+    (
+        spacing,
+        (first..=last).map(|i| i as f32 * spacing).collect(),
+    )
+}
 
-        frame.push_transform();
+/// Tick text with just enough decimals to tell neighbouring ticks apart
+pub fn tick_label(value: f32, spacing: f32, log: bool) -> String {
+    match log {
+        true if spacing >= 1. => format!("1e{}", value.round()),
+        true => format!("{:.3}", 10f32.powf(value)),
+        // Adding zero turns -0 into 0
+        false => {
+            let decimals = (-spacing.log10().floor()).max(0.) as usize;
+            format!("{:.decimals$}", value + 0.)
+        }
+    }
+}
 
-        let scale = Vector::new(
-            node_width / self.config.rect.width,
-            node_height / self.config.rect.height,
-        );
-        // scale for the conifgured height/width
-        frame.scale_nonuniform(scale);
+/// Maps between plot space and the pixels of the plot area
+#[derive(Debug, Clone, Copy)]
+struct Mapping {
+    rect: Rect,
+    area: Rectangle,
+}
 
-        //move the center point to the center of our canvas
-        frame.translate((-self.config.rect.center).into());
+impl Mapping {
+    fn to_screen(self, (x, y): (f32, f32)) -> Point {
+        Point::new(
+            self.area.x + (x - self.rect.left()) / self.rect.width * self.area.width,
+            self.area.y + (self.rect.top() - y) / self.rect.height * self.area.height,
+        )
+    }
 
-        // The frame is now centered on center, and goes from:
-        // rect.left   -> rect.right
-        // rect.bottom -> rect.top
+    fn to_plot(self, point: Point) -> (f32, f32) {
+        (
+            self.rect.left() + (point.x - self.area.x) / self.area.width * self.rect.width,
+            self.rect.top() - (point.y - self.area.y) / self.area.height * self.rect.height,
+        )
+    }
+}
 
-        //// Grid
-        {
-            let color = theme.extended_palette().secondary.strong.color;
-            let main_grid_stroke = Stroke::default()
-                .with_width(0.8)
-                .with_color(color.scale_alpha(0.5));
+#[derive(Debug)]
+struct PlotCanvas {
+    id: u32,
+    traces: Vec<Trace>,
+    config: Plot,
+}
 
-            let secondary_grid_stroke = main_grid_stroke.with_width(0.4);
+#[derive(Debug, Default)]
+struct Interaction {
+    /// Cursor position and visible range where a pan started
+    pan: Option<(Point, Rect)>,
+    /// Range of the node when a pan or zoom started, and the range shown since.
+    /// The node is updated once the gesture ends, rather than recomputing on every event
+    live: Option<(Rect, Rect)>,
+}
 
-            let tertiary_grid_strok = secondary_grid_stroke;
+impl PlotCanvas {
+    /// The configuration to draw, with the range of a pan or zoom in progress
+    fn live_config(&self, state: &Interaction) -> Plot {
+        match state.live {
+            // Ignore the gesture if the node's range changed in the meantime
+            Some((start, rect)) if start == self.config.rect => Plot {
+                rect,
+                ..self.config.clone()
+            },
+            _ => self.config.clone(),
+        }
+    }
 
-            let max_length = (self.config.rect.width + self.config.rect.center.x.abs())
-                .max(self.config.rect.height + self.config.rect.center.y.abs());
+    /// Show a new range until the gesture ends
+    fn preview_rect(&self, state: &mut Interaction, rect: Rect) {
+        state.live = Some((self.config.rect, rect));
+    }
 
-            grid_path(self.config.rect, max_length, aspect, max_length)
-                .into_iter()
-                .for_each(|p| frame.stroke(&p, main_grid_stroke));
+    /// End a pan or zoom, updating the node with the range that is shown
+    fn finish_gesture(&self, state: &mut Interaction) -> Option<Message> {
+        match state.live.take() {
+            Some((start, rect)) if start == self.config.rect && rect != start => {
+                Some(self.update_rect(rect))
+            }
+            _ => None,
+        }
+    }
 
-            {
-                grid_path(self.config.rect, 0.05, aspect, 10.)
-                    .into_iter()
-                    .for_each(|p| frame.stroke(&p, secondary_grid_stroke));
+    fn update_rect(&self, rect: Rect) -> Message {
+        Message::UpdateNodeTemplate(
+            self.id,
+            NodeTemplate::RustNode(RustNode::Plot(Plot {
+                rect,
+                ..self.config.clone()
+            })),
+        )
+    }
+
+    /// The trace and point closest to `cursor`, if any are close enough
+    fn hovered(&self, mapping: Mapping, cursor: Point) -> Option<(usize, (f32, f32))> {
+        self.traces
+            .iter()
+            .enumerate()
+            .flat_map(|(i, trace)| trace.points.iter().map(move |p| (i, *p)))
+            .filter(|(_, (x, y))| x.is_finite() && y.is_finite())
+            .map(|(i, p)| (i, p, mapping.to_screen(p).distance(cursor)))
+            .filter(|(_, _, distance)| *distance < HOVER_DISTANCE)
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+            .map(|(i, p, _)| (i, p))
+    }
+}
+
+impl canvas::Program<Message> for PlotCanvas {
+    type State = Interaction;
 
-                grid_path(self.config.rect, 0.025, aspect, 1.)
-                    .into_iter()
-                    .for_each(|p| frame.stroke(&p, tertiary_grid_strok));
+    /// Scroll to zoom around the cursor, and drag with the right button to pan.
+    /// A zoom is applied to the node when the cursor next moves
+    fn update(
+        &self,
+        state: &mut Interaction,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (event::Status, Option<Message>) {
+        let config = self.live_config(state);
+        let mapping = config.mapping(bounds.size());
+        let rect = config.rect;
+        match event {
+            canvas::Event::Mouse(ButtonPressed(mouse::Button::Right)) => {
+                match cursor.position_in(bounds) {
+                    Some(position) => {
+                        state.pan = Some((position, rect));
+                        (event::Status::Captured, None)
+                    }
+                    None => (event::Status::Ignored, None),
+                }
+            }
+            canvas::Event::Mouse(ButtonReleased(mouse::Button::Right)) if state.pan.is_some() => {
+                state.pan = None;
+                (event::Status::Captured, self.finish_gesture(state))
             }
+            canvas::Event::Mouse(CursorMoved { position }) => match state.pan {
+                Some((start, start_rect)) => {
+                    let position = position - bounds.position();
+                    let delta = (position.x - start.x, position.y - start.y);
+                    let center = Vector::new(
+                        start_rect.center.x - delta.0 / mapping.area.width * start_rect.width,
+                        start_rect.center.y + delta.1 / mapping.area.height * start_rect.height,
+                    );
+                    self.preview_rect(
+                        state,
+                        Rect {
+                            center,
+                            ..start_rect
+                        },
+                    );
+                    (event::Status::Captured, None)
+                }
+                None => (event::Status::Ignored, self.finish_gesture(state)),
+            },
+            canvas::Event::Mouse(WheelScrolled { delta }) => {
+                let Some(position) = cursor.position_in(bounds) else {
+                    return (event::Status::Ignored, None);
+                };
+                let factor = match delta {
+                    ScrollDelta::Lines { y, .. } => 1.1_f32.powf(-y),
+                    ScrollDelta::Pixels { y, .. } => (-y * 0.005).exp(),
+                };
+                // Keep the point under the cursor in place
+                let anchor = mapping.to_plot(position);
+                let center = Vector::new(
+                    anchor.0 + (rect.center.x - anchor.0) * factor,
+                    anchor.1 + (rect.center.y - anchor.1) * factor,
+                );
+                self.preview_rect(
+                    state,
+                    Rect {
+                        center,
+                        width: rect.width * factor,
+                        height: rect.height * factor,
+                    },
+                );
+                (event::Status::Captured, None)
+            }
+            _ => (event::Status::Ignored, None),
         }
+    }
 
-        let line_stroke = Stroke::default()
-            .with_color(theme.extended_palette().success.strong.color)
-            .with_width(2.);
-        self.x
-            .clone()
-            .into_iter()
-            .zip(self.y.clone())
-            .tuple_windows()
-            .map(|(from, to)| {
-                if from.0.is_finite() && from.1.is_finite() && to.0.is_finite() && to.1.is_finite()
-                {
-                    (Path::line(Point::from(from), Point::from(to)), line_stroke)
-                } else if from.0.is_finite() && to.0.is_finite() {
-                    (
-                        Path::line(
-                            Point::from((from.0, self.config.rect.center.y)),
-                            Point::from((to.0, self.config.rect.center.y)),
-                        ),
-                        line_stroke.with_color(theme.palette().danger),
-                    )
-                } else {
-                    (
-                        Path::circle(
-                            Point::from((
-                                self.config.rect.right() - 1.,
-                                self.config.rect.top() - 1.,
-                            )),
-                            0.75,
-                        ),
-                        line_stroke.with_color(theme.palette().danger),
-                    )
-                }
-            })
-            .for_each(|(path, stroke)| frame.stroke(&path, stroke));
+    fn draw(
+        &self,
+        state: &Interaction,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let style = FigureStyle::from_theme(theme);
+        let config = self.live_config(state);
+        let mut figure = config.traces_figure(&self.traces, bounds.size(), style);
 
-        frame.pop_transform();
+        //// Hover readout
+        let mapping = config.mapping(bounds.size());
+        let area = mapping.area;
+        if let Some((i, point)) = cursor
+            .position_in(bounds)
+            .filter(|position| area.contains(*position))
+            .and_then(|position| self.hovered(mapping, position))
+        {
+            let screen = mapping.to_screen(point);
+            figure.push(Mark::Dot {
+                center: screen,
+                radius: 3.,
+                color: SERIES_COLORS[i % SERIES_COLORS.len()],
+            });
+            let value = |v: f32, log: bool| match log {
+                true => 10f32.powf(v),
+                false => v,
+            };
+            let left_half = screen.x < area.center_x();
+            figure.push(Mark::Text {
+                content: format!(
+                    "{}: {:.4}, {:.4}",
+                    self.traces[i].name,
+                    value(point.0, config.log_x),
+                    value(point.1, config.log_y)
+                ),
+                position: Point::new(screen.x + if left_half { 6. } else { -6. }, screen.y - 4.),
+                size: LABEL_SIZE,
+                color: style.text,
+                horizontal: if left_half {
+                    Horizontal::Left
+                } else {
+                    Horizontal::Right
+                },
+                vertical: Vertical::Bottom,
+            });
+        }
 
+        figure.draw(&mut frame);
         vec![frame.into_geometry()]
     }
+
+    fn mouse_interaction(
+        &self,
+        state: &Interaction,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        match (state.pan, cursor.is_over(bounds)) {
+            (Some(_), _) => mouse::Interaction::Grabbing,
+            (None, true) => mouse::Interaction::Crosshair,
+            (None, false) => mouse::Interaction::default(),
+        }
+    }
 }
 
-fn grid_path(
-    plot_rect: Rect,
-    tick_length_node_space: f32,
-    aspect: f32,
-    tick_spacing: f32,
-) -> Vec<Path> {
-    let left = ((plot_rect.left() / tick_spacing).floor()) * tick_spacing;
-    let right = ((plot_rect.right() / tick_spacing).ceil()) * tick_spacing;
-    let bottom = ((plot_rect.bottom() / tick_spacing).floor()) * tick_spacing;
-    let top = ((plot_rect.top() / tick_spacing).ceil()) * tick_spacing;
-    let cx = 0.0f32.clamp(left, right);
-    let cy = 0.0f32.clamp(bottom, top);
-    let x_tick_length = tick_length_node_space * plot_rect.height * aspect;
-    let y_tick_length = tick_length_node_space * plot_rect.width;
-
-    if left.is_nan() || right.is_nan() || top.is_nan() || bottom.is_nan() {
-        panic!("Encountered nan!{:?}", (plot_rect, tick_spacing))
-    }
-
-    let h_lines = linspace_delta(top, bottom, tick_spacing)
-        .into_iter()
-        .map(|y| {
-            if y.is_nan() {
-                panic!("Encountered nan!{:?}", (plot_rect, tick_spacing))
-            }
-            Path::line(
-                (cx - (y_tick_length / 2.), y).into(),
-                (cx + (y_tick_length / 2.), y).into(),
-            )
-        });
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ndarray::array;
 
-    let v_lines = linspace_delta(right, left, tick_spacing)
-        .into_iter()
-        .map(|x| {
-            if x.is_nan() {
-                panic!("Encountered nan!{:?}", (plot_rect, tick_spacing))
-            }
+    #[test]
+    fn nice_ticks() {
+        let (spacing, values) = ticks(-1., 1.);
+        assert_eq!(spacing, 0.5);
+        assert_eq!(values, vec![-1., -0.5, 0., 0.5, 1.]);
+        assert_eq!(tick_label(-0., 0.5, false), "0.0");
+        assert_eq!(tick_label(20., 10., false), "20");
+        assert_eq!(tick_label(3., 1., true), "1e3");
+    }
 
-            Path::line(
-                (x, cy - (x_tick_length / 2.)).into(),
-                (x, cy + (x_tick_length / 2.)).into(),
-            )
+    #[test]
+    fn multiple_series() {
+        let plot = Plot {
+            series: 2,
+            complex: ComplexParts::MagPhase,
+            log_y: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            plot.inputs().keys().collect::<Vec<_>>(),
+            vec!["x", "y", "y2"]
+        );
+
+        let x = PortData::ArrayReal(array![0., 1.].into_dyn());
+        let y = PortData::ArrayReal(array![10., -1.].into_dyn());
+        let y2 = PortData::ArrayComplex(array![Complex64::new(0., 100.)].into_dyn());
+        let traces = plot
+            .traces(Some(&x), &[("y".into(), &y), ("y2".into(), &y2)])
+            .unwrap();
+        assert_eq!(
+            traces.iter().map(|t| t.name.as_str()).collect::<Vec<_>>(),
+            vec!["y", "|y2|", "∠y2"]
+        );
+        assert_eq!(traces[0].points[0], (0., 1.));
+        // Non-positive values are left out of log axes
+        assert!(traces[0].points[1].1.is_nan());
+        assert_eq!(traces[1].points, vec![(0., 2.)]);
+
+        let object = PortData::Object(Default::default());
+        assert!(plot.traces(None, &[("y".into(), &object)]).is_err());
+    }
+
+    #[test]
+    fn zoom_updates_the_node_once() {
+        use canvas::Program;
+        let plot = PlotCanvas {
+            id: 3,
+            traces: vec![],
+            config: Plot::default(),
+        };
+        let mut state = Interaction::default();
+        let bounds = Rectangle::new(Point::ORIGIN, Size::new(200., 100.));
+        let position = Point::new(100., 50.);
+        let cursor = mouse::Cursor::Available(position);
+
+        let wheel = canvas::Event::Mouse(WheelScrolled {
+            delta: ScrollDelta::Lines { x: 0., y: 1. },
         });
+        for _ in 0..3 {
+            let (_, message) = plot.update(&mut state, wheel.clone(), bounds, cursor);
+            assert!(message.is_none());
+        }
+        let zoomed = plot.live_config(&state).rect;
+        assert!(zoomed.width < plot.config.rect.width);
 
-    h_lines.chain(v_lines).collect()
+        let moved = canvas::Event::Mouse(CursorMoved { position });
+        let (_, message) = plot.update(&mut state, moved, bounds, cursor);
+        assert!(matches!(
+            message,
+            Some(Message::UpdateNodeTemplate(3, NodeTemplate::RustNode(RustNode::Plot(Plot { rect, .. }))))
+                if rect == zoomed
+        ));
+        assert!(state.live.is_none());
+    }
 }
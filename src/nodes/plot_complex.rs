@@ -3,15 +3,24 @@ use crate::app::Message;
 use crate::gui_node::{PortDataContainer, PortDataReference};
 use crate::interface::node::{INNER_NODE_WIDTH, NODE_BORDER_WIDTH};
 use crate::math::Vector;
+use crate::nodes::figure::{Figure, FigureStyle, Mark};
+use crate::nodes::port::PortDataDiscriminants;
+use crate::nodes::status::NodeError;
 use crate::nodes::NodeTemplate;
 use crate::StableMap;
+use colorgrad::{preset, Gradient};
+use derive_more::Display;
 use iced::widget::image::Handle;
-use iced::widget::{button, container, horizontal_space, image, row, text, text_input};
+use iced::widget::{
+    button, checkbox, container, horizontal_space, image, pick_list, row, text, text_input,
+};
 use iced::Alignment::Center;
-use iced::{widget::column, Element};
+use iced::{widget::column, Element, Length::Fill, Point, Rectangle, Size};
 use log::trace;
-use ndarray::Array3;
+use numpy::Complex64;
 use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use strum::{EnumIter, IntoEnumIterator};
 
 // Rectanlge specified by center position, width and height
 // y is up
@@ -45,9 +54,88 @@ impl Default for Rect {
     }
 }
 
+/// How complex values are turned into an image
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize, Display, EnumIter,
+)]
+pub enum ComplexMode {
+    #[default]
+    Magnitude,
+    Phase,
+    Real,
+    Imaginary,
+    /// Phase as hue, and magnitude as brightness
+    #[display("HSV")]
+    Hsv,
+}
+
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize, Display, EnumIter,
+)]
+pub enum Colormap {
+    #[default]
+    Gray,
+    Viridis,
+    Inferno,
+    Magma,
+    Plasma,
+    Cividis,
+    Turbo,
+    /// Cyclic, suited to phase
+    Sinebow,
+}
+
+impl Colormap {
+    /// Color of `t` in 0..=1
+    fn rgba(self, t: f32) -> [u8; 4] {
+        match self {
+            Colormap::Gray => {
+                let b = (t * 255.0).round() as u8;
+                [b, b, b, 255]
+            }
+            Colormap::Viridis => preset::viridis().at(t).to_rgba8(),
+            Colormap::Inferno => preset::inferno().at(t).to_rgba8(),
+            Colormap::Magma => preset::magma().at(t).to_rgba8(),
+            Colormap::Plasma => preset::plasma().at(t).to_rgba8(),
+            Colormap::Cividis => preset::cividis().at(t).to_rgba8(),
+            Colormap::Turbo => preset::turbo().at(t).to_rgba8(),
+            Colormap::Sinebow => preset::sinebow().at(t).to_rgba8(),
+        }
+    }
+}
+
+/// Range of values that is spread over the colormap
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum Windowing {
+    /// Ignore this percentage of the lowest and highest values, 0 uses the full range
+    Percentile(f64),
+    /// A range of `window` width, centered on `level`
+    WindowLevel { window: f64, level: f64 },
+}
+
+impl Default for Windowing {
+    fn default() -> Self {
+        Windowing::Percentile(0.)
+    }
+}
+
+/// How data is turned into colors, shared by the image viewing nodes
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageDisplay {
+    pub windowing: Windowing,
+    pub colormap: Colormap,
+    pub complex_mode: ComplexMode,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Plot2D {
     pub rect: Rect,
+    #[serde(default)]
+    pub display: ImageDisplay,
+    /// Range of values in the current image, a starting point for manual window/level
+    #[serde(skip)]
+    pub range: Option<(f64, f64)>,
     #[serde(skip)]
     pub image_handle: Option<Handle>,
 }
@@ -71,8 +159,8 @@ impl Plot2D {
             Some(handle) => container(
                 image(handle)
                     .filter_method(image::FilterMethod::Nearest)
-                    .width(INNER_NODE_WIDTH * 2.)
-                    .height(INNER_NODE_WIDTH * 2.),
+                    .width(Fill)
+                    .height(Fill),
             ),
             _ => container(text("")),
         }
@@ -98,8 +186,18 @@ impl Plot2D {
             )
         };
         let zoom_speed = 0.125;
+        let update = move |display| {
+            Message::UpdateNodeTemplate(
+                id,
+                NodeTemplate::RustNode(RustNode::Plot2D(Self {
+                    display,
+                    ..self.clone()
+                })),
+            )
+        };
         Some(
             column![
+                self.display.controls(self.range, update),
                 row![
                     text("center:"),
                     horizontal_space(),
@@ -174,66 +272,286 @@ impl Plot2D {
         )
     }
 
-    fn create_image_handle(data: &Array3<f64>) -> Handle {
-        trace!("Creating image handle for plot2d, {:?}", data.shape());
-        let max = data.iter().fold(-f64::INFINITY, |a, &b| a.max(b));
-        let min = data.iter().fold(f64::INFINITY, |a, &b| a.min(b));
-        let brightness = |p: f64| {
-            let p = ((p - min) / (max - min)) as f32;
-            let p = if p.is_nan() { 0.0 } else { p };
-            (p * 255.0).round() as u8
-        };
-        let img: Vec<u8> = data
-            .outer_iter()
-            .flat_map(|row| {
-                row.outer_iter()
-                    .flat_map(|p| {
-                        if p.len() == 1 {
-                            let b = brightness(p[0]);
-                            [b, b, b, 255]
-                        } else if p.len() == 3 {
-                            [brightness(p[0]), brightness(p[1]), brightness(p[2]), 255]
-                        } else {
-                            panic!("unsupported array dimensions")
-                        }
-                    })
-                    .collect::<Vec<_>>()
-            })
-            .collect();
-        Handle::from_rgba(data.dim().0 as u32, data.dim().1 as u32, img)
+    /// The image of the input at `size`
+    pub fn figure(
+        &self,
+        input_data: &StableMap<String, PortDataContainer>,
+        size: Size,
+        style: FigureStyle,
+    ) -> Result<Figure, NodeError> {
+        let port = input_data
+            .get("a")
+            .ok_or(NodeError::Input("No image on input a".to_string()))?;
+        let image = self.display.image(&port.read().unwrap())?;
+        let mut figure = Figure::new(size, style);
+        figure.push(image.mark(Rectangle::with_size(size)));
+        Ok(figure)
     }
 
+    /// Render the input into an image, for 1D or 2D arrays of any shape
     pub(crate) fn input_changed(
         &mut self,
         input_data: StableMap<String, PortDataReference>,
-    ) -> PortData {
-        let (image_handle, port_data) = match input_data.get("a") {
-            Some(port) => {
-                let data = match &**port {
-                    PortData::ArrayReal(a) => &Array3::<f64>::from_shape_vec(
-                        (a.shape()[0], a.shape()[1], 3),
-                        a.iter().flat_map(|v| [*v, *v, *v]).collect::<Vec<_>>(),
-                    )
-                    .expect("square matrix"),
-                    PortData::ArrayComplex(a) => &Array3::<f64>::from_shape_vec(
-                        (
-                            (a.len() as f32).sqrt() as usize,
-                            (a.len() as f32).sqrt() as usize,
-                            3,
-                        ),
-                        a.iter()
-                            .map(|v| v.norm_sqr().sqrt())
-                            .flat_map(|v| [v, v, v])
-                            .collect::<Vec<_>>(),
-                    )
-                    .expect("square matrix"),
-                    _ => panic!("unsuported plot types {:?}", port),
+    ) -> Result<PortData, NodeError> {
+        let Some(port) = input_data.get("a") else {
+            self.image_handle = None;
+            self.range = None;
+            return Ok(PortData::ArrayReal(Default::default()));
+        };
+        let image = self.display.image(port)?;
+        trace!("Creating image handle for plot2d, {:?}", image.shape);
+        self.range = Some(image.range);
+        self.image_handle = Some(image.handle());
+        Ok((**port).clone())
+    }
+}
+
+impl ImageDisplay {
+    /// Controls for the complex mode, colormap and windowing.
+    /// `range` is the range of the current image, where manual window/level starts from
+    pub fn controls<'a>(
+        self,
+        range: Option<(f64, f64)>,
+        update: impl Fn(Self) -> Message + Copy + 'a,
+    ) -> Element<'a, Message> {
+        let (lo, hi) = range.unwrap_or((0., 1.));
+        let windowing = match self.windowing {
+            Windowing::Percentile(clip) => row![
+                text("clip %:"),
+                horizontal_space(),
+                text_input("0", &clip.to_string()).on_input(move |value| update(Self {
+                    windowing: Windowing::Percentile(value.parse().unwrap_or(clip).clamp(0., 50.)),
+                    ..self
+                })),
+            ],
+            Windowing::WindowLevel { window, level } => row![
+                text("window"),
+                text_input("1", &window.to_string()).on_input(move |value| update(Self {
+                    windowing: Windowing::WindowLevel {
+                        window: value.parse().unwrap_or(window),
+                        level
+                    },
+                    ..self
+                })),
+                text("level"),
+                text_input("0", &level.to_string()).on_input(move |value| update(Self {
+                    windowing: Windowing::WindowLevel {
+                        window,
+                        level: value.parse().unwrap_or(level)
+                    },
+                    ..self
+                })),
+            ],
+        }
+        .align_y(Center)
+        .spacing(4.);
+        column![
+            row![
+                text("complex:"),
+                horizontal_space(),
+                pick_list(
+                    ComplexMode::iter().collect::<Vec<_>>(),
+                    Some(self.complex_mode),
+                    move |complex_mode| update(Self {
+                        complex_mode,
+                        ..self
+                    })
+                ),
+            ]
+            .align_y(Center),
+            row![
+                text("colormap:"),
+                horizontal_space(),
+                pick_list(
+                    Colormap::iter().collect::<Vec<_>>(),
+                    Some(self.colormap),
+                    move |colormap| update(Self { colormap, ..self })
+                ),
+            ]
+            .align_y(Center),
+            checkbox(
+                "window/level",
+                matches!(self.windowing, Windowing::WindowLevel { .. })
+            )
+            .on_toggle(move |manual| update(Self {
+                windowing: match manual {
+                    // Start from the range currently shown
+                    true => Windowing::WindowLevel {
+                        window: hi - lo,
+                        level: (hi + lo) / 2.,
+                    },
+                    false => Windowing::default(),
+                },
+                ..self
+            })),
+            windowing,
+        ]
+        .spacing(5.0)
+        .into()
+    }
+
+    /// Map data to colors, according to the complex mode, windowing and colormap
+    pub fn image(&self, data: &PortData) -> Result<Image, NodeError> {
+        let shape = |dims: &[usize]| match dims {
+            [columns] => Ok((1, *columns)),
+            [rows, columns] => Ok((*rows, *columns)),
+            _ => Err(NodeError::Input(format!(
+                "Plot 2D needs a 1D or 2D array, found shape {dims:?}"
+            ))),
+        };
+        let (shape, values, hue): (_, Vec<f64>, Option<Vec<f64>>) = match data {
+            PortData::ArrayReal(a) | PortData::Dynamic(a) => {
+                (shape(a.shape())?, a.iter().copied().collect(), None)
+            }
+            PortData::ArrayInteger(a) => (
+                shape(a.shape())?,
+                a.iter().map(|v| *v as f64).collect(),
+                None,
+            ),
+            PortData::ArrayComplex(a) => {
+                let part = |f: fn(&Complex64) -> f64| a.iter().map(f).collect();
+                let values = match self.complex_mode {
+                    ComplexMode::Magnitude | ComplexMode::Hsv => part(|v| v.norm()),
+                    ComplexMode::Phase => part(|v| v.arg()),
+                    ComplexMode::Real => part(|v| v.re),
+                    ComplexMode::Imaginary => part(|v| v.im),
                 };
-                (Some(Self::create_image_handle(data)), (**port).clone())
+                let hue = (self.complex_mode == ComplexMode::Hsv).then(|| part(|v| v.arg()));
+                (shape(a.shape())?, values, hue)
+            }
+            other => {
+                return Err(NodeError::Input(format!(
+                    "Plot 2D can't display {:?}",
+                    PortDataDiscriminants::from(other)
+                )))
             }
-            None => (None, PortData::ArrayReal(Default::default())),
         };
-        self.image_handle = image_handle;
-        port_data
+
+        // Phase has a known range, so it isn't clipped unless a window is set
+        let is_phase =
+            matches!(data, PortData::ArrayComplex(_)) && self.complex_mode == ComplexMode::Phase;
+        let range = match self.windowing {
+            Windowing::Percentile(0.) if is_phase => (-PI, PI),
+            Windowing::Percentile(clip) => percentile_range(&values, clip),
+            Windowing::WindowLevel { window, level } => (level - window / 2., level + window / 2.),
+        };
+        let normalize = |v: f64| {
+            let t = ((v - range.0) / (range.1 - range.0)).clamp(0., 1.);
+            if t.is_nan() {
+                0.
+            } else {
+                t as f32
+            }
+        };
+        let pixels = match hue {
+            Some(hue) => values
+                .iter()
+                .zip(hue)
+                .flat_map(|(v, phase)| {
+                    let degrees = (phase.to_degrees() as f32).rem_euclid(360.);
+                    colorgrad::Color::from_hsva(degrees, 1., normalize(*v), 1.).to_rgba8()
+                })
+                .collect(),
+            None => values
+                .iter()
+                .flat_map(|v| self.colormap.rgba(normalize(*v)))
+                .collect(),
+        };
+        Ok(Image {
+            shape,
+            range,
+            pixels,
+        })
+    }
+}
+
+/// RGBA pixels of a plot, with `shape` in (rows, columns)
+pub struct Image {
+    pub shape: (usize, usize),
+    /// Range of values spread over the colormap
+    pub range: (f64, f64),
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn handle(self) -> Handle {
+        Handle::from_rgba(self.shape.1 as u32, self.shape.0 as u32, self.pixels)
+    }
+
+    /// The image centered in `bounds`, as large as fits while keeping its aspect ratio
+    pub fn mark(self, bounds: Rectangle) -> Mark {
+        let (rows, columns) = (self.shape.0 as f32, self.shape.1 as f32);
+        let scale = (bounds.width / columns).min(bounds.height / rows);
+        let size = Size::new(columns * scale, rows * scale);
+        Mark::Image {
+            bounds: Rectangle::new(
+                Point::new(
+                    bounds.center_x() - size.width / 2.,
+                    bounds.center_y() - size.height / 2.,
+                ),
+                size,
+            ),
+            columns: self.shape.1 as u32,
+            rows: self.shape.0 as u32,
+            pixels: self.pixels,
+        }
+    }
+}
+
+/// The range between the `clip` and `100 - clip` percentiles of the finite values
+fn percentile_range(values: &[f64], clip: f64) -> (f64, f64) {
+    let mut finite: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    if finite.is_empty() {
+        return (0., 1.);
+    }
+    finite.sort_by(f64::total_cmp);
+    let last = finite.len() - 1;
+    let index = |percent: f64| ((percent.clamp(0., 100.) / 100.) * last as f64).round() as usize;
+    (finite[index(clip)], finite[index(100. - clip)])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ndarray::{ArrayD, IxDyn};
+
+    #[test]
+    fn clip_percentiles() {
+        let values: Vec<f64> = (0..=100).map(f64::from).chain([f64::NAN]).collect();
+        assert_eq!(percentile_range(&values, 0.), (0., 100.));
+        assert_eq!(percentile_range(&values, 5.), (5., 95.));
+    }
+
+    #[test]
+    fn rectangular_complex_image() {
+        let data = PortData::ArrayComplex(ArrayD::from_shape_fn(IxDyn(&[2, 3]), |i| {
+            Complex64::new(i[0] as f64, i[1] as f64)
+        }));
+        for complex_mode in ComplexMode::iter() {
+            let display = ImageDisplay {
+                complex_mode,
+                colormap: Colormap::Viridis,
+                ..Default::default()
+            };
+            let image = display.image(&data).unwrap();
+            assert_eq!(image.shape, (2, 3));
+            assert_eq!(image.pixels.len(), 2 * 3 * 4);
+        }
+
+        let display = ImageDisplay {
+            complex_mode: ComplexMode::Imaginary,
+            windowing: Windowing::WindowLevel {
+                window: 2.,
+                level: 1.,
+            },
+            ..Default::default()
+        };
+        let image = display.image(&data).unwrap();
+        assert_eq!(image.range, (0., 2.));
+        // Imaginary part of the last column is 2, at the top of the window
+        assert_eq!(image.pixels[8..12], [255, 255, 255, 255]);
+
+        let cube = PortData::ArrayReal(ArrayD::zeros(IxDyn(&[2, 2, 2])));
+        assert!(display.image(&cube).is_err());
     }
 }
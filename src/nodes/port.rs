@@ -3,9 +3,10 @@ use itertools::Itertools;
 use ndarray::{ArrayD, ArrayView, AsArray};
 use numpy::Complex64;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use strum::{EnumDiscriminants, EnumString, VariantNames};
 
-use crate::StableMap;
+use crate::{nodes::status::NodeError, StableMap};
 
 #[derive(
     Clone, Display, Debug, EnumString, VariantNames, PartialEq, Serialize, Deserialize, PartialOrd,
@@ -20,6 +21,98 @@ pub enum PortType {
     Dynamic,
     #[display("{_0:?}")]
     Object(StableMap<String, PortType>),
+    /// An array type constrained to a shape, written like `ArrayReal[N,3]`
+    #[strum(disabled)]
+    #[display("{_0}{_1}")]
+    Shaped(Box<PortType>, ArrayShape),
+}
+
+/// Length of one array axis in a declared shape
+#[derive(Clone, Display, Debug, PartialEq, Serialize, Deserialize, PartialOrd)]
+pub enum Dim {
+    #[display("{_0}")]
+    Fixed(usize),
+    /// Any length, shared by every axis with the same name. `_` is never shared
+    #[display("{_0}")]
+    Named(String),
+}
+
+/// Declared shape of an array port.
+/// `[2]` only fixes the number of dimensions, `[N,3]` fixes each axis, `[3,]` is a 1D array of length 3
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, PartialOrd)]
+pub enum ArrayShape {
+    Rank(usize),
+    Dims(Vec<Dim>),
+}
+
+impl std::fmt::Display for ArrayShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArrayShape::Rank(rank) => write!(f, "[{rank}]"),
+            ArrayShape::Dims(dims) if dims.len() == 1 => write!(f, "[{},]", dims[0]),
+            ArrayShape::Dims(dims) => write!(f, "[{}]", dims.iter().join(",")),
+        }
+    }
+}
+
+impl ArrayShape {
+    /// Parse the inside of the brackets, e.g. `N,3`
+    fn parse(dims: &str) -> Result<Self, String> {
+        let dims = dims.trim();
+        if let Ok(rank) = dims.parse() {
+            return Ok(ArrayShape::Rank(rank));
+        }
+        let dims = dims.strip_suffix(',').unwrap_or(dims);
+        dims.split(',')
+            .map(|dim| {
+                let dim = dim.trim();
+                if let Ok(length) = dim.parse() {
+                    Ok(Dim::Fixed(length))
+                } else if !dim.is_empty() && dim.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    Ok(Dim::Named(dim.to_string()))
+                } else {
+                    Err(format!("Invalid dimension '{dim}'"))
+                }
+            })
+            .collect::<Result<_, _>>()
+            .map(ArrayShape::Dims)
+    }
+
+    pub fn rank(&self) -> usize {
+        match self {
+            ArrayShape::Rank(rank) => *rank,
+            ArrayShape::Dims(dims) => dims.len(),
+        }
+    }
+
+    /// Does an array of `shape` satisfy this declaration
+    pub fn matches(&self, shape: &[usize]) -> bool {
+        let ArrayShape::Dims(dims) = self else {
+            return shape.len() == self.rank();
+        };
+        let mut named: StableMap<&str, usize> = StableMap::default();
+        dims.len() == shape.len()
+            && dims.iter().zip(shape).all(|(dim, &length)| match dim {
+                Dim::Fixed(fixed) => *fixed == length,
+                Dim::Named(name) if name == "_" => true,
+                Dim::Named(name) => *named.entry(name).or_insert(length) == length,
+            })
+    }
+
+    /// Can an array declared with this shape satisfy `other`.
+    /// Named axes are only known at compute time, so only ranks and fixed lengths are compared
+    pub fn compatible(&self, other: &ArrayShape) -> bool {
+        match (self, other) {
+            (ArrayShape::Dims(a), ArrayShape::Dims(b)) => {
+                a.len() == b.len()
+                    && a.iter().zip(b).all(|dims| match dims {
+                        (Dim::Fixed(a), Dim::Fixed(b)) => a == b,
+                        _ => true,
+                    })
+            }
+            _ => self.rank() == other.rank(),
+        }
+    }
 }
 
 impl Default for PortType {
@@ -28,6 +121,90 @@ impl Default for PortType {
     }
 }
 
+impl PortType {
+    /// Parse a port type name, optionally followed by a shape, e.g. `ArrayComplex[2]`, `ArrayReal[N,3]`
+    pub fn parse(s: &str) -> Result<PortType, String> {
+        let s = s.trim();
+        let Some((base, dims)) = s.strip_suffix(']').and_then(|s| s.split_once('[')) else {
+            return PortType::from_str(s).map_err(|_| format!("Unknown port type '{s}'"));
+        };
+        let base = PortType::parse(base)?;
+        if !matches!(
+            base,
+            PortType::ArrayInteger
+                | PortType::ArrayReal
+                | PortType::ArrayComplex
+                | PortType::Dynamic
+        ) {
+            return Err(format!("{base} can't have a shape, only array types can"));
+        }
+        Ok(PortType::Shaped(Box::new(base), ArrayShape::parse(dims)?))
+    }
+
+    /// The type without any shape constraint
+    pub fn base(&self) -> &PortType {
+        match self {
+            PortType::Shaped(base, _) => base,
+            _ => self,
+        }
+    }
+
+    /// Number of dimensions, if it is known
+    pub fn rank(&self) -> Option<usize> {
+        match self {
+            PortType::Integer | PortType::Real | PortType::Complex => Some(0),
+            PortType::Shaped(_, shape) => Some(shape.rank()),
+            _ => None,
+        }
+    }
+
+    /// Check that computed `data` for port `name` has the declared shape
+    pub fn validate(&self, name: &str, data: &PortData) -> Result<(), NodeError> {
+        match (self, data) {
+            (PortType::Shaped(_, shape), data) => match data.shape() {
+                Some(found) if shape.matches(found) => Ok(()),
+                Some(found) => Err(NodeError::Output(format!(
+                    "Output '{name}' should have shape {shape}, found {found:?}"
+                ))),
+                None => Err(NodeError::Output(format!(
+                    "Output '{name}' should be an array of shape {shape}"
+                ))),
+            },
+            (PortType::Object(types), PortData::Object(fields)) => {
+                types.iter().try_for_each(|(field, port_type)| {
+                    fields.get(field).map_or(Ok(()), |data| {
+                        port_type.validate(&format!("{name}.{field}"), data)
+                    })
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Can an output of this type be wired to an input of type `input`.
+    /// Scalars and arrays of the same element type are interchangeable, as nodes broadcast
+    pub fn connects_to(&self, input: &PortType) -> bool {
+        use PortType::*;
+        match (self, input) {
+            (Shaped(output, output_shape), Shaped(input, input_shape)) => {
+                output.connects_to(input) && output_shape.compatible(input_shape)
+            }
+            (Shaped(output, _), input) => output.connects_to(input),
+            (output, Shaped(input, _)) => output.connects_to(input),
+            (Dynamic, _) | (_, Dynamic) => true,
+            (Integer | ArrayInteger, Integer | ArrayInteger)
+            | (Real | ArrayReal, Real | ArrayReal)
+            | (Complex | ArrayComplex, Complex | ArrayComplex) => true,
+            (Object(output), Object(input)) => input.iter().all(|(name, input_type)| {
+                output
+                    .get(name)
+                    .is_some_and(|output_type| output_type.connects_to(input_type))
+            }),
+            _ => false,
+        }
+    }
+}
+
 //PERF: consider ArcArray
 #[derive(Clone, Debug, EnumDiscriminants)]
 pub enum PortData {
@@ -41,6 +218,18 @@ pub enum PortData {
     Object(StableMap<String, PortData>),
 }
 
+impl PortData {
+    /// Shape of array data, `None` for scalars and objects
+    pub fn shape(&self) -> Option<&[usize]> {
+        match self {
+            PortData::ArrayInteger(a) => Some(a.shape()),
+            PortData::ArrayReal(a) | PortData::Dynamic(a) => Some(a.shape()),
+            PortData::ArrayComplex(a) => Some(a.shape()),
+            _ => None,
+        }
+    }
+}
+
 fn write_nd_array<'a, A, T, D>(data: T) -> String
 where
     T: AsArray<'a, A, D>,
@@ -77,3 +266,63 @@ impl std::fmt::Display for PortData {
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ndarray::Array;
+
+    #[test]
+    fn parse_shapes() {
+        assert_eq!(PortType::parse("Real"), Ok(PortType::Real));
+        let complex_2d = PortType::parse("ArrayComplex[2]").unwrap();
+        assert_eq!(complex_2d.rank(), Some(2));
+        assert_eq!(complex_2d.base(), &PortType::ArrayComplex);
+        let vectors = PortType::parse("ArrayReal[N, 3]").unwrap();
+        assert_eq!(vectors.to_string(), "ArrayReal[N,3]");
+        assert_eq!(PortType::parse("ArrayReal[3,]").unwrap().rank(), Some(1));
+        assert!(PortType::parse("Real[2]").is_err());
+        assert!(PortType::parse("ArrayReal[N,-1]").is_err());
+
+        // Round trips through the network file
+        let ron = ron::to_string(&vectors).unwrap();
+        assert_eq!(ron::from_str::<PortType>(&ron).unwrap(), vectors);
+    }
+
+    #[test]
+    fn validate_shapes() {
+        let vectors = PortType::parse("ArrayReal[N,3]").unwrap();
+        let data = |shape: &[usize]| PortData::ArrayReal(Array::zeros(shape).into_dyn());
+        assert!(vectors.validate("out", &data(&[10, 3])).is_ok());
+        assert_eq!(
+            vectors.validate("out", &data(&[4, 4])),
+            Err(NodeError::Output(
+                "Output 'out' should have shape [N,3], found [4, 4]".to_string()
+            ))
+        );
+        assert!(vectors.validate("out", &PortData::Real(1.)).is_err());
+
+        let square = PortType::parse("ArrayReal[N,N]").unwrap();
+        assert!(square.validate("out", &data(&[4, 4])).is_ok());
+        assert!(square.validate("out", &data(&[4, 5])).is_err());
+        let image = PortType::parse("ArrayReal[2]").unwrap();
+        assert!(image.validate("out", &data(&[4, 5])).is_ok());
+        assert!(image.validate("out", &data(&[4, 5, 1])).is_err());
+
+        let object = PortType::Object([("a".to_string(), vectors)].into());
+        let fields = PortData::Object([("a".to_string(), data(&[3]))].into());
+        assert!(object.validate("out", &fields).is_err());
+    }
+
+    #[test]
+    fn connect_shapes() {
+        let parse = |s| PortType::parse(s).unwrap();
+        assert!(parse("ArrayReal[N,3]").connects_to(&PortType::ArrayReal));
+        assert!(PortType::ArrayReal.connects_to(&parse("ArrayReal[N,3]")));
+        assert!(parse("ArrayReal[N,3]").connects_to(&parse("ArrayReal[2]")));
+        assert!(parse("ArrayReal[10,M]").connects_to(&parse("ArrayReal[N,3]")));
+        assert!(!parse("ArrayReal[N,2]").connects_to(&parse("ArrayReal[N,3]")));
+        assert!(!parse("ArrayReal[3]").connects_to(&parse("ArrayReal[2]")));
+        assert!(!parse("ArrayComplex[2]").connects_to(&parse("ArrayReal[2]")));
+    }
+}
@@ -0,0 +1,403 @@
+use crate::app::Message;
+use crate::gui_node::PortDataReference;
+use crate::interface::numeric_input::styled_text_input;
+use crate::nodes::{NodeError, NodeTemplate, RustNode};
+use crate::StableMap;
+use iced::widget::{horizontal_space, row, text, text_input};
+use iced::Alignment::Center;
+use iced::Element;
+use ndarray::{ArrayD, ArrayView1, Axis};
+use numpy::{Complex64, IxDyn};
+use serde::{Deserialize, Serialize};
+
+use super::port::{PortData, PortType};
+
+/// Axis aware reductions.
+/// Reducing over all axes produces a scalar, otherwise the selected axis is removed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reduction {
+    Sum,
+    Mean,
+    Max,
+    Min,
+    Std,
+    /// Root sum of squares, commonly used to combine coil images
+    RootSumOfSquares,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, PartialOrd)]
+pub struct ReductionConfig {
+    /// Axis to reduce over, `None` reduces over every axis
+    pub axis: Option<usize>,
+}
+
+impl Reduction {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Reduction::Sum => "Σ",
+            Reduction::Mean => "μ",
+            Reduction::Max => "max",
+            Reduction::Min => "min",
+            Reduction::Std => "σ",
+            Reduction::RootSumOfSquares => "√Σ|α|²",
+        }
+    }
+
+    fn wrap(&self, config: ReductionConfig) -> RustNode {
+        match self {
+            Reduction::Sum => RustNode::Sum(config),
+            Reduction::Mean => RustNode::Mean(config),
+            Reduction::Max => RustNode::Max(config),
+            Reduction::Min => RustNode::Min(config),
+            Reduction::Std => RustNode::Std(config),
+            Reduction::RootSumOfSquares => RustNode::RootSumOfSquares(config),
+        }
+    }
+
+    /// Sum and Mean preserve complex values, every other reduction is real valued
+    fn preserves_complex(&self) -> bool {
+        matches!(self, Reduction::Sum | Reduction::Mean)
+    }
+
+    /// Declared type of the output port.
+    /// Sum and mean follow their input, which may be real or complex
+    pub fn output_type(&self, config: &ReductionConfig) -> PortType {
+        match (self.preserves_complex(), config.axis) {
+            (true, _) => PortType::Dynamic,
+            (false, None) => PortType::Real,
+            (false, Some(_)) => PortType::ArrayReal,
+        }
+    }
+
+    fn reduce_real(&self, lane: ArrayView1<f64>) -> f64 {
+        let n = lane.len() as f64;
+        match self {
+            Reduction::Sum => lane.sum(),
+            Reduction::Mean => lane.sum() / n,
+            Reduction::Max => lane.fold(f64::NEG_INFINITY, |a, &b| a.max(b)),
+            Reduction::Min => lane.fold(f64::INFINITY, |a, &b| a.min(b)),
+            Reduction::Std => {
+                let mean = lane.sum() / n;
+                (lane.fold(0.0, |acc, v| acc + (v - mean).powi(2)) / n).sqrt()
+            }
+            Reduction::RootSumOfSquares => lane.fold(0.0, |acc, v| acc + v * v).sqrt(),
+        }
+    }
+
+    /// Max and Min of complex values compare magnitudes
+    fn reduce_complex(&self, lane: ArrayView1<Complex64>) -> Complex64 {
+        let n = lane.len() as f64;
+        match self {
+            Reduction::Sum => lane.sum(),
+            Reduction::Mean => lane.sum() / n,
+            Reduction::Max => lane.fold(f64::NEG_INFINITY, |a, b| a.max(b.norm())).into(),
+            Reduction::Min => lane.fold(f64::INFINITY, |a, b| a.min(b.norm())).into(),
+            Reduction::Std => {
+                let mean = lane.sum() / n;
+                (lane.fold(0.0, |acc, v| acc + (v - mean).norm_sqr()) / n)
+                    .sqrt()
+                    .into()
+            }
+            Reduction::RootSumOfSquares => {
+                lane.fold(0.0, |acc, v| acc + v.norm_sqr()).sqrt().into()
+            }
+        }
+    }
+
+    pub fn compute(
+        &self,
+        config: &ReductionConfig,
+        inputs: StableMap<String, PortDataReference>,
+    ) -> Result<StableMap<String, PortData>, NodeError> {
+        let a = inputs.get("a").ok_or(NodeError::input_error("a"))?;
+
+        // Scalars are reduced as single element arrays
+        let scalar = |v| ArrayD::from_elem(IxDyn(&[1]), v);
+        let all = ReductionConfig::default();
+
+        let out = match &**a {
+            PortData::Real(v) => self.reduce_real_array(&scalar(*v), &all)?,
+            PortData::Integer(v) => self.reduce_real_array(&scalar(*v as f64), &all)?,
+            PortData::Complex(v) => {
+                self.reduce_complex_array(&ArrayD::from_elem(IxDyn(&[1]), *v), &all)?
+            }
+            PortData::ArrayReal(a) | PortData::Dynamic(a) => self.reduce_real_array(a, config)?,
+            PortData::ArrayInteger(a) => self.reduce_real_array(&a.mapv(|v| v as f64), config)?,
+            PortData::ArrayComplex(a) => self.reduce_complex_array(a, config)?,
+            PortData::Object(_) => {
+                return Err(NodeError::Input(
+                    "Reductions are not supported for Object inputs".to_string(),
+                ))
+            }
+        };
+
+        Ok([("out".into(), out)].into())
+    }
+
+    fn reduce_real_array(
+        &self,
+        a: &ArrayD<f64>,
+        config: &ReductionConfig,
+    ) -> Result<PortData, NodeError> {
+        Ok(match config.axis {
+            None => PortData::Real(self.reduce_real(flatten(a).view())),
+            Some(axis) => PortData::ArrayReal(
+                a.map_axis(checked_axis(axis, a.ndim())?, |lane| self.reduce_real(lane)),
+            ),
+        })
+    }
+
+    fn reduce_complex_array(
+        &self,
+        a: &ArrayD<Complex64>,
+        config: &ReductionConfig,
+    ) -> Result<PortData, NodeError> {
+        Ok(match config.axis {
+            None => {
+                let v = self.reduce_complex(flatten(a).view());
+                match self.preserves_complex() {
+                    true => PortData::Complex(v),
+                    false => PortData::Real(v.re),
+                }
+            }
+            Some(axis) => {
+                let out = a.map_axis(checked_axis(axis, a.ndim())?, |lane| {
+                    self.reduce_complex(lane)
+                });
+                match self.preserves_complex() {
+                    true => PortData::ArrayComplex(out),
+                    false => PortData::ArrayReal(out.mapv(|v| v.re)),
+                }
+            }
+        })
+    }
+
+    pub fn config_view<'a>(&self, id: u32, config: ReductionConfig) -> Element<'a, Message> {
+        let reduction = *self;
+        row![
+            text("axis:"),
+            horizontal_space(),
+            styled_text_input(
+                text_input(
+                    "all",
+                    &config.axis.map(|a| a.to_string()).unwrap_or_default()
+                )
+                .on_input(move |value| {
+                    Message::UpdateNodeTemplate(
+                        id,
+                        NodeTemplate::RustNode(reduction.wrap(ReductionConfig {
+                            axis: value.parse().ok(),
+                        })),
+                    )
+                })
+            ),
+        ]
+        .align_y(Center)
+        .spacing(4.)
+        .into()
+    }
+}
+
+/// Histogram with evenly spaced bins. The `x`/`y` outputs can be wired directly into a `Plot` node
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, PartialOrd)]
+pub struct HistogramConfig {
+    pub bins: usize,
+}
+
+impl Default for HistogramConfig {
+    fn default() -> Self {
+        Self { bins: 32 }
+    }
+}
+
+impl HistogramConfig {
+    /// Produces bin centers as `x` and counts as `y`.
+    /// Complex values are binned by magnitude, non-finite values are ignored
+    pub fn compute(
+        &self,
+        inputs: StableMap<String, PortDataReference>,
+    ) -> Result<StableMap<String, PortData>, NodeError> {
+        let a = inputs.get("a").ok_or(NodeError::input_error("a"))?;
+        let values: Vec<f64> = match &**a {
+            PortData::Real(v) => vec![*v],
+            PortData::Integer(v) => vec![*v as f64],
+            PortData::Complex(v) => vec![v.norm()],
+            PortData::ArrayReal(a) | PortData::Dynamic(a) => a.iter().copied().collect(),
+            PortData::ArrayInteger(a) => a.iter().map(|v| *v as f64).collect(),
+            PortData::ArrayComplex(a) => a.iter().map(|v| v.norm()).collect(),
+            PortData::Object(_) => {
+                return Err(NodeError::Input(
+                    "Histograms are not supported for Object inputs".to_string(),
+                ))
+            }
+        };
+        if self.bins == 0 {
+            return Err(NodeError::Config(
+                "Histogram needs at least 1 bin".to_string(),
+            ));
+        }
+
+        let (centers, counts) = histogram(values.into_iter().filter(|v| v.is_finite()), self.bins);
+        Ok([
+            (
+                "x".into(),
+                PortData::ArrayReal(
+                    ArrayD::from_shape_vec(IxDyn(&[self.bins]), centers)
+                        .expect("1D arrray shape should always work"),
+                ),
+            ),
+            (
+                "y".into(),
+                PortData::ArrayReal(
+                    ArrayD::from_shape_vec(IxDyn(&[self.bins]), counts)
+                        .expect("1D arrray shape should always work"),
+                ),
+            ),
+        ]
+        .into())
+    }
+
+    pub fn config_view<'a>(&self, id: u32) -> Element<'a, Message> {
+        row![
+            text("bins:"),
+            horizontal_space(),
+            styled_text_input(
+                text_input("32", &self.bins.to_string()).on_input(move |value| {
+                    Message::UpdateNodeTemplate(
+                        id,
+                        NodeTemplate::RustNode(RustNode::Histogram(HistogramConfig {
+                            bins: value.parse().unwrap_or(1).max(1),
+                        })),
+                    )
+                })
+            ),
+        ]
+        .align_y(Center)
+        .spacing(4.)
+        .into()
+    }
+}
+
+/// Bin values into `bins` evenly spaced bins spanning the range of the values.
+/// returns (bin centers, counts)
+fn histogram(values: impl Iterator<Item = f64> + Clone, bins: usize) -> (Vec<f64>, Vec<f64>) {
+    let (min, max) = values
+        .clone()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+            (min.min(v), max.max(v))
+        });
+    // Handle empty and constant data by giving the bins a unit width around the value
+    let (min, max) = match (min.is_finite(), min == max) {
+        (false, _) => (0., 1.),
+        (true, true) => (min - 0.5, max + 0.5),
+        (true, false) => (min, max),
+    };
+    let width = (max - min) / bins as f64;
+
+    let mut counts = vec![0.0; bins];
+    values.for_each(|v| {
+        let i = (((v - min) / width) as usize).min(bins - 1);
+        counts[i] += 1.0;
+    });
+    let centers = (0..bins).map(|i| min + (i as f64 + 0.5) * width).collect();
+    (centers, counts)
+}
+
+fn flatten<T: Clone>(a: &ArrayD<T>) -> ndarray::Array1<T> {
+    a.iter().cloned().collect()
+}
+
+fn checked_axis(axis: usize, ndim: usize) -> Result<Axis, NodeError> {
+    if axis < ndim {
+        Ok(Axis(axis))
+    } else {
+        Err(NodeError::Config(format!(
+            "axis {axis} is out of range for an array with {ndim} dimensions"
+        )))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn reduce_axis() {
+        let a = array![[1., 2., 3.], [4., 5., 6.]].into_dyn();
+        let sum = Reduction::Sum
+            .reduce_real_array(&a, &ReductionConfig { axis: Some(0) })
+            .unwrap();
+        let PortData::ArrayReal(sum) = sum else {
+            panic!("expected array output")
+        };
+        assert_eq!(sum, array![5., 7., 9.].into_dyn());
+
+        let max = Reduction::Max
+            .reduce_real_array(&a, &ReductionConfig { axis: Some(1) })
+            .unwrap();
+        let PortData::ArrayReal(max) = max else {
+            panic!("expected array output")
+        };
+        assert_eq!(max, array![3., 6.].into_dyn());
+
+        assert!(Reduction::Sum
+            .reduce_real_array(&a, &ReductionConfig { axis: Some(2) })
+            .is_err());
+    }
+
+    #[test]
+    fn reduce_all() {
+        let a = array![[1., 2.], [3., 4.]].into_dyn();
+        let PortData::Real(mean) = Reduction::Mean
+            .reduce_real_array(&a, &ReductionConfig::default())
+            .unwrap()
+        else {
+            panic!("expected scalar output")
+        };
+        assert_eq!(mean, 2.5);
+    }
+
+    #[test]
+    fn root_sum_of_squares() {
+        let coils = array![[Complex64::new(3., 0.)], [Complex64::new(0., 4.)]].into_dyn();
+        let PortData::ArrayReal(rss) = Reduction::RootSumOfSquares
+            .reduce_complex_array(&coils, &ReductionConfig { axis: Some(0) })
+            .unwrap()
+        else {
+            panic!("expected real array output")
+        };
+        assert_eq!(rss, array![5.].into_dyn());
+        let all = ReductionConfig::default();
+        assert_eq!(
+            Reduction::RootSumOfSquares.output_type(&all),
+            PortType::Real
+        );
+
+        // Sum keeps complex data complex, so its output is declared to follow the input
+        let sum = Reduction::Sum.reduce_complex_array(&coils, &all).unwrap();
+        assert!(matches!(sum, PortData::Complex(_)));
+        assert_eq!(Reduction::Sum.output_type(&all), PortType::Dynamic);
+    }
+
+    #[test]
+    fn histogram_counts() {
+        let (centers, counts) = histogram([0., 0.5, 1., 1., 2.].into_iter(), 2);
+        assert_eq!(centers, vec![0.5, 1.5]);
+        assert_eq!(counts, vec![2., 3.]);
+
+        let (_, counts) = histogram([3., 3.].into_iter(), 4);
+        assert_eq!(counts.iter().sum::<f64>(), 2.);
+    }
+    #[test]
+    fn unconnected_reduction_waits_for_input() {
+        use crate::graph::Graph;
+        use crate::nodes::{status::NodeStatus, NodeData};
+
+        let node: NodeData = NodeTemplate::RustNode(RustNode::Sum(Default::default())).into();
+        let (_, result) = Graph::compute_node(0, node, [].into());
+        let (outputs, node) = result.unwrap();
+        assert!(outputs.is_empty());
+        assert_eq!(node.status, NodeStatus::Waiting);
+    }
+}
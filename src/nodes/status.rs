@@ -11,22 +11,26 @@ pub enum NodeStatus {
     Idle,
     #[display("Running")]
     Running(Instant),
+    /// The node has inputs, but none of them are connected
+    #[display("Waiting for input")]
+    Waiting,
     Error(NodeError),
 }
 
 impl NodeStatus {
-    pub fn icon(&self) -> Text {
+    pub fn icon(&self) -> Text<'_> {
         match self {
-            NodeStatus::Idle => icon(""),
+            NodeStatus::Idle | NodeStatus::Waiting => icon(""),
             NodeStatus::Running(_) => icon(""), //icon(""),
             NodeStatus::Error(_) => icon("").style(text::danger),
         }
     }
 
-    pub fn text_element(&self) -> Text {
+    pub fn text_element(&self) -> Text<'_> {
         match self {
             NodeStatus::Idle => text(""),
             NodeStatus::Running(_) => text(""),
+            NodeStatus::Waiting => text("waiting for input").style(text::secondary),
             NodeStatus::Error(err) => text(err.to_string()).style(text::danger),
         }
     }
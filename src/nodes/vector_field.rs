@@ -3,24 +3,23 @@ use std::f32::consts::PI;
 use super::{PortData, RustNode};
 use crate::app::Message;
 use crate::gui_node::PortDataContainer;
-use crate::interface::node::{INNER_NODE_WIDTH, NODE_BORDER_WIDTH};
+use crate::interface::node::NODE_BORDER_WIDTH;
 use crate::math::{linspace_delta, Vector};
+use crate::nodes::figure::{Figure, FigureStyle, Mark};
+use crate::nodes::status::NodeError;
 use crate::nodes::NodeTemplate;
 use crate::StableMap;
 use colorgrad::Gradient;
 use glam::{Mat3, Vec3};
 use iced::mouse;
-use iced::widget::canvas::path::Builder;
-use iced::widget::canvas::{Path, Stroke};
 use iced::widget::{container, horizontal_space, row, text, text_input};
 use iced::Alignment::Center;
 use iced::{
     widget::{canvas, column},
     Element,
 };
-use iced::{Rectangle, Renderer, Theme};
-use itertools::Itertools;
-use ndarray::Array3;
+use iced::{Length::Fill, Point, Rectangle, Renderer, Size, Theme};
+use ndarray::{Array3, Axis};
 use serde::{Deserialize, Serialize};
 
 // Rectanlge specified by center position, width and height
@@ -68,51 +67,104 @@ impl VectorField {
         _id: u32,
         input_data: StableMap<String, PortDataContainer>,
     ) -> Element<'a, Message> {
-        match input_data.get("a") {
-            Some(port) => {
-                let data = match (**port).read().unwrap().clone() {
-                    PortData::ArrayComplex(a) => Array3::<f64>::from_shape_vec(
-                        [
-                            (a.len() as f32).sqrt() as usize,
-                            (a.len() as f32).sqrt() as usize,
-                            3,
-                        ],
-                        a.iter()
-                            .flat_map(|v| {
-                                let (r, theta) = v.to_polar();
-                                [theta.sin(), theta.cos(), r]
-                            })
-                            .collect::<Vec<_>>(),
-                    )
-                    .expect("square matrix"),
-                    PortData::ArrayReal(a) => {
-                        let xy_len = (a.len() as f32 / 3.0).sqrt() as usize;
-                        a.into_shape_with_order([xy_len, xy_len, 3])
-                            .unwrap_or_else(|e| panic!("Unexpected shape{e}"))
-                    }
-                    _ => panic!("unsuported plot types {:?}", port),
-                };
-                container(
-                    canvas(VectorFieldCanvas {
-                        data,
-                        config: *self,
-                    })
-                    .width(INNER_NODE_WIDTH * 2.)
-                    .height(INNER_NODE_WIDTH * 2.),
-                )
-                .padding(NODE_BORDER_WIDTH)
-                .into()
-            }
-            None => container(
-                container(text("n/a"))
-                    .width(INNER_NODE_WIDTH * 2.)
-                    .height(INNER_NODE_WIDTH * 2.),
+        let data = input_data.get("a").map(|port| field(&port.read().unwrap()));
+        match data {
+            Some(Ok(data)) => container(
+                canvas(VectorFieldCanvas {
+                    data,
+                    config: *self,
+                })
+                .width(Fill)
+                .height(Fill),
             )
             .padding(NODE_BORDER_WIDTH)
             .into(),
+            Some(Err(e)) => container(container(text(e.to_string())).center(Fill))
+                .padding(NODE_BORDER_WIDTH)
+                .into(),
+            None => container(container(text("n/a")).center(Fill))
+                .padding(NODE_BORDER_WIDTH)
+                .into(),
         }
     }
 
+    /// The field at `size`, failing if there is no field to draw
+    pub fn figure(
+        &self,
+        input_data: &StableMap<String, PortDataContainer>,
+        size: Size,
+        style: FigureStyle,
+    ) -> Result<Figure, NodeError> {
+        let port = input_data
+            .get("a")
+            .ok_or(NodeError::Input("No vector field on input a".to_string()))?;
+        let data = field(&port.read().unwrap())?;
+        Ok(self.field_figure(&data, size, style))
+    }
+
+    /// Maps plot space, centered on `rect` with y up, to the pixels of a figure
+    fn screen_point(&self, size: Size, (x, y): (f32, f32)) -> Point {
+        let rect = self.rect;
+        Point::new(
+            size.width / 2. + (x - rect.center.x) * size.width / rect.width,
+            size.height / 2. - (y - rect.center.y) * size.height / rect.height,
+        )
+    }
+
+    /// Grid and an arrow for each vector of the field
+    fn field_figure(&self, data: &Array3<f64>, size: Size, style: FigureStyle) -> Figure {
+        let mut figure = Figure::new(size, style);
+        let aspect = size.width / size.height;
+        let line = |(from, to): ((f32, f32), (f32, f32)), width: f32| Mark::Line {
+            points: vec![self.screen_point(size, from), self.screen_point(size, to)],
+            color: style.grid,
+            width,
+        };
+
+        //// Grid
+        let max_length = (self.rect.width + self.rect.center.x.abs())
+            .max(self.rect.height + self.rect.center.y.abs());
+        for (tick_length, spacing, width) in [
+            (max_length, max_length, 0.8),
+            (0.05, 10., 0.4),
+            (0.025, 1., 0.4),
+        ] {
+            for segment in grid_lines(self.rect, tick_length, aspect, spacing) {
+                figure.push(line(segment, width));
+            }
+        }
+
+        //// Arrows
+        let vec_scale = 1.5;
+        let arrow_angle = PI / 8.0;
+        for ((x, y), v) in data.lanes(Axis(2)).into_iter().enumerate().map(|(i, v)| {
+            let position = (i / data.shape()[1], i % data.shape()[1]);
+            (position, Vec3::new(v[0] as f32, v[1] as f32, v[2] as f32))
+        }) {
+            let vz = v.z;
+            let v = v * vec_scale;
+            let arrow_left = Mat3::from_rotation_z(arrow_angle) * (v * 0.8);
+            let arrow_right = Mat3::from_rotation_z(-arrow_angle) * (v * 0.8);
+
+            let v_tail = Vec3::from([x as f32 - 5.0, y as f32 - 5.0, 0.0]);
+            let point = |p: Vec3| self.screen_point(size, (p.x, p.y));
+            let color = colorgrad::preset::spectral().at((vz + 1.0) / 2.0);
+            let color = iced::Color::from_rgb(color.r, color.g, color.b);
+            //TODO: change line stroke based on z
+            figure.push(Mark::Line {
+                points: vec![point(v_tail), point(v_tail + v), point(v_tail + arrow_left)],
+                color,
+                width: 1.,
+            });
+            figure.push(Mark::Line {
+                points: vec![point(v_tail + v), point(v_tail + arrow_right)],
+                color,
+                width: 1.,
+            });
+        }
+        figure
+    }
+
     pub fn config_view(
         &self,
         id: u32,
@@ -193,130 +245,22 @@ impl<Message> canvas::Program<Message> for VectorFieldCanvas {
         bounds: Rectangle,
         _cursor: mouse::Cursor,
     ) -> Vec<canvas::Geometry> {
-        // We prepare a new `Frame`
         let mut frame = canvas::Frame::new(renderer, bounds.size());
-
-        let node_width = bounds.width;
-        let node_height = bounds.height;
-        let aspect = node_width / node_height;
-
-        frame.push_transform();
-        //TODO: figure out how ot rephrase this in math, so I can actually
-        //predict/undestand how to switch between plot space and screen space
-        //// center canvas on the origin, y up
-        frame.translate([frame.center().x, frame.center().y].into());
-        frame.scale_nonuniform([1., -1.]);
-
-        frame.push_transform();
-
-        let scale = Vector::new(
-            node_width / self.config.rect.width,
-            node_height / self.config.rect.height,
-        );
-        // Scale for the conifgured height/width
-        frame.scale_nonuniform(scale);
-
-        // Move the center point to the center of our canvas
-        frame.translate((-self.config.rect.center).into());
-
-        // The frame is now centered on center, and goes from:
-        // rect.left   -> rect.right
-        // rect.bottom -> rect.top
-
-        //// Grid
-        {
-            let color = theme.extended_palette().secondary.strong.color;
-            let main_grid_stroke = Stroke::default()
-                .with_width(0.8)
-                .with_color(color.scale_alpha(0.5));
-
-            let secondary_grid_stroke = main_grid_stroke.with_width(0.4);
-
-            let tertiary_grid_strok = secondary_grid_stroke;
-
-            let max_length = (self.config.rect.width + self.config.rect.center.x.abs())
-                .max(self.config.rect.height + self.config.rect.center.y.abs());
-
-            grid_path(self.config.rect, max_length, aspect, max_length)
-                .into_iter()
-                .for_each(|p| frame.stroke(&p, main_grid_stroke));
-
-            {
-                grid_path(self.config.rect, 0.05, aspect, 10.)
-                    .into_iter()
-                    .for_each(|p| frame.stroke(&p, secondary_grid_stroke));
-
-                grid_path(self.config.rect, 0.025, aspect, 1.)
-                    .into_iter()
-                    .for_each(|p| frame.stroke(&p, tertiary_grid_strok));
-            }
-        }
-
-        let vec_scale = 1.5;
-
-        self.data
-            .indexed_iter()
-            // This looks nicer(don't need to do the chunk.collect() weirdness below) with
-            // the nightly iter_array_chunks feature
-            .chunks(3)
-            .into_iter()
-            .map(|chunk| {
-                let [((x, y, _), vx), ((_, _, _), vy), ((_, _, _), vz)] =
-                    chunk.collect::<Vec<_>>()[..]
-                else {
-                    panic!("array3 is in an unexpected format")
-                };
-
-                let v = Vec3::from([
-                    *vx as f32 * vec_scale,
-                    *vy as f32 * vec_scale,
-                    *vz as f32 * vec_scale,
-                ]);
-
-                let arrow_angle = PI / 8.0;
-                let arrow_left = Mat3::from_rotation_z(arrow_angle) * (v * 0.8);
-                let arrow_right = Mat3::from_rotation_z(-arrow_angle) * (v * 0.8);
-
-                let v_tail = Vec3::from([x as f32 - 5.0, y as f32 - 5.0, 0.0]);
-                let v_tip = v_tail + v;
-                let tip_left = v_tail + arrow_left;
-                let tip_right = v_tail + arrow_right;
-
-                let mut path = Builder::new();
-                path.move_to((v_tail[0], v_tail[1]).into());
-                path.line_to((v_tip[0], v_tip[1]).into());
-                path.line_to((tip_left[0], tip_left[1]).into());
-                path.move_to((v_tip[0], v_tip[1]).into());
-                path.line_to((tip_right[0], tip_right[1]).into());
-                let color = colorgrad::preset::spectral().at((*vz as f32 + 1.0) / 2.0);
-                (
-                    path.build(),
-                    //TODO: change line stroke based on z
-                    Stroke::default()
-                        .with_color(iced::Color {
-                            r: color.r,
-                            g: color.g,
-                            b: color.b,
-                            a: 1.0,
-                        })
-                        .with_line_join(canvas::LineJoin::Miter)
-                        .with_width(1.),
-                )
-            })
-            .for_each(|(path, stroke)| frame.stroke(&path, stroke));
-
-        frame.pop_transform();
-
+        self.config
+            .field_figure(&self.data, bounds.size(), FigureStyle::from_theme(theme))
+            .draw(&mut frame);
         vec![frame.into_geometry()]
     }
 }
 
-fn grid_path(
+/// Line segments along the axes through the origin, with ticks every `tick_spacing`,
+/// in plot space
+fn grid_lines(
     plot_rect: Rect,
     tick_length_node_space: f32,
     aspect: f32,
     tick_spacing: f32,
-) -> Vec<Path> {
+) -> Vec<((f32, f32), (f32, f32))> {
     let left = ((plot_rect.left() / tick_spacing).floor()) * tick_spacing;
     let right = ((plot_rect.right() / tick_spacing).ceil()) * tick_spacing;
     let bottom = ((plot_rect.bottom() / tick_spacing).floor()) * tick_spacing;
@@ -336,9 +280,9 @@ fn grid_path(
             if y.is_nan() {
                 panic!("Encountered nan!{:?}", (plot_rect, tick_spacing))
             }
-            Path::line(
-                (cx - (y_tick_length / 2.), y).into(),
-                (cx + (y_tick_length / 2.), y).into(),
+            (
+                (cx - (y_tick_length / 2.), y),
+                (cx + (y_tick_length / 2.), y),
             )
         });
 
@@ -349,11 +293,46 @@ fn grid_path(
                 panic!("Encountered nan!{:?}", (plot_rect, tick_spacing))
             }
 
-            Path::line(
-                (x, cy - (x_tick_length / 2.)).into(),
-                (x, cy + (x_tick_length / 2.)).into(),
+            (
+                (x, cy - (x_tick_length / 2.)),
+                (x, cy + (x_tick_length / 2.)),
             )
         });
 
     h_lines.chain(v_lines).collect()
 }
+
+/// A square grid of 3D vectors, from complex values as unit vectors in the plane
+/// scaled by their magnitude along z, or from real values as x, y, z triples
+fn field(data: &PortData) -> Result<Array3<f64>, NodeError> {
+    match data {
+        PortData::ArrayComplex(a) => {
+            let side = (a.len() as f32).sqrt() as usize;
+            Array3::<f64>::from_shape_vec(
+                [side, side, 3],
+                a.iter()
+                    .flat_map(|v| {
+                        let (r, theta) = v.to_polar();
+                        [theta.sin(), theta.cos(), r]
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .map_err(|_| NodeError::Input(format!("Expected a square array, got {:?}", a.shape())))
+        }
+        PortData::ArrayReal(a) => {
+            let xy_len = (a.len() as f32 / 3.0).sqrt() as usize;
+            a.clone()
+                .into_shape_with_order([xy_len, xy_len, 3])
+                .map_err(|_| {
+                    NodeError::Input(format!(
+                        "Expected a square grid of 3 vectors, got {:?}",
+                        a.shape()
+                    ))
+                })
+        }
+        other => Err(NodeError::Input(format!(
+            "Can't draw {:?} as a vector field",
+            crate::nodes::port::PortDataDiscriminants::from(other)
+        ))),
+    }
+}
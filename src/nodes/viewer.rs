@@ -0,0 +1,400 @@
+use super::{PortData, RustNode};
+use crate::app::Message;
+use crate::gui_node::{PortDataContainer, PortDataReference};
+use crate::interface::inspector::{dtype_shape_bytes, stats};
+use crate::interface::node::{default_node_size, NODE_BORDER_WIDTH};
+use crate::nodes::figure::{Figure, FigureStyle};
+use crate::nodes::plot::Plot;
+use crate::nodes::plot_complex::Plot2D;
+use crate::nodes::status::NodeError;
+use crate::nodes::vector_field::VectorField;
+use crate::nodes::volume::VolumeViewer;
+use crate::nodes::NodeTemplate;
+use crate::StableMap;
+use derive_more::Display;
+use iced::widget::{column, container, horizontal_space, pick_list, row, text};
+use iced::Alignment::Center;
+use iced::{Element, Length::Fill, Size};
+use serde::{Deserialize, Serialize};
+use strum::{EnumIter, IntoEnumIterator};
+
+/// The ways the `View` node can show its input
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Serialize, Deserialize, Display, EnumIter,
+)]
+pub enum ViewKind {
+    #[display("readout")]
+    Scalar,
+    #[display("line plot")]
+    Line,
+    #[display("image")]
+    Image,
+    #[display("vector field")]
+    VectorField,
+    #[display("volume")]
+    Volume,
+}
+
+impl ViewKind {
+    /// The best way to show `data`, from its type and shape
+    pub fn detect(data: &PortData) -> Self {
+        match shape(data) {
+            None => ViewKind::Scalar,
+            Some(shape) if shape.iter().product::<usize>() == 1 => ViewKind::Scalar,
+            Some(shape) => match shape.len() {
+                1 => ViewKind::Line,
+                2 => ViewKind::Image,
+                _ if ViewKind::VectorField.supports(data) => ViewKind::VectorField,
+                _ => ViewKind::Volume,
+            },
+        }
+    }
+
+    /// Can `data` be shown this way
+    pub fn supports(self, data: &PortData) -> bool {
+        let shape = shape(data);
+        match self {
+            ViewKind::Scalar => true,
+            ViewKind::Line => shape.is_some_and(|shape| shape.len() <= 1),
+            ViewKind::Image => shape.is_some_and(|shape| (1..=2).contains(&shape.len())),
+            ViewKind::Volume => shape.is_some_and(|shape| shape.len() >= 2),
+            // Vectors of (x, y, z) on a square grid
+            ViewKind::VectorField => match (data, shape.as_deref()) {
+                (PortData::ArrayReal(_), Some([rows, columns, 3])) => rows == columns,
+                _ => false,
+            },
+        }
+    }
+}
+
+/// Shape of array data, `None` for scalars and objects
+fn shape(data: &PortData) -> Option<Vec<usize>> {
+    match data {
+        PortData::ArrayInteger(a) => Some(a.shape().to_vec()),
+        PortData::ArrayReal(a) | PortData::Dynamic(a) => Some(a.shape().to_vec()),
+        PortData::ArrayComplex(a) => Some(a.shape().to_vec()),
+        _ => None,
+    }
+}
+
+/// Shows its input with whichever viewer suits the data, unless one is picked manually
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Clone)]
+pub struct View {
+    /// Overrides the detected kind of view
+    pub kind: Option<ViewKind>,
+    #[serde(skip)]
+    pub detected: Option<ViewKind>,
+    // Configuration of each viewer, kept when switching between them
+    #[serde(default)]
+    pub plot: Plot,
+    #[serde(default)]
+    pub plot_2d: Plot2D,
+    #[serde(default)]
+    pub vector_field: VectorField,
+    #[serde(default)]
+    pub volume: VolumeViewer,
+}
+
+/// Ordered by the chosen kind of view. Views that differ otherwise are unordered,
+/// so that the ordering agrees with `PartialEq`
+impl PartialOrd for View {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match self.kind.partial_cmp(&other.kind) {
+            Some(std::cmp::Ordering::Equal) if self != other => None,
+            ord => ord,
+        }
+    }
+}
+
+impl View {
+    /// The kind of view shown
+    pub fn kind(&self) -> ViewKind {
+        self.kind.or(self.detected).unwrap_or(ViewKind::Scalar)
+    }
+
+    pub(crate) fn compute(
+        &mut self,
+        inputs: StableMap<String, PortDataReference>,
+    ) -> Result<StableMap<String, PortData>, NodeError> {
+        let Some(data) = inputs.get("a") else {
+            self.detected = None;
+            return Ok([].into());
+        };
+        self.detected = Some(ViewKind::detect(data));
+        let kind = self.kind();
+        if !kind.supports(data) {
+            return Err(NodeError::Config(format!(
+                "Can't show {} as a {kind}",
+                describe(data)
+            )));
+        }
+        match kind {
+            ViewKind::Scalar | ViewKind::VectorField => {}
+            ViewKind::Line => {
+                // The line plot's `y` input
+                let inputs = inputs.into_values().map(|v| ("y".to_string(), v)).collect();
+                self.plot.compute(inputs)?;
+            }
+            ViewKind::Image => {
+                self.plot_2d.input_changed(inputs)?;
+            }
+            ViewKind::Volume => {
+                self.volume.input_changed(inputs)?;
+            }
+        }
+        Ok([].into())
+    }
+
+    /// Messages from the wrapped viewers update that viewer's configuration within this node
+    fn wrap(&self, message: Message) -> Message {
+        let Message::UpdateNodeTemplate(id, NodeTemplate::RustNode(node)) = message else {
+            return message;
+        };
+        let mut view = self.clone();
+        match node {
+            RustNode::Plot(plot) => view.plot = plot,
+            RustNode::Plot2D(plot_2d) => view.plot_2d = plot_2d,
+            RustNode::VectorField(vector_field) => view.vector_field = vector_field,
+            RustNode::VolumeViewer(volume) => view.volume = volume,
+            node => return Message::UpdateNodeTemplate(id, NodeTemplate::RustNode(node)),
+        }
+        Message::UpdateNodeTemplate(id, NodeTemplate::RustNode(RustNode::View(Box::new(view))))
+    }
+
+    /// The figure of the viewer that is shown
+    pub fn figure(
+        &self,
+        input_data: &StableMap<String, PortDataContainer>,
+        size: Size,
+        style: FigureStyle,
+    ) -> Result<Figure, NodeError> {
+        match self.kind() {
+            ViewKind::Scalar => Err(NodeError::Config(
+                "A readout can't be exported, only plots and images".to_string(),
+            )),
+            ViewKind::Line => {
+                let input_data = input_data
+                    .values()
+                    .map(|v| ("y".to_string(), v.clone()))
+                    .collect();
+                self.plot.figure(&input_data, size, style)
+            }
+            ViewKind::Image => self.plot_2d.figure(input_data, size, style),
+            ViewKind::VectorField => self.vector_field.figure(input_data, size, style),
+            ViewKind::Volume => self.volume.figure(input_data, size, style),
+        }
+    }
+
+    pub fn view<'a>(
+        &self,
+        id: u32,
+        input_data: StableMap<String, PortDataContainer>,
+    ) -> Element<'a, Message> {
+        let wrapper = self.clone();
+        let kind = self.kind();
+        let unsupported = input_data
+            .get("a")
+            .map(|data| data.read().unwrap())
+            .filter(|data| !kind.supports(data))
+            .map(|data| describe(&data));
+        if let Some(description) = unsupported {
+            return container(text(format!("Can't show {description} as a {kind}")))
+                .width(Fill)
+                .padding(NODE_BORDER_WIDTH)
+                .into();
+        }
+        let content: Element<'a, Message> = match kind {
+            ViewKind::Scalar => {
+                let readout = match input_data.get("a") {
+                    Some(data) => readout(&data.read().unwrap()),
+                    None => "n/a".to_string(),
+                };
+                container(text(readout).size(14))
+                    .center_x(Fill)
+                    .padding(NODE_BORDER_WIDTH)
+                    .into()
+            }
+            ViewKind::Line => {
+                let input_data = input_data
+                    .into_values()
+                    .map(|v| ("y".to_string(), v))
+                    .collect();
+                self.plot.view(id, input_data)
+            }
+            ViewKind::Image => self.plot_2d.view(id, input_data),
+            ViewKind::VectorField => self.vector_field.view(id, input_data),
+            ViewKind::Volume => self.volume.view(),
+        };
+        content.map(move |message| wrapper.wrap(message))
+    }
+
+    pub fn config_view(
+        &self,
+        id: u32,
+        input_data: StableMap<String, PortDataContainer>,
+    ) -> Option<Element<'_, Message>> {
+        let update = move |kind| {
+            Message::UpdateNodeTemplate(
+                id,
+                NodeTemplate::RustNode(RustNode::View(Box::new(View {
+                    kind,
+                    ..self.clone()
+                }))),
+            )
+        };
+        let automatic = match self.detected {
+            Some(kind) => format!("auto ({kind})"),
+            None => "auto".to_string(),
+        };
+        let options = std::iter::once(Choice(None, automatic.clone()))
+            .chain(ViewKind::iter().map(|kind| Choice(Some(kind), kind.to_string())))
+            .collect::<Vec<_>>();
+        let selected = match self.kind {
+            Some(kind) => Choice(Some(kind), kind.to_string()),
+            None => Choice(None, automatic),
+        };
+        let wrapper = self.clone();
+        let viewer_config = match self.kind() {
+            ViewKind::Scalar => None,
+            ViewKind::Line => self.plot.config_view(
+                id,
+                input_data
+                    .into_values()
+                    .map(|v| ("y".to_string(), v))
+                    .collect(),
+            ),
+            ViewKind::Image => self.plot_2d.config_view(id, input_data),
+            ViewKind::VectorField => self.vector_field.config_view(id, input_data),
+            ViewKind::Volume => self.volume.config_view(id),
+        };
+        Some(
+            column![row![
+                text("view:"),
+                horizontal_space(),
+                pick_list(options, Some(selected), move |choice| update(choice.0)),
+            ]
+            .align_y(Center)]
+            .push_maybe(
+                viewer_config.map(|config| config.map(move |message| wrapper.wrap(message))),
+            )
+            .spacing(5.)
+            .into(),
+        )
+    }
+
+    /// Size for the chosen kind of view. An automatic view keeps one size,
+    /// so the node doesn't change size when its input does
+    pub fn node_size(&self) -> Size {
+        let dft = default_node_size();
+        match self.kind {
+            None | Some(ViewKind::Line) => dft * 2.,
+            Some(ViewKind::Scalar) => Size::new(dft.width * 2., dft.height),
+            Some(ViewKind::Image | ViewKind::VectorField) => {
+                Size::new(dft.width * 2., dft.width * 2.)
+            }
+            Some(ViewKind::Volume) => match self.volume.orthogonal {
+                true => Size::new(dft.width * 3., dft.width * 2.),
+                false => Size::new(dft.width * 2., dft.width * 2.),
+            },
+        }
+    }
+}
+
+/// An entry in the list of kinds of view, `None` for automatic
+#[derive(Debug, Clone, PartialEq)]
+struct Choice(Option<ViewKind>, String);
+
+impl std::fmt::Display for Choice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.1)
+    }
+}
+
+/// Type and shape of `data`
+fn describe(data: &PortData) -> String {
+    let (dtype, shape, _) = dtype_shape_bytes(data);
+    format!("{dtype} {shape:?}")
+}
+
+/// The value of a scalar, or a summary of an array
+fn readout(data: &PortData) -> String {
+    match data {
+        PortData::Integer(v) => v.to_string(),
+        PortData::Real(v) => format!("{v:.4}"),
+        PortData::Complex(v) => format!("{:.4}{:+.4}i", v.re, v.im),
+        PortData::ArrayInteger(a) if a.len() == 1 => a.iter().next().unwrap().to_string(),
+        PortData::ArrayReal(a) | PortData::Dynamic(a) if a.len() == 1 => {
+            format!("{:.4}", a.iter().next().unwrap())
+        }
+        PortData::ArrayComplex(a) if a.len() == 1 => {
+            let v = a.iter().next().unwrap();
+            format!("{:.4}{:+.4}i", v.re, v.im)
+        }
+        data => match stats(data) {
+            Some(stats) => format!("{}\n{:.3} .. {:.3}", describe(data), stats.min, stats.max),
+            None => describe(data),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ndarray::{ArrayD, IxDyn};
+    use numpy::Complex64;
+    use std::sync::RwLock;
+
+    #[test]
+    fn detect_kind() {
+        let real = |shape: &[usize]| PortData::ArrayReal(ArrayD::zeros(IxDyn(shape)));
+        assert_eq!(ViewKind::detect(&PortData::Real(1.)), ViewKind::Scalar);
+        assert_eq!(ViewKind::detect(&real(&[1])), ViewKind::Scalar);
+        assert_eq!(ViewKind::detect(&real(&[10])), ViewKind::Line);
+        assert_eq!(ViewKind::detect(&real(&[4, 5])), ViewKind::Image);
+        assert_eq!(ViewKind::detect(&real(&[4, 4, 3])), ViewKind::VectorField);
+        assert_eq!(ViewKind::detect(&real(&[4, 5, 3])), ViewKind::Volume);
+        let complex = PortData::ArrayComplex(ArrayD::from_elem(IxDyn(&[2, 3, 4]), Complex64::ONE));
+        assert_eq!(ViewKind::detect(&complex), ViewKind::Volume);
+    }
+
+    #[test]
+    fn manual_override() {
+        let data = RwLock::new(PortData::ArrayReal(ArrayD::zeros(IxDyn(&[4, 5]))));
+        let mut view = View::default();
+        assert!(view
+            .compute([("a".to_string(), data.read().unwrap())].into())
+            .is_ok());
+        assert_eq!(view.kind(), ViewKind::Image);
+        assert!(view.plot_2d.image_handle.is_some());
+
+        view.kind = Some(ViewKind::Volume);
+        assert!(view
+            .compute([("a".to_string(), data.read().unwrap())].into())
+            .is_ok());
+        assert_eq!(view.volume.images.len(), 1);
+
+        view.kind = Some(ViewKind::VectorField);
+        assert!(view
+            .compute([("a".to_string(), data.read().unwrap())].into())
+            .is_err());
+    }
+
+    #[test]
+    fn size_and_ordering_ignore_results() {
+        let data = RwLock::new(PortData::ArrayReal(ArrayD::zeros(IxDyn(&[4, 5]))));
+        let view = View::default();
+        let mut computed = view.clone();
+        assert!(computed
+            .compute([("a".to_string(), data.read().unwrap())].into())
+            .is_ok());
+        assert_eq!(computed.node_size(), view.node_size());
+        assert_ne!(computed, view);
+        assert_eq!(computed.partial_cmp(&view), None);
+
+        let image = View {
+            kind: Some(ViewKind::Image),
+            ..View::default()
+        };
+        assert_eq!(view.partial_cmp(&image), Some(std::cmp::Ordering::Less));
+    }
+}
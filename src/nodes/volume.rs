@@ -0,0 +1,349 @@
+use super::{PortData, RustNode};
+use crate::app::Message;
+use crate::gui_node::{PortDataContainer, PortDataReference};
+use crate::interface::node::NODE_BORDER_WIDTH;
+use crate::nodes::figure::{Figure, FigureStyle};
+use crate::nodes::plot_complex::ImageDisplay;
+use crate::nodes::status::NodeError;
+use crate::nodes::NodeTemplate;
+use crate::StableMap;
+use iced::widget::image::Handle;
+use iced::widget::{checkbox, column, container, horizontal_space, image, pick_list, row, slider};
+use iced::widget::{text, Column};
+use iced::Alignment::Center;
+use iced::Length::{Fill, FillPortion};
+use iced::{Element, Point, Rectangle, Size};
+use log::trace;
+use ndarray::{ArrayD, Axis};
+use serde::{Deserialize, Serialize};
+
+/// Shows a 2D plane of an N-D array, with the other axes fixed at an index
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Clone)]
+pub struct VolumeViewer {
+    /// Axes shown as the rows and columns of the image, `None` shows the last two axes
+    pub axes: Option<(usize, usize)>,
+    /// Index into every axis. The display axes' indices place the orthogonal views
+    pub indices: Vec<usize>,
+    /// Also show the planes through the first hidden axis
+    pub orthogonal: bool,
+    #[serde(default)]
+    pub display: ImageDisplay,
+    #[serde(skip)]
+    pub shape: Vec<usize>,
+    /// Range of values in the main view, a starting point for manual window/level
+    #[serde(skip)]
+    pub range: Option<(f64, f64)>,
+    /// The main view, followed by the orthogonal views
+    #[serde(skip)]
+    pub images: Vec<Handle>,
+}
+
+/// Ordered by the viewer's configuration. Viewers that only differ in their
+/// images are unordered, so that the ordering agrees with `PartialEq`
+impl PartialOrd for VolumeViewer {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (self.axes, &self.indices, self.orthogonal, self.display).partial_cmp(&(
+            other.axes,
+            &other.indices,
+            other.orthogonal,
+            other.display,
+        )) {
+            Some(std::cmp::Ordering::Equal) if self != other => None,
+            ord => ord,
+        }
+    }
+}
+
+impl VolumeViewer {
+    /// Axes shown as rows and columns, for an array of `ndim` dimensions
+    pub fn display_axes(&self, ndim: usize) -> (usize, usize) {
+        self.axes
+            .unwrap_or((ndim.saturating_sub(2), ndim.saturating_sub(1)))
+    }
+
+    /// The planes shown, as (row axis, column axis)
+    fn planes(&self, ndim: usize) -> Vec<(usize, usize)> {
+        let (rows, columns) = self.display_axes(ndim);
+        let depth = (0..ndim).find(|axis| *axis != rows && *axis != columns);
+        match (self.orthogonal, depth) {
+            (true, Some(depth)) => vec![(rows, columns), (depth, columns), (rows, depth)],
+            _ => vec![(rows, columns)],
+        }
+    }
+
+    pub(crate) fn input_changed(
+        &mut self,
+        input_data: StableMap<String, PortDataReference>,
+    ) -> Result<StableMap<String, PortData>, NodeError> {
+        let Some(data) = input_data.get("a") else {
+            self.images = vec![];
+            self.range = None;
+            return Ok([].into());
+        };
+        let shape = match &**data {
+            PortData::ArrayReal(a) | PortData::Dynamic(a) => a.shape().to_vec(),
+            PortData::ArrayInteger(a) => a.shape().to_vec(),
+            PortData::ArrayComplex(a) => a.shape().to_vec(),
+            _ => return Err(NodeError::input_error("a")),
+        };
+        if shape.len() < 2 || shape.contains(&0) {
+            return Err(NodeError::Input(format!(
+                "Volume viewer needs a non-empty array of at least 2 dimensions, found shape {shape:?}"
+            )));
+        }
+        let (rows, columns) = self.display_axes(shape.len());
+        if rows == columns || rows >= shape.len() || columns >= shape.len() {
+            return Err(NodeError::Config(format!(
+                "Can't display axes {rows} and {columns} of an array with shape {shape:?}"
+            )));
+        }
+
+        self.indices.resize(shape.len(), 0);
+        for (index, len) in self.indices.iter_mut().zip(&shape) {
+            *index = (*index).min(len - 1);
+        }
+        self.shape = shape;
+
+        let images = self
+            .planes(self.shape.len())
+            .into_iter()
+            .map(|axes| self.display.image(&plane(data, axes, &self.indices)))
+            .collect::<Result<Vec<_>, _>>()?;
+        trace!("Creating image handles for volume viewer, {:?}", self.shape);
+        self.range = images.first().map(|image| image.range);
+        self.images = images.into_iter().map(|image| image.handle()).collect();
+        Ok([].into())
+    }
+
+    pub fn view<'a>(&self) -> Element<'a, Message> {
+        let plane = |handle: &Handle| {
+            image(handle.clone())
+                .filter_method(image::FilterMethod::Nearest)
+                .width(Fill)
+                .height(Fill)
+        };
+        // The orthogonal views take a third of the width, to the right of the main view
+        let content: Element<'a, Message> = match self.images.as_slice() {
+            [main] => plane(main).into(),
+            [main, depth_columns, rows_depth] => row![
+                container(plane(main)).width(FillPortion(2)),
+                column![plane(depth_columns), plane(rows_depth)].width(FillPortion(1))
+            ]
+            .into(),
+            _ => text("").into(),
+        };
+        container(content).padding(NODE_BORDER_WIDTH).into()
+    }
+
+    /// The planes through the input at `size`, laid out as in the node
+    pub fn figure(
+        &self,
+        input_data: &StableMap<String, PortDataContainer>,
+        size: Size,
+        style: FigureStyle,
+    ) -> Result<Figure, NodeError> {
+        let port = input_data
+            .get("a")
+            .ok_or(NodeError::Input("No array on input a".to_string()))?;
+        let data = port.read().unwrap();
+        let images = self
+            .planes(self.shape.len())
+            .into_iter()
+            .map(|axes| self.display.image(&plane(&data, axes, &self.indices)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut figure = Figure::new(size, style);
+        let mut images = images.into_iter();
+        let main_width = match images.len() {
+            1 => size.width,
+            _ => size.width * 2. / 3.,
+        };
+        if let Some(main) = images.next() {
+            figure.push(main.mark(Rectangle::new(
+                Point::ORIGIN,
+                Size::new(main_width, size.height),
+            )));
+        }
+        let side = Size::new(size.width - main_width, size.height / 2.);
+        for (i, image) in images.enumerate() {
+            figure.push(image.mark(Rectangle::new(
+                Point::new(main_width, i as f32 * side.height),
+                side,
+            )));
+        }
+        Ok(figure)
+    }
+
+    pub fn config_view(&self, id: u32) -> Option<Element<'_, Message>> {
+        let update = move |viewer| {
+            Message::UpdateNodeTemplate(id, NodeTemplate::RustNode(RustNode::VolumeViewer(viewer)))
+        };
+        let ndim = self.shape.len();
+        if ndim < 2 {
+            return Some(text("Connect an array of at least 2 dimensions").into());
+        }
+        let (rows, columns) = self.display_axes(ndim);
+        // Picking an axis that is already displayed swaps the two display axes
+        let set_axes = move |axes: (usize, usize)| {
+            let axes = match axes {
+                (r, c) if r == c => (columns, rows),
+                axes => axes,
+            };
+            update(Self {
+                axes: Some(axes),
+                ..self.clone()
+            })
+        };
+
+        //// Index sliders of the hidden axes, and of the display axes for orthogonal views
+        let sliders = Column::with_children(
+            self.shape
+                .iter()
+                .enumerate()
+                .filter(|(axis, _)| self.orthogonal || (*axis != rows && *axis != columns))
+                .map(|(axis, len)| {
+                    let index = self.indices.get(axis).copied().unwrap_or(0);
+                    row![
+                        text(format!("axis {axis}:")),
+                        slider(0..=(len - 1) as u32, index as u32, move |index| {
+                            let mut indices = self.indices.clone();
+                            indices.resize(ndim, 0);
+                            indices[axis] = index as usize;
+                            update(Self {
+                                indices,
+                                ..self.clone()
+                            })
+                        }),
+                        text(format!("{index}/{len}")),
+                    ]
+                    .spacing(5.)
+                    .align_y(Center)
+                    .into()
+                }),
+        )
+        .spacing(2.);
+
+        Some(
+            column![
+                text(format!("shape: {:?}", self.shape)),
+                row![
+                    text("rows:"),
+                    pick_list((0..ndim).collect::<Vec<_>>(), Some(rows), move |r| {
+                        set_axes((r, columns))
+                    }),
+                    horizontal_space(),
+                    text("columns:"),
+                    pick_list((0..ndim).collect::<Vec<_>>(), Some(columns), move |c| {
+                        set_axes((rows, c))
+                    }),
+                ]
+                .spacing(4.)
+                .align_y(Center),
+                checkbox("orthogonal views", self.orthogonal).on_toggle_maybe(
+                    (ndim > 2).then_some(move |orthogonal| update(Self {
+                        orthogonal,
+                        ..self.clone()
+                    }))
+                ),
+                sliders,
+                self.display
+                    .controls(self.range, move |display| update(Self {
+                        display,
+                        ..self.clone()
+                    })),
+            ]
+            .spacing(5.0)
+            .into(),
+        )
+    }
+}
+
+/// The 2D plane of `data` along `axes`, with every other axis fixed at its entry in `indices`
+fn plane(data: &PortData, axes: (usize, usize), indices: &[usize]) -> PortData {
+    match data {
+        PortData::ArrayReal(a) => PortData::ArrayReal(slice_plane(a, axes, indices)),
+        PortData::Dynamic(a) => PortData::Dynamic(slice_plane(a, axes, indices)),
+        PortData::ArrayInteger(a) => PortData::ArrayInteger(slice_plane(a, axes, indices)),
+        PortData::ArrayComplex(a) => PortData::ArrayComplex(slice_plane(a, axes, indices)),
+        other => other.clone(),
+    }
+}
+
+fn slice_plane<T: Clone>(array: &ArrayD<T>, axes: (usize, usize), indices: &[usize]) -> ArrayD<T> {
+    let mut view = array.view();
+    // Remove the highest axes first, so that the lower axis numbers stay valid
+    for axis in (0..array.ndim()).rev() {
+        if axis == axes.0 || axis == axes.1 {
+            continue;
+        }
+        let len = view.len_of(Axis(axis));
+        let index = indices.get(axis).copied().unwrap_or(0);
+        view = view.index_axis_move(Axis(axis), index.min(len.saturating_sub(1)));
+    }
+    match axes.0 > axes.1 {
+        true => view.reversed_axes().to_owned(),
+        false => view.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ndarray::IxDyn;
+    use std::{cmp::Ordering, sync::RwLock};
+
+    #[test]
+    fn slice_planes() {
+        let array = ArrayD::from_shape_fn(IxDyn(&[2, 3, 4, 5]), |i| {
+            i[0] * 1000 + i[1] * 100 + i[2] * 10 + i[3]
+        });
+        let indices = [1, 2, 3, 4];
+
+        let view = slice_plane(&array, (2, 3), &indices);
+        assert_eq!(view.shape(), &[4, 5]);
+        assert_eq!(view[[0, 1]], 1201);
+
+        // Axes are transposed when the row axis comes after the column axis
+        let view = slice_plane(&array, (3, 1), &indices);
+        assert_eq!(view.shape(), &[5, 3]);
+        assert_eq!(view[[4, 0]], 1034);
+    }
+
+    #[test]
+    fn orthogonal_views() {
+        let mut viewer = VolumeViewer {
+            orthogonal: true,
+            indices: vec![9, 1, 0],
+            ..Default::default()
+        };
+        let data = RwLock::new(PortData::ArrayReal(ArrayD::zeros(IxDyn(&[3, 4, 5]))));
+        let out = viewer.input_changed([("a".to_string(), data.read().unwrap())].into());
+        assert!(out.is_ok());
+        assert_eq!(viewer.planes(3), vec![(1, 2), (0, 2), (1, 0)]);
+        assert_eq!(viewer.images.len(), 3);
+        // Indices beyond the end of an axis are clamped
+        assert_eq!(viewer.indices, vec![2, 1, 0]);
+
+        viewer.axes = Some((1, 1));
+        let out = viewer.input_changed([("a".to_string(), data.read().unwrap())].into());
+        assert!(out.is_err());
+    }
+
+    #[test]
+    fn ordering_agrees_with_equality() {
+        let viewer = VolumeViewer::default();
+        let computed = VolumeViewer {
+            shape: vec![3, 4],
+            range: Some((0., 1.)),
+            ..Default::default()
+        };
+        assert_ne!(viewer, computed);
+        assert_eq!(viewer.partial_cmp(&computed), None);
+        assert_eq!(viewer.partial_cmp(&viewer.clone()), Some(Ordering::Equal));
+
+        let moved = VolumeViewer {
+            indices: vec![1],
+            ..Default::default()
+        };
+        assert_eq!(viewer.partial_cmp(&moved), Some(Ordering::Less));
+    }
+}
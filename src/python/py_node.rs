@@ -9,9 +9,10 @@ use iced::{Element, Length::Fill};
 use log::trace;
 use numpy::{Complex64, PyArrayMethods, ToPyArray};
 use pyo3::{
+    exceptions::PyValueError,
     ffi::c_str,
     types::{PyAnyMethods, PyComplex, PyDict, PyDictMethods, PyModule},
-    Bound, FromPyObject, IntoPyObject, PyAny, PyErr, PyObject, PyResult, Python,
+    Bound, FromPyObject, IntoPyObject, PyAny, PyObject, Python,
 };
 use relative_path::RelativePathBuf;
 use serde::{Deserialize, Serialize};
@@ -50,10 +51,7 @@ pub struct PortDef {
 impl<'py> FromPyObject<'py> for PortType {
     fn extract_bound(ob: &pyo3::Bound<'py, pyo3::PyAny>) -> pyo3::PyResult<Self> {
         if let Ok(s) = ob.extract::<String>() {
-            match PortType::from_str(&s) {
-                Ok(pt) => Ok(pt),
-                Err(_) => PyResult::Err(PyErr::from_value(ob.clone())),
-            }
+            PortType::parse(&s).map_err(PyValueError::new_err)
         } else {
             Ok(PortType::Object(
                 ob.extract::<StableMap<String, PortType>>()?
@@ -368,6 +366,7 @@ impl PyNode {
                         .as_array()
                         .to_owned(),
                 ),
+                PortType::Shaped(base, _) => Self::extract_py_data(base, py_object, py)?,
                 PortType::Object(types) => {
                     let dict: &Bound<PyDict> = py_object
                         .bind(py)
@@ -453,3 +452,45 @@ fn output_error(port_type: &PortType, py_object: &PyObject) -> NodeError {
         "Received unexpected output from node. Expected one of {port_type:#?}, found {py_object:#?}"
     ))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::nodes::port::{ArrayShape, Dim};
+
+    #[test]
+    fn extract_shaped_ports() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            // As declared by `port.ArrayReal.shape("N", 3)` and `port.ArrayComplex.ndim(2)`
+            let ports = py
+                .eval(
+                    c_str!(
+                        "__import__('types').SimpleNamespace(
+                            inputs={'points': 'ArrayReal[N,3]'},
+                            outputs={'out': {'image': 'ArrayComplex[2]', 'count': 'Integer'}},
+                        )"
+                    ),
+                    None,
+                    None,
+                )
+                .unwrap();
+            let ports: PortDef = ports.extract().unwrap();
+            assert_eq!(
+                ports.inputs["points"],
+                PortType::Shaped(
+                    Box::new(PortType::ArrayReal),
+                    ArrayShape::Dims(vec![Dim::Named("N".into()), Dim::Fixed(3)])
+                )
+            );
+            let PortType::Object(fields) = &ports.outputs["out"] else {
+                panic!("expected an object output")
+            };
+            assert_eq!(fields["image"].rank(), Some(2));
+            assert_eq!(fields["count"], PortType::Integer);
+
+            let invalid = py.eval(c_str!("'ArrayReal[N,-1]'"), None, None).unwrap();
+            assert!(invalid.extract::<PortType>().is_err());
+        });
+    }
+}
@@ -1,6 +1,7 @@
 use pyo3::{pyclass, pymethods};
 
 #[pyclass]
+#[allow(dead_code)]
 pub struct Slider {
     default: f32,
     start: f32,
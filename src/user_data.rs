@@ -3,6 +3,9 @@ use std::{fs::read_to_string, path::PathBuf};
 use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
 
+/// Number of recent command palette choices that are remembered
+const MAX_RECENT_PALETTE_ITEMS: usize = 10;
+
 /// User data that should persist across sessions, but isn't explicitly configured by the user
 /// e.g. recent files, recently used nodes, etc.
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -11,6 +14,12 @@ pub struct UserData {
     most_recent_network_file: Option<PathBuf>,
     /// Expanded folder path when selecting new node
     new_node_path: Vec<String>,
+    /// Snap dragged nodes to the workspace grid
+    #[serde(default)]
+    snap_to_grid: bool,
+    /// Items most recently chosen in the command palette, newest first
+    #[serde(default)]
+    recent_palette_items: Vec<String>,
 }
 
 /// Getters and Setters, are used to serialize to
@@ -31,6 +40,24 @@ impl UserData {
     pub fn get_new_node_path(&self) -> &[String] {
         &self.new_node_path
     }
+
+    pub fn set_snap_to_grid(&mut self, snap_to_grid: bool) {
+        self.snap_to_grid = snap_to_grid;
+        self.write();
+    }
+    pub fn snap_to_grid(&self) -> bool {
+        self.snap_to_grid
+    }
+
+    pub fn add_recent_palette_item(&mut self, key: String) {
+        self.recent_palette_items.retain(|k| *k != key);
+        self.recent_palette_items.insert(0, key);
+        self.recent_palette_items.truncate(MAX_RECENT_PALETTE_ITEMS);
+        self.write();
+    }
+    pub fn recent_palette_items(&self) -> &[String] {
+        &self.recent_palette_items
+    }
 }
 impl UserData {
     fn user_data_dir() -> PathBuf {
@@ -0,0 +1,270 @@
+use iced::advanced::graphics::geometry::frame::Backend;
+use iced::alignment;
+use iced::widget::canvas::{Fill, Path, Stroke, Text};
+use iced::{Color, Rectangle, Size};
+use serde::{Deserialize, Serialize};
+use strum::EnumIter;
+
+use crate::math::Point;
+
+pub type AnnotationId = u32;
+
+/// Height of the title bar of a frame, which is used to select and drag it
+pub const TITLE_HEIGHT: f32 = 24.;
+/// Size of the handle in the bottom right corner, used to resize annotations
+const HANDLE_SIZE: f32 = 12.;
+const TEXT_SIZE: f32 = 14.;
+const PADDING: f32 = 6.;
+pub const MIN_SIZE: Size = Size::new(60., 40.);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnnotationKind {
+    /// A sticky note with wrapped text
+    Note,
+    /// A titled region that encloses nodes, and moves them with it
+    Frame,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter)]
+pub enum AnnotationColor {
+    #[default]
+    Yellow,
+    Orange,
+    Red,
+    Purple,
+    Blue,
+    Green,
+    Gray,
+}
+
+impl AnnotationColor {
+    pub fn color(self) -> Color {
+        match self {
+            AnnotationColor::Yellow => Color::from_rgb8(232, 208, 112),
+            AnnotationColor::Orange => Color::from_rgb8(232, 160, 96),
+            AnnotationColor::Red => Color::from_rgb8(216, 112, 112),
+            AnnotationColor::Purple => Color::from_rgb8(176, 136, 208),
+            AnnotationColor::Blue => Color::from_rgb8(112, 160, 216),
+            AnnotationColor::Green => Color::from_rgb8(128, 192, 128),
+            AnnotationColor::Gray => Color::from_rgb8(168, 168, 168),
+        }
+    }
+}
+
+/// Part of an annotation under the cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationPart {
+    Body,
+    ResizeHandle,
+}
+
+/// A non-computing item on the canvas, used to document a network
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub kind: AnnotationKind,
+    pub text: String,
+    pub position: Point,
+    pub width: f32,
+    pub height: f32,
+    pub color: AnnotationColor,
+}
+
+impl Annotation {
+    pub fn note(position: Point) -> Self {
+        Annotation {
+            kind: AnnotationKind::Note,
+            text: "Note".into(),
+            position,
+            width: 160.,
+            height: 100.,
+            color: AnnotationColor::Yellow,
+        }
+    }
+
+    pub fn frame(position: Point, size: Size) -> Self {
+        Annotation {
+            kind: AnnotationKind::Frame,
+            text: "Frame".into(),
+            position,
+            width: size.width,
+            height: size.height,
+            color: AnnotationColor::Blue,
+        }
+    }
+
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    pub fn bounds(&self) -> Rectangle {
+        Rectangle::new(self.position.into(), self.size())
+    }
+
+    /// Find the part of the annotation at `point`, in workspace coordinates.
+    /// Only the title bar of a frame can be grabbed, so that nodes inside it can still be selected
+    pub fn hit(&self, point: Point) -> Option<AnnotationPart> {
+        let bounds = self.bounds();
+        let handle = Rectangle::new(
+            iced::Point::new(
+                bounds.x + bounds.width - HANDLE_SIZE,
+                bounds.y + bounds.height - HANDLE_SIZE,
+            ),
+            Size::new(HANDLE_SIZE, HANDLE_SIZE),
+        );
+        let grab = match self.kind {
+            AnnotationKind::Note => bounds,
+            AnnotationKind::Frame => Rectangle {
+                height: TITLE_HEIGHT,
+                ..bounds
+            },
+        };
+        if handle.contains(point.into()) {
+            Some(AnnotationPart::ResizeHandle)
+        } else if grab.contains(point.into()) {
+            Some(AnnotationPart::Body)
+        } else {
+            None
+        }
+    }
+
+    /// Is the rectangle at `position` with `size` completely inside the annotation
+    pub fn encloses(&self, position: Point, size: Size) -> bool {
+        let bounds = self.bounds();
+        bounds.contains(position.into())
+            && bounds.contains(iced::Point::new(
+                position.x + size.width,
+                position.y + size.height,
+            ))
+    }
+
+    /// Draw the annotation into a frame that is transformed into workspace coordinates
+    pub fn draw(&self, frame: &mut impl Backend, selected: bool, highlight: Color) {
+        let bounds = self.bounds();
+        let color = self.color.color();
+        let text_color = Color::from_rgb(0.1, 0.1, 0.1);
+        let outline = Path::rounded_rectangle(bounds.position(), bounds.size(), 4.0.into());
+        let (text_bounds, text_size) = match self.kind {
+            AnnotationKind::Note => {
+                frame.fill(&outline, Fill::from(color.scale_alpha(0.9)));
+                (bounds, TEXT_SIZE)
+            }
+            AnnotationKind::Frame => {
+                frame.fill(&outline, Fill::from(color.scale_alpha(0.12)));
+                frame.fill(
+                    &Path::rounded_rectangle(
+                        bounds.position(),
+                        Size::new(bounds.width, TITLE_HEIGHT),
+                        4.0.into(),
+                    ),
+                    Fill::from(color.scale_alpha(0.8)),
+                );
+                (
+                    Rectangle {
+                        height: TITLE_HEIGHT,
+                        ..bounds
+                    },
+                    TEXT_SIZE + 2.,
+                )
+            }
+        };
+        frame.stroke(
+            &outline,
+            match selected {
+                true => Stroke::default().with_width(2.0).with_color(highlight),
+                false => Stroke::default().with_width(1.0).with_color(color),
+            },
+        );
+
+        //// Text, wrapped by character count since the canvas font is monospaced
+        let max_chars = ((text_bounds.width - 2. * PADDING) / (text_size * 0.6)).max(1.) as usize;
+        let max_lines = ((text_bounds.height - PADDING) / (text_size * 1.3)).max(1.) as usize;
+        frame.fill_text(Text {
+            content: wrap(&self.text, max_chars)
+                .into_iter()
+                .take(max_lines)
+                .collect::<Vec<_>>()
+                .join("\n"),
+            position: iced::Point::new(text_bounds.x + PADDING, text_bounds.y + PADDING / 2.),
+            color: text_color,
+            size: text_size.into(),
+            font: crate::DEFAULT_FONT,
+            horizontal_alignment: alignment::Horizontal::Left,
+            vertical_alignment: alignment::Vertical::Top,
+            ..Text::default()
+        });
+
+        //// Resize handle
+        let corner = iced::Point::new(bounds.x + bounds.width, bounds.y + bounds.height);
+        let handle = Path::new(|builder| {
+            builder.move_to(iced::Point::new(corner.x - HANDLE_SIZE, corner.y - 2.));
+            builder.line_to(iced::Point::new(corner.x - 2., corner.y - HANDLE_SIZE));
+            builder.move_to(iced::Point::new(corner.x - HANDLE_SIZE / 2., corner.y - 2.));
+            builder.line_to(iced::Point::new(corner.x - 2., corner.y - HANDLE_SIZE / 2.));
+        });
+        frame.stroke(
+            &handle,
+            Stroke::default()
+                .with_width(1.0)
+                .with_color(match self.kind {
+                    AnnotationKind::Note => text_color.scale_alpha(0.5),
+                    AnnotationKind::Frame => color,
+                }),
+        );
+    }
+}
+
+/// Split text into lines of at most `max_chars` characters, breaking at spaces where possible
+fn wrap(text: &str, max_chars: usize) -> Vec<String> {
+    let mut lines = vec![];
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            let mut word: Vec<char> = word.chars().collect();
+            // Words that are too long for a line are broken up
+            while word.len() > max_chars {
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                lines.push(word.drain(..max_chars).collect());
+            }
+            let word: String = word.into_iter().collect();
+            if line.is_empty() {
+                line = word;
+            } else if line.chars().count() + 1 + word.chars().count() <= max_chars {
+                line = line + " " + &word;
+            } else {
+                lines.push(std::mem::replace(&mut line, word));
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wrap_text() {
+        assert_eq!(
+            wrap("this branch does coil compression\nnext", 12),
+            ["this branch", "does coil", "compression", "next"]
+        );
+        assert_eq!(wrap("abcdefgh ij", 3), ["abc", "def", "gh", "ij"]);
+    }
+
+    #[test]
+    fn frame_hit_areas() {
+        let frame = Annotation::frame(Point::new(0., 0.), Size::new(200., 100.));
+        assert_eq!(frame.hit(Point::new(50., 10.)), Some(AnnotationPart::Body));
+        // The inside of a frame is left for the nodes it encloses
+        assert_eq!(frame.hit(Point::new(50., 50.)), None);
+        assert_eq!(
+            frame.hit(Point::new(195., 95.)),
+            Some(AnnotationPart::ResizeHandle)
+        );
+        assert!(frame.encloses(Point::new(10., 30.), Size::new(100., 60.)));
+        assert!(!frame.encloses(Point::new(150., 30.), Size::new(100., 60.)));
+    }
+}
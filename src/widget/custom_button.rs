@@ -285,27 +285,27 @@ where
                 }
             }
             Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
-            | Event::Touch(touch::Event::FingerLifted { .. }) => {
-                if self.on_press.is_some() {
-                    if state.is_pressed {
-                        state.is_pressed = false;
-
-                        if let Some(on_release_self) =
-                            self.on_release_self.as_ref().map(OnInteract::get)
-                        {
-                            //if cursor.is_over(bounds) {
-                            shell.publish(on_release_self);
-                            //}
-
-                            return event::Status::Captured;
-                        }
-                    } else if let Some(on_release_other) =
-                        self.on_release_other.as_ref().map(OnInteract::get)
+            | Event::Touch(touch::Event::FingerLifted { .. })
+                if self.on_press.is_some() =>
+            {
+                if state.is_pressed {
+                    state.is_pressed = false;
+
+                    if let Some(on_release_self) =
+                        self.on_release_self.as_ref().map(OnInteract::get)
                     {
-                        if cursor.is_over(bounds) {
-                            shell.publish(on_release_other);
-                            return event::Status::Captured;
-                        }
+                        //if cursor.is_over(bounds) {
+                        shell.publish(on_release_self);
+                        //}
+
+                        return event::Status::Captured;
+                    }
+                } else if let Some(on_release_other) =
+                    self.on_release_other.as_ref().map(OnInteract::get)
+                {
+                    if cursor.is_over(bounds) {
+                        shell.publish(on_release_other);
+                        return event::Status::Captured;
                     }
                 }
             }
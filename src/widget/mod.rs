@@ -1,3 +1,4 @@
+pub mod annotation;
 pub mod custom_button;
 pub mod node_container;
 pub mod pin;
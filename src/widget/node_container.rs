@@ -9,7 +9,12 @@ use iced::advanced::widget::Widget;
 use iced::advanced::Shell;
 use iced::advanced::{Clipboard, Layout};
 use iced::event;
-use iced::{Element, Event, Length, Rectangle, Size, Vector};
+use iced::{Element, Event, Length, Point, Rectangle, Size, Vector};
+
+/// Size of the square in the bottom right corner of the content that resizes the node
+const HANDLE_SIZE: f32 = 12.;
+/// Size of the dots that are drawn on the resize handle
+const GRIP_SIZE: f32 = 2.;
 
 /// A container that can have additional pinned elements positioned relative to the container
 /// and doesn't cut them off because they are out of the container's bounds
@@ -22,6 +27,7 @@ where
     absolute_children: Vec<Element<'a, Message, Theme, Renderer>>,
     width: Length,
     height: Length,
+    on_resize_press: Option<Message>,
 }
 
 impl<'a, Message, Theme, Renderer> NodeContainer<'a, Message, Theme, Renderer>
@@ -38,6 +44,7 @@ where
             absolute_children,
             width: Length::Shrink,
             height: Length::Shrink,
+            on_resize_press: None,
         }
     }
 
@@ -52,11 +59,29 @@ where
         self.height = height.into();
         self
     }
+
+    /// Shows a resize handle in the bottom right corner, which publishes `message` when pressed
+    pub fn on_resize_press(mut self, message: Message) -> Self {
+        self.on_resize_press = Some(message);
+        self
+    }
+}
+
+/// Area of the resize handle, within the bounds of the main content
+fn resize_handle(content: Rectangle) -> Rectangle {
+    Rectangle::new(
+        Point::new(
+            content.x + content.width - HANDLE_SIZE,
+            content.y + content.height - HANDLE_SIZE,
+        ),
+        Size::new(HANDLE_SIZE, HANDLE_SIZE),
+    )
 }
 
 impl<Message, Theme, Renderer> Widget<Message, Theme, Renderer>
     for NodeContainer<'_, Message, Theme, Renderer>
 where
+    Message: Clone,
     Renderer: iced::advanced::Renderer,
 {
     fn tag(&self) -> widget::tree::Tag {
@@ -150,6 +175,16 @@ where
         shell: &mut Shell<'_, Message>,
         viewport: &Rectangle,
     ) -> iced::event::Status {
+        // The handle takes priority over the content underneath it
+        if let (Some(message), Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))) =
+            (&self.on_resize_press, &event)
+        {
+            let content = layout.children().next().unwrap().bounds();
+            if cursor.is_over(resize_handle(content)) {
+                shell.publish(message.clone());
+                return event::Status::Captured;
+            }
+        }
         iter::once(&mut self.main_content)
             .chain(&mut self.absolute_children)
             .zip(&mut tree.children)
@@ -177,6 +212,10 @@ where
         viewport: &Rectangle,
         renderer: &Renderer,
     ) -> mouse::Interaction {
+        let content = layout.children().next().unwrap().bounds();
+        if self.on_resize_press.is_some() && cursor.is_over(resize_handle(content)) {
+            return mouse::Interaction::ResizingDiagonallyDown;
+        }
         iter::once(&self.main_content)
             .chain(&self.absolute_children)
             .zip(&tree.children)
@@ -227,6 +266,29 @@ where
             cursor,
             &layout.children().next().unwrap().bounds(),
         );
+        //Render the resize handle as a grip of dots, while hovering the node
+        let content = layout.children().next().unwrap().bounds();
+        if self.on_resize_press.is_some() && cursor.is_over(content) {
+            let corner = Point::new(content.x + content.width, content.y + content.height);
+            let color = style.text_color.scale_alpha(0.5);
+            // A triangle of dots, filling the corner
+            for (x, y) in [(0., 0.), (1., 0.), (2., 0.), (0., 1.), (1., 1.), (0., 2.)] {
+                let dot = Rectangle::new(
+                    Point::new(
+                        corner.x - (x + 1.) * 2. * GRIP_SIZE - GRIP_SIZE,
+                        corner.y - (y + 1.) * 2. * GRIP_SIZE - GRIP_SIZE,
+                    ),
+                    Size::new(GRIP_SIZE, GRIP_SIZE),
+                );
+                renderer.fill_quad(
+                    renderer::Quad {
+                        bounds: dot,
+                        ..Default::default()
+                    },
+                    color,
+                );
+            }
+        }
     }
 
     fn overlay<'b>(
@@ -258,7 +320,7 @@ where
 impl<'a, Message, Theme, Renderer> From<NodeContainer<'a, Message, Theme, Renderer>>
     for Element<'a, Message, Theme, Renderer>
 where
-    Message: 'a,
+    Message: Clone + 'a,
     Theme: 'a,
     Renderer: iced::advanced::Renderer + 'a,
 {
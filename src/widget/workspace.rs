@@ -1,22 +1,26 @@
 use iced::advanced::graphics::geometry::frame::Backend;
 use iced::advanced::layout::{self, Layout};
 use iced::advanced::overlay;
-use iced::advanced::widget::{self, Tree};
+use iced::advanced::widget::{self, tree, Tree};
 use iced::advanced::{Clipboard, Shell, Widget};
+use iced::keyboard::{self, Modifiers};
 use iced::mouse::Event::{ButtonPressed, ButtonReleased, CursorMoved, WheelScrolled};
 use iced::mouse::ScrollDelta;
 use iced::touch::Event::{FingerLifted, FingerLost, FingerMoved, FingerPressed};
+use iced::touch::Finger;
 
-use iced::widget::canvas::{Path, Stroke};
-use iced::{event, mouse, Color, Theme};
+use iced::widget::canvas::{Fill, Path, Stroke};
+use iced::{event, mouse, Color, Theme, Transformation};
 use iced::{Element, Event};
 use iced::{Length, Rectangle, Size};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 use crate::math::{Point, Vector};
 use crate::StableMap;
 
+use super::annotation::{Annotation, AnnotationId, AnnotationKind, AnnotationPart};
 use super::shapes::{Shape, ShapeId, Shapes};
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -34,6 +38,39 @@ impl Default for Camera {
     }
 }
 
+pub const MIN_ZOOM: f32 = 0.1;
+pub const MAX_ZOOM: f32 = 4.0;
+/// Minimum distance between grid lines on screen
+const MIN_GRID_SPACING: f32 = 10.;
+
+impl Camera {
+    /// Convert a point on screen, relative to the workspace origin, into workspace coordinates
+    pub fn to_world(&self, screen: Point) -> Point {
+        (self.position + screen.to_vector() * (1. / self.zoom)).to_point()
+    }
+
+    /// Set the zoom level, keeping the workspace point under `anchor` (a point on screen,
+    /// relative to the workspace origin) fixed in place
+    pub fn zoom_about(&mut self, zoom: f32, anchor: Point) {
+        let world = self.to_world(anchor);
+        self.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        self.position = world.to_vector() - anchor.to_vector() * (1. / self.zoom);
+    }
+
+    /// Zoom and pan so that the rectangle from `min` to `max` (in workspace coordinates)
+    /// is centered in, and fills the viewport. Zoom is never increased beyond 1.0
+    pub fn fit(&mut self, min: Point, max: Point, viewport: Size) {
+        let padding = 40.;
+        let size = max - min;
+        self.zoom = ((viewport.width - padding) / size.x)
+            .min((viewport.height - padding) / size.y)
+            .clamp(MIN_ZOOM, 1.0);
+        let center = min + size * 0.5;
+        self.position =
+            center.to_vector() - Vector::new(viewport.width, viewport.height) * (0.5 / self.zoom);
+    }
+}
+
 /// A workspace is a an infinite canvas that can be zoomed, panned,
 /// and contains widgets that can be placed anywhere in 2d
 pub struct Workspace<'a, Message, Theme, Renderer>
@@ -46,10 +83,19 @@ where
     camera: Camera,
     pan: Option<Box<dyn Fn(Vector) -> Message + 'a>>,
     zoom: Option<Box<dyn Fn(f32) -> Message + 'a>>,
+    on_resize: Option<Box<dyn Fn(Size) -> Message + 'a>>,
     on_cursor_move: Option<Box<dyn Fn(Point) -> Message + 'a>>,
     #[allow(clippy::type_complexity)]
     on_click: Option<Box<dyn Fn(Option<ShapeId>) -> Message + 'a>>,
     on_shape_release: Option<Message>,
+    on_select: Option<Box<dyn Fn(Vec<ShapeId>) -> Message + 'a>>,
+    on_pan_press: Option<Message>,
+    grid: Option<f32>,
+    /// Annotations in drawing order, and whether they are selected
+    annotations: Vec<(AnnotationId, &'a Annotation, bool)>,
+    #[allow(clippy::type_complexity)]
+    on_annotation_press: Option<Box<dyn Fn(AnnotationId, AnnotationPart) -> Message + 'a>>,
+    class: Theme::Class<'a>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -107,9 +153,16 @@ where
             camera: state.camera.clone(),
             pan: None,
             zoom: None,
+            on_resize: None,
             on_cursor_move: None,
             on_click: None,
             on_shape_release: None,
+            on_select: None,
+            on_pan_press: None,
+            grid: None,
+            annotations: vec![],
+            on_annotation_press: None,
+            class: Theme::default(),
         }
     }
 
@@ -118,11 +171,92 @@ where
         self
     }
 
+    /// Publishes a relative zoom factor, from ctrl + scroll or a two finger pinch
     pub fn zoom(mut self, zoom: impl Fn(f32) -> Message + 'a) -> Self {
         self.zoom = Some(Box::new(zoom));
         self
     }
 
+    /// Draw a background grid with the given spacing, in workspace coordinates
+    pub fn grid(mut self, spacing: Option<f32>) -> Self {
+        self.grid = spacing;
+        self
+    }
+
+    /// Notes and frames, drawn beneath the wires and nodes, and whether each one is selected
+    pub fn annotations(
+        mut self,
+        annotations: impl IntoIterator<Item = (AnnotationId, &'a Annotation, bool)>,
+    ) -> Self {
+        self.annotations = annotations.into_iter().collect();
+        // Frames are drawn below notes
+        self.annotations
+            .sort_by_key(|(_, annotation, _)| annotation.kind == AnnotationKind::Note);
+        self
+    }
+
+    /// Publishes the annotation, and the part of it, that was pressed
+    pub fn on_annotation_press(
+        mut self,
+        on_press: impl Fn(AnnotationId, AnnotationPart) -> Message + 'a,
+    ) -> Self {
+        self.on_annotation_press = Some(Box::new(on_press));
+        self
+    }
+
+    /// Publishes the size of the workspace whenever it changes
+    pub fn on_resize(mut self, on_resize: impl Fn(Size) -> Message + 'a) -> Self {
+        self.on_resize = Some(Box::new(on_resize));
+        self
+    }
+
+    /// Children are laid out at their unzoomed size, relative to the camera.
+    /// This maps that layout onto the screen
+    fn transformation(&self, bounds: Rectangle) -> Transformation {
+        Transformation::translate(bounds.x, bounds.y)
+            * Transformation::scale(self.camera.zoom)
+            * Transformation::translate(-bounds.x, -bounds.y)
+    }
+
+    /// Map a point on screen into the coordinates that children are laid out in
+    fn to_layout(&self, bounds: Rectangle, point: iced::Point) -> iced::Point {
+        bounds.position() + (point - bounds.position()) * (1. / self.camera.zoom)
+    }
+
+    /// Shapes whose bounds intersect the box with corners `start` and `end`, on screen
+    fn shapes_in_box(
+        &self,
+        bounds: Rectangle,
+        start: iced::Point,
+        end: iced::Point,
+        layout: Layout<'_>,
+    ) -> Vec<ShapeId> {
+        let (start, end) = (self.to_layout(bounds, start), self.to_layout(bounds, end));
+        let selection = Rectangle::new(
+            iced::Point::new(start.x.min(end.x), start.y.min(end.y)),
+            Size::new((end.x - start.x).abs(), (end.y - start.y).abs()),
+        );
+        self.shapes
+            .0
+            .keys()
+            .zip(layout.children())
+            .filter(|(_, layout)| layout.bounds().intersects(&selection))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    fn layout_cursor(&self, bounds: Rectangle, cursor: mouse::Cursor) -> mouse::Cursor {
+        match cursor {
+            mouse::Cursor::Available(p) => mouse::Cursor::Available(self.to_layout(bounds, p)),
+            mouse::Cursor::Unavailable => mouse::Cursor::Unavailable,
+        }
+    }
+
+    /// The region of the layout that is visible on screen
+    fn layout_viewport(&self, bounds: Rectangle) -> Rectangle {
+        Rectangle::new(bounds.position(), bounds.size() * (1. / self.camera.zoom))
+    }
+
     pub fn on_press(mut self, on_press: impl Fn(Option<ShapeId>) -> Message + 'a) -> Self {
         self.on_click = Some(Box::new(on_press));
         self
@@ -137,6 +271,49 @@ where
         self.on_cursor_move = Some(Box::new(on_move));
         self
     }
+
+    /// Publishes every shape that intersects the box dragged out on the empty canvas
+    pub fn on_select(mut self, on_select: impl Fn(Vec<ShapeId>) -> Message + 'a) -> Self {
+        self.on_select = Some(Box::new(on_select));
+        self
+    }
+
+    /// Published when empty canvas is pressed with the middle button, or the left button
+    /// while holding space, to begin panning
+    pub fn on_pan_press(mut self, on_pan_press: Message) -> Self {
+        self.on_pan_press = Some(on_pan_press);
+        self
+    }
+
+    pub fn style(mut self, style: impl Fn(&Theme) -> Style + 'a) -> Self
+    where
+        Theme::Class<'a>: From<StyleFn<'a, Theme>>,
+    {
+        self.class = (Box::new(style) as StyleFn<'a, Theme>).into();
+        self
+    }
+}
+
+/// Input state that the workspace tracks between events
+#[derive(Default)]
+struct Interaction {
+    size: Option<Size>,
+    modifiers: Modifiers,
+    /// Whether space is held, which turns left drags of empty canvas into panning
+    space: bool,
+    /// Active touch points, used for pinch to zoom
+    fingers: HashMap<Finger, iced::Point>,
+    /// Start and end corners of the selection box, on screen
+    marquee: Option<(iced::Point, iced::Point)>,
+}
+
+impl Interaction {
+    fn finger_distance(&self) -> Option<f32> {
+        match self.fingers.values().collect::<Vec<_>>()[..] {
+            [a, b] => Some(a.distance(*b)),
+            _ => None,
+        }
+    }
 }
 
 /// Implement Widet
@@ -147,6 +324,14 @@ where
     Theme: Catalog,
     Renderer: iced::advanced::graphics::geometry::Renderer,
 {
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<Interaction>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(Interaction::default())
+    }
+
     fn diff(&self, tree: &mut widget::Tree) {
         tree.diff_children(
             &self
@@ -213,8 +398,47 @@ where
 
         ////// Draw saved curves
         let mut frame = renderer.new_frame(bounds.size());
+        frame.scale(self.camera.zoom);
         frame.translate((self.camera.position * -1.0).into());
 
+        if let Some(spacing) = self.grid {
+            // Double the spacing until grid lines aren't too crowded on screen
+            let doublings = (MIN_GRID_SPACING / (spacing * self.camera.zoom))
+                .log2()
+                .ceil()
+                .max(0.);
+            let spacing = spacing * 2f32.powi(doublings as i32);
+            let min = self.camera.position;
+            let max = min + Vector::new(bounds.width, bounds.height) * (1. / self.camera.zoom);
+            let grid = Path::new(|builder| {
+                let mut x = (min.x / spacing).floor() * spacing;
+                while x < max.x {
+                    builder.move_to(iced::Point::new(x, min.y));
+                    builder.line_to(iced::Point::new(x, max.y));
+                    x += spacing;
+                }
+                let mut y = (min.y / spacing).floor() * spacing;
+                while y < max.y {
+                    builder.move_to(iced::Point::new(min.x, y));
+                    builder.line_to(iced::Point::new(max.x, y));
+                    y += spacing;
+                }
+            });
+            frame.stroke(
+                &grid,
+                Stroke::default()
+                    .with_width(1. / self.camera.zoom)
+                    .with_color(theme.style(&self.class).foreground.scale_alpha(0.08)),
+            );
+        }
+
+        let highlight = theme.style(&self.class).foreground;
+        self.annotations
+            .iter()
+            .for_each(|(_, annotation, selected)| {
+                annotation.draw(&mut frame, *selected, highlight)
+            });
+
         self.connections
             .iter()
             .for_each(|(p, s)| frame.stroke(p, *s));
@@ -226,7 +450,8 @@ where
         });
         //// Draw Elements
         {
-            //TODO: apply zoom transform
+            let cursor = self.layout_cursor(bounds, cursor);
+            let viewport = self.layout_viewport(bounds);
             //// Render Children in a layer that is bounded to the size of the workspace
             let elements: Vec<_> = self
                 .shapes
@@ -238,13 +463,40 @@ where
 
             for ((shape, tree), c_layout) in elements.into_iter().rev() {
                 renderer.with_layer(workspace_layout.bounds(), |renderer| {
-                    shape
-                        .state
-                        .as_widget()
-                        .draw(tree, renderer, theme, style, c_layout, cursor, &bounds);
+                    renderer.with_transformation(self.transformation(bounds), |renderer| {
+                        shape
+                            .state
+                            .as_widget()
+                            .draw(tree, renderer, theme, style, c_layout, cursor, &viewport);
+                    });
                 });
             }
         }
+
+        //// Draw selection box
+        if let Some((start, end)) = tree.state.downcast_ref::<Interaction>().marquee {
+            let workspace_style = theme.style(&self.class);
+            let mut frame = renderer.new_frame(bounds.size());
+            let rect = Path::rectangle(
+                iced::Point::new(start.x.min(end.x) - bounds.x, start.y.min(end.y) - bounds.y),
+                Size::new((end.x - start.x).abs(), (end.y - start.y).abs()),
+            );
+            frame.fill(
+                &rect,
+                Fill::from(workspace_style.foreground.scale_alpha(0.15)),
+            );
+            frame.stroke(
+                &rect,
+                Stroke::default()
+                    .with_width(1.0)
+                    .with_color(workspace_style.foreground),
+            );
+            renderer.with_layer(bounds, |renderer| {
+                renderer.with_translation(workspace_offset.into(), |renderer| {
+                    renderer.draw_geometry(frame.into_geometry())
+                });
+            });
+        }
     }
 
     //// Move children based on input events
@@ -257,13 +509,62 @@ where
         renderer: &Renderer,
         clipboard: &mut dyn Clipboard,
         shell: &mut Shell<'_, Message>,
-        viewport: &Rectangle,
+        _viewport: &Rectangle,
     ) -> event::Status {
         let event_status = event::Status::Ignored;
 
         let bounds = layout.bounds();
         let workspace_offset = Vector::new(bounds.position().x, bounds.position().y);
 
+        //// Track input state
+        let interaction = tree.state.downcast_mut::<Interaction>();
+        if interaction.size != Some(bounds.size()) {
+            interaction.size = Some(bounds.size());
+            if let Some(on_resize) = &self.on_resize {
+                shell.publish(on_resize(bounds.size()));
+            }
+        }
+        match &event {
+            Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                interaction.modifiers = *modifiers;
+            }
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key: keyboard::Key::Named(keyboard::key::Named::Space),
+                ..
+            }) => interaction.space = true,
+            Event::Keyboard(keyboard::Event::KeyReleased {
+                key: keyboard::Key::Named(keyboard::key::Named::Space),
+                ..
+            }) => interaction.space = false,
+            Event::Touch(FingerPressed { id, position }) if bounds.contains(*position) => {
+                interaction.fingers.insert(*id, *position);
+            }
+            Event::Touch(FingerMoved { id, position }) => {
+                let before = interaction.finger_distance();
+                if let Some(finger) = interaction.fingers.get_mut(id) {
+                    *finger = *position;
+                }
+                if let (Some(before), Some(after), Some(zoom)) =
+                    (before, interaction.finger_distance(), &self.zoom)
+                {
+                    if before > 0. {
+                        shell.publish(zoom(after / before));
+                        return event::Status::Captured;
+                    }
+                }
+            }
+            Event::Touch(FingerLifted { id, .. }) | Event::Touch(FingerLost { id, .. }) => {
+                interaction.fingers.remove(id);
+            }
+            _ => {}
+        }
+        let modifiers = interaction.modifiers;
+        let space = interaction.space;
+
+        //// Children are laid out without zoom applied
+        let child_cursor = self.layout_cursor(bounds, cursor);
+        let child_viewport = self.layout_viewport(bounds);
+
         //// Pass event down to children
         let event_status = self
             .shapes
@@ -276,11 +577,11 @@ where
                     tree,
                     event.clone(),
                     layout,
-                    cursor,
+                    child_cursor,
                     renderer,
                     clipboard,
                     shell,
-                    viewport,
+                    &child_viewport,
                 )
             })
             .fold(event_status, event::Status::merge);
@@ -292,8 +593,9 @@ where
                 | Event::Touch(FingerPressed { .. }) => {
                     //TODO: collapse this logic down more compactly
                     //// Find the first coliding shape
-                    if let Some((id, _shape_pos)) =
-                        self.shapes.find_shape(Point::from(cursor_position), layout)
+                    if let Some((id, _shape_pos)) = self
+                        .shapes
+                        .find_shape(Point::from(self.to_layout(bounds, cursor_position)), layout)
                     {
                         //// Publish event
                         if let Some(on_shape_click) = &self.on_click {
@@ -301,11 +603,37 @@ where
                         }
                         //// Capture event
                         event::Status::Captured
+                    } else if let Some((id, part)) = bounds
+                        .contains(cursor_position)
+                        .then(|| {
+                            let world = self
+                                .camera
+                                .to_world(Point::from(cursor_position) - workspace_offset);
+                            self.annotations
+                                .iter()
+                                .rev()
+                                .find_map(|(id, annotation, _)| Some((*id, annotation.hit(world)?)))
+                        })
+                        .flatten()
+                    {
+                        //// Pressed an annotation
+                        if let Some(on_annotation_press) = &self.on_annotation_press {
+                            shell.publish(on_annotation_press(id, part));
+                        }
+                        event::Status::Captured
                     } else {
-                        //// Deselect
+                        //// Pan while holding space, otherwise deselect and start a selection box
                         if bounds.contains(cursor_position) {
-                            if let Some(on_shape_click) = &self.on_click {
-                                shell.publish(on_shape_click(None));
+                            if space {
+                                if let Some(on_pan_press) = &self.on_pan_press {
+                                    shell.publish(on_pan_press.clone());
+                                }
+                            } else {
+                                if let Some(on_shape_click) = &self.on_click {
+                                    shell.publish(on_shape_click(None));
+                                }
+                                tree.state.downcast_mut::<Interaction>().marquee =
+                                    Some((cursor_position, cursor_position));
                             }
                             event::Status::Captured
                         } else {
@@ -313,9 +641,31 @@ where
                         }
                     }
                 }
+                Event::Mouse(ButtonPressed(mouse::Button::Middle)) => {
+                    match (&self.on_pan_press, bounds.contains(cursor_position)) {
+                        (Some(on_pan_press), true) => {
+                            shell.publish(on_pan_press.clone());
+                            event::Status::Captured
+                        }
+                        _ => event::Status::Ignored,
+                    }
+                }
                 Event::Mouse(ButtonReleased(mouse::Button::Left))
+                | Event::Mouse(ButtonReleased(mouse::Button::Middle))
                 | Event::Touch(FingerLifted { .. })
                 | Event::Touch(FingerLost { .. }) => {
+                    //// Finish the selection box, ignoring boxes too small to be intentional
+                    if let Some((start, end)) =
+                        tree.state.downcast_mut::<Interaction>().marquee.take()
+                    {
+                        if let (Some(on_select), true) = (
+                            &self.on_select,
+                            (end.x - start.x).abs().max((end.y - start.y).abs()) > 3.,
+                        ) {
+                            shell
+                                .publish(on_select(self.shapes_in_box(bounds, start, end, layout)));
+                        }
+                    }
                     //// Publish event
                     if let Some(on_shape_release) = &self.on_shape_release {
                         shell.publish(on_shape_release.clone());
@@ -324,11 +674,27 @@ where
                     event::Status::Captured
                 }
                 Event::Mouse(CursorMoved { .. }) | Event::Touch(FingerMoved { .. }) => {
+                    if let Some((_, end)) = &mut tree.state.downcast_mut::<Interaction>().marquee {
+                        *end = cursor_position;
+                    }
                     if let Some(on_move) = &self.on_cursor_move {
                         shell.publish(on_move(Point::from(cursor_position) - workspace_offset));
                     }
                     event::Status::Ignored
                 }
+                Event::Mouse(WheelScrolled { delta }) if modifiers.command() => {
+                    match (&self.zoom, bounds.contains(cursor_position)) {
+                        (Some(zoom), true) => {
+                            let factor = match delta {
+                                ScrollDelta::Lines { y, .. } => 1.1_f32.powf(y),
+                                ScrollDelta::Pixels { y, .. } => (y * 0.005).exp(),
+                            };
+                            shell.publish(zoom(factor));
+                            event::Status::Captured
+                        }
+                        _ => event::Status::Ignored,
+                    }
+                }
                 Event::Mouse(WheelScrolled { delta }) => {
                     if let Some(pan) = &self.pan {
                         if bounds.contains(cursor_position) {
@@ -361,16 +727,22 @@ where
         viewport: &Rectangle,
         renderer: &Renderer,
     ) -> mouse::Interaction {
+        let bounds = layout.bounds();
+        let cursor = self.layout_cursor(bounds, cursor);
+        let viewport = self.layout_viewport(bounds).intersection(viewport);
         self.shapes
             .0
             .values()
             .zip(&tree.children)
             .zip(layout.children())
             .map(|((shape, state), layout)| {
-                shape
-                    .state
-                    .as_widget()
-                    .mouse_interaction(state, layout, cursor, viewport, renderer)
+                shape.state.as_widget().mouse_interaction(
+                    state,
+                    layout,
+                    cursor,
+                    &viewport.unwrap_or_default(),
+                    renderer,
+                )
             })
             .max()
             .unwrap_or_default()
@@ -383,6 +755,8 @@ where
         renderer: &Renderer,
         translation: iced::Vector,
     ) -> Option<overlay::Element<'b, Message, Theme, Renderer>> {
+        let origin = layout.bounds().position();
+        let zoom = self.camera.zoom;
         let child_overlays: Vec<_> = self
             .shapes
             .0
@@ -390,9 +764,12 @@ where
             .zip(layout.children())
             .zip(tree.children.iter_mut())
             .filter_map(|((e, layout), tree)| {
+                // Overlays are not zoomed, so shift them to follow the zoomed
+                // position of the shape that they belong to
+                let offset = (layout.position() - origin) * (zoom - 1.);
                 e.state
                     .as_widget_mut()
-                    .overlay(tree, layout, renderer, translation)
+                    .overlay(tree, layout, renderer, translation + offset)
             })
             .collect();
         if child_overlays.is_empty() {
@@ -473,3 +850,36 @@ pub fn default(theme: &Theme) -> Style {
         foreground,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn zoom_keeps_anchor_fixed() {
+        let mut camera = Camera {
+            position: [10., 20.].into(),
+            zoom: 1.0,
+        };
+        let anchor = Point::new(100., 50.);
+        let before = camera.to_world(anchor);
+        camera.zoom_about(2.0, anchor);
+        assert_eq!(camera.zoom, 2.0);
+        assert_eq!(camera.to_world(anchor), before);
+        camera.zoom_about(100.0, anchor);
+        assert_eq!(camera.zoom, MAX_ZOOM);
+    }
+
+    #[test]
+    fn fit() {
+        let mut camera = Camera::default();
+        let viewport = Size::new(840., 440.);
+        camera.fit(Point::new(0., 0.), Point::new(1600., 200.), viewport);
+        assert_eq!(camera.zoom, 0.5);
+        // The center of the fitted region is in the center of the viewport
+        assert_eq!(
+            camera.to_world(Point::new(420., 220.)),
+            Point::new(800., 100.)
+        );
+    }
+}